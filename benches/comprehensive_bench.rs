@@ -17,6 +17,7 @@ fn create_symbol_spec() -> CoreSymbolSpecification {
         maker_fee: 0,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     }
 }
 
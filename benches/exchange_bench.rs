@@ -1,4 +1,4 @@
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
 use matching_core::api::*;
 use matching_core::core::orderbook::{DirectOrderBook, NaiveOrderBook, OrderBook};
 use matching_core::core::processors::risk_engine::RiskEngine;
@@ -69,13 +69,7 @@ fn bench_risk_engine(c: &mut Criterion) {
         uid: 1,
         ..Default::default()
     });
-    engine.pre_process(&mut OrderCommand {
-        command: OrderCommandType::BalanceAdjustment,
-        uid: 1,
-        symbol: 1,
-        price: 1_000_000,
-        ..Default::default()
-    });
+    engine.pre_process(&mut OrderCommand::balance_adjustment(1, 1, 1_000_000, 1));
 
     c.bench_function("RiskEngine_pre_process_PlaceOrder_Ask", |b| {
         b.iter(|| {
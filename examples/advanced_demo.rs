@@ -16,6 +16,7 @@ fn main() {
         maker_fee: 0,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     };
 
     let mut book = AdvancedOrderBook::new(spot_spec);
@@ -138,6 +139,7 @@ fn main() {
         maker_fee: 0,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     };
     let mut perp_book = AdvancedOrderBook::new(perp_spec);
     
@@ -183,6 +185,7 @@ fn main() {
         maker_fee: 0,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     };
     let mut option_book = AdvancedOrderBook::new(call_spec);
     
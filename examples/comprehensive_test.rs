@@ -14,6 +14,7 @@ fn create_symbol_spec() -> CoreSymbolSpecification {
         maker_fee: 0,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     }
 }
 
@@ -90,7 +91,7 @@ fn test_advanced_order_types() {
     };
     book.new_order(&mut iceberg);
     let l2 = book.get_l2_data(5);
-    assert!(l2.bid_volumes.iter().any(|&v| v == 10));
+    assert!(l2.bid_volumes.contains(&10));
     
     // FOK
     let mut fok = create_order(4, 4, 10000, 20, OrderAction::Bid, OrderType::Fok);
@@ -140,6 +141,7 @@ fn test_multiple_symbol_types() {
             maker_fee: 0,
             margin_buy: 0,
             margin_sell: 0,
+            ..Default::default()
         };
         
         let mut book = AdvancedOrderBook::new(spec);
@@ -176,7 +178,7 @@ fn test_performance_comparison() {
     let start = Instant::now();
     let mut optimized = DirectOrderBookOptimized::new(create_symbol_spec());
     for i in 0..num_orders {
-        let mut cmd = create_order(1, i + num_orders as u64, 10000 + (i % 100) as i64, 10,
+        let mut cmd = create_order(1, i + num_orders, 10000 + (i % 100) as i64, 10,
             if i % 2 == 0 { OrderAction::Ask } else { OrderAction::Bid },
             OrderType::Gtc);
         optimized.new_order(&mut cmd);
@@ -187,7 +189,7 @@ fn test_performance_comparison() {
     let start = Instant::now();
     let mut naive = NaiveOrderBook::new(create_symbol_spec());
     for i in 0..num_orders {
-        let mut cmd = create_order(1, i + (num_orders * 2) as u64, 10000 + (i % 100) as i64, 10,
+        let mut cmd = create_order(1, i + num_orders * 2, 10000 + (i % 100) as i64, 10,
             if i % 2 == 0 { OrderAction::Ask } else { OrderAction::Bid },
             OrderType::Gtc);
         naive.new_order(&mut cmd);
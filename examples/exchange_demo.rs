@@ -22,6 +22,7 @@ fn main() {
         maker_fee: 5,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     });
 
     // Thêm người dùng
@@ -37,22 +38,8 @@ fn main() {
     });
 
     // Nạp tiền
-    core.submit_command(OrderCommand {
-        command: OrderCommandType::BalanceAdjustment,
-        uid: 1001,
-        symbol: 1,
-        price: 100000,
-        order_id: 1,
-        ..Default::default()
-    });
-    core.submit_command(OrderCommand {
-        command: OrderCommandType::BalanceAdjustment,
-        uid: 1002,
-        symbol: 2,
-        price: 10000,
-        order_id: 2,
-        ..Default::default()
-    });
+    core.submit_command(OrderCommand::balance_adjustment(1001, 1, 100000, 1));
+    core.submit_command(OrderCommand::balance_adjustment(1002, 2, 10000, 2));
 
     println!("\n=== Test quy trình khớp lệnh đầy đủ ===\n");
 
@@ -219,13 +206,7 @@ fn main() {
         uid: 2001,
         ..Default::default()
     });
-    core_wal.submit_command(OrderCommand {
-        command: OrderCommandType::BalanceAdjustment,
-        uid: 2001,
-        symbol: 1,
-        price: 500000,
-        ..Default::default()
-    });
+    core_wal.submit_command(OrderCommand::balance_adjustment(2001, 1, 500000, 1));
 
     println!("Khôi phục từ nhật ký sang core mới...");
     let mut core_recovered = ExchangeCore::new(ExchangeConfig::default());
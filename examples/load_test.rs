@@ -1,12 +1,11 @@
 use matching_core::api::*;
 use matching_core::core::exchange::{ExchangeCore, ExchangeConfig, ProducerType, WaitStrategyType};
-use std::time::{Duration, Instant};
+use std::time::Instant;
 use std::sync::Arc;
 
 /// Cấu hình test tải
 struct LoadTestConfig {
     num_orders: usize,
-    batch_size: usize,
 }
 
 fn main() {
@@ -14,7 +13,6 @@ fn main() {
 
     let config = LoadTestConfig {
         num_orders: 1_000_000, // 1 triệu lệnh
-        batch_size: 1000,
     };
 
     println!("Quy mô test: {} lệnh", config.num_orders);
@@ -33,6 +31,7 @@ fn run_load_test(name: &str, config: &LoadTestConfig) {
         risk_engines_num: 1,
         producer_type: ProducerType::Single,
         wait_strategy: WaitStrategyType::BusySpin,
+        ..Default::default()
     };
     
     let mut core = ExchangeCore::new(exchange_config);
@@ -71,20 +70,8 @@ fn run_load_test(name: &str, config: &LoadTestConfig) {
             uid,
             ..Default::default()
         });
-        core.submit_command(OrderCommand {
-            command: OrderCommandType::BalanceAdjustment,
-            uid,
-            symbol: 2,
-            price: 10_000_000,
-            ..Default::default()
-        });
-        core.submit_command(OrderCommand {
-            command: OrderCommandType::BalanceAdjustment,
-            uid,
-            symbol: 1,
-            price: 10_000_000,
-            ..Default::default()
-        });
+        core.submit_command(OrderCommand::balance_adjustment(uid, 2, 10_000_000, 1));
+        core.submit_command(OrderCommand::balance_adjustment(uid, 1, 10_000_000, 2));
     }
 
     // Giai đoạn làm nóng
@@ -104,7 +91,7 @@ fn run_load_test(name: &str, config: &LoadTestConfig) {
     
     // Chờ tất cả tin nhắn bất đồng bộ được xử lý xong (init + warmup + num_orders)
     // Lưu ý: init bao gồm 3*init_user_count
-    let expected = 3 * (init_user_count as usize) + (warmup_count as usize) + (config.num_orders as usize);
+    let expected = 3 * (init_user_count as usize) + (warmup_count as usize) + config.num_orders;
     while processed_count.load(Ordering::Acquire) < expected {
         std::hint::spin_loop();
     }
@@ -122,7 +109,7 @@ fn run_load_test(name: &str, config: &LoadTestConfig) {
 #[inline(always)]
 fn simulate_order(core: &mut ExchangeCore, i: u64) {
     let uid = (i % 10000) + 1;
-    let action = if i % 2 == 0 { OrderAction::Bid } else { OrderAction::Ask };
+    let action = if i.is_multiple_of(2) { OrderAction::Bid } else { OrderAction::Ask };
     
     // Mô phỏng mua bán thực tế luân phiên, ID lệnh tăng dần
     core.submit_command(OrderCommand {
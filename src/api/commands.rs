@@ -24,6 +24,28 @@ pub enum OrderCommandType {
     PersistStateRisk,
     GroupingControl,
     ShutdownSignal,
+    UpdateMarkPrice,
+    ExpireOption,       // Quyết toán quyền chọn đáo hạn theo giá quyết toán (price)
+    SettleFutures,      // Quyết toán cuối cùng hợp đồng tương lai theo giá quyết toán (price)
+    Liquidate,          // Nội bộ: đóng vị thế của người dùng thiếu ký quỹ
+    InsuranceCover,     // Nội bộ: quỹ bảo hiểm bù phần thâm hụt
+    SetReferencePrice,  // Đặt giá tham chiếu của dải giá động (price) khi chưa có giao dịch
+    SetSessionState,    // Quản trị: chuyển trạng thái phiên giao dịch của công cụ (session_state)
+    AmendOrder,         // Sửa lệnh đang treo: giá (price), khối lượng còn lại (size), phần hiển thị (visible_size)
+    MassCancel,         // Hủy mọi lệnh đang treo của người dùng trên mọi phân đoạn (lọc theo mass_cancel_symbol / mass_cancel_action)
+    Heartbeat,          // Phiên kết nối của người dùng còn hoạt động: gia hạn / đăng ký hạn heartbeat (heartbeat_timeout)
+    KillSwitch,         // Quản trị: khóa người dùng (như SuspendUser) và hủy mọi lệnh đang treo
+    MassQuote,          // Thay báo giá hai chiều trên một hoặc nhiều công cụ (quotes) trong một lệnh
+    SetFeeTier,         // Quản trị: định nghĩa (fee_tier) / xóa (None) cấp phí fee_tier_id
+    SetUserFeeTier,     // Quản trị: gán cấp phí fee_tier_id cho người dùng (None = phí của công cụ)
+    SetFeeOverride,     // Quản trị: đặt (fee_override) / xóa (None) phí riêng của người dùng trên công cụ
+}
+
+impl OrderCommandType {
+    /// Lệnh chỉ được sinh bên trong pipeline, không chấp nhận từ bên ngoài
+    pub fn is_internal(self) -> bool {
+        matches!(self, OrderCommandType::Liquidate | OrderCommandType::InsuranceCover)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
@@ -48,8 +70,33 @@ pub struct OrderCommand {
     
     // Các trường mở rộng
     pub stop_price: Option<Price>,      // Giá kích hoạt cắt lỗ
+    pub trailing_offset: Option<TrailingOffset>, // Cắt lỗ động: giá kích hoạt dịch theo giá khớp
     pub visible_size: Option<Size>,     // Số lượng hiển thị của lệnh iceberg
+    pub visible_variance: Option<Size>, // Biên độ ngẫu nhiên (±) của phần hiển thị khi làm mới iceberg
+    pub hidden: bool,                   // Lệnh ẩn hoàn toàn: không hiển thị trên L2, ưu tiên sau lệnh hiển thị cùng giá
+    pub min_quantity: Option<Size>,     // Khối lượng tối thiểu của mỗi lần khớp (bằng size = all-or-none)
+    pub post_only_reprice: bool,        // Post-Only giao với phía đối ứng: dời giá vào trong một bước giá thay vì từ chối
+    pub peg: Option<Peg>,               // Lệnh neo giá: giá tính lại khi giá tốt nhất của sổ lệnh thay đổi
     pub expire_time: Option<i64>,       // Thời gian hết hạn (GTD)
+    pub group_id: Option<u64>,          // Nhóm lệnh liên kết: khớp hoặc hủy một lệnh sẽ hủy các lệnh còn lại (OCO)
+    pub parent_order_id: Option<OrderId>, // Lệnh con của bracket: chỉ kích hoạt khi lệnh vào khớp đủ
+    pub index_price: Option<Price>,     // Giá chỉ số (cập nhật giá đánh dấu)
+    pub funding_rate: Option<i64>,      // Tỷ lệ funding (theo FUNDING_RATE_SCALE)
+    pub session_state: Option<TradingSessionState>, // Trạng thái phiên mới (SetSessionState)
+    pub hold_increase: Size,            // Sửa lệnh: số lot engine rủi ro đã giữ thêm (R1), hoàn lại nếu sửa lệnh thất bại
+    pub mass_cancel_symbol: Option<SymbolId>, // Hủy hàng loạt: chỉ hủy lệnh trên công cụ này (None = mọi công cụ)
+    pub mass_cancel_action: Option<OrderAction>, // Hủy hàng loạt: chỉ hủy lệnh phía này (None = cả hai phía)
+    pub session_id: SessionId,          // Phiên kết nối gửi lệnh: lệnh đặt gắn với phiên, heartbeat đăng ký / gia hạn phiên này
+    pub heartbeat_timeout: Option<i64>, // Heartbeat: thời hạn tới heartbeat kế tiếp (<= 0 = hủy đăng ký, None = giữ thời hạn cũ)
+    pub quotes: Vec<Quote>,             // Báo giá hai chiều (MassQuote), mỗi công cụ một báo giá
+    pub fee_tier_id: Option<u32>,       // Cấp phí (SetFeeTier, SetUserFeeTier)
+    pub fee_tier: Option<FeeTier>,      // Tỷ lệ phí của cấp phí (SetFeeTier)
+    pub fee_override: Option<FeeOverride>, // Phí riêng theo lot (SetFeeOverride)
+    
+    // Điều chỉnh số dư (nạp/rút)
+    pub currency: Currency,
+    pub amount: i64,                    // Dương = nạp, âm = rút
+    pub transaction_id: u64,            // Mã giao dịch nạp/rút, lệnh trùng mã bị từ chối
     
     // Danh sách sự kiện khớp lệnh (dung lượng được cấp phát trước)
     pub matcher_events: Vec<MatcherTradeEvent>,
@@ -72,9 +119,103 @@ impl Default for OrderCommand {
             events_group: 0,
             service_flags: 0,
             stop_price: None,
+            trailing_offset: None,
             visible_size: None,
+            visible_variance: None,
+            hidden: false,
+            min_quantity: None,
+            post_only_reprice: false,
+            peg: None,
             expire_time: None,
+            group_id: None,
+            parent_order_id: None,
+            index_price: None,
+            funding_rate: None,
+            session_state: None,
+            hold_increase: 0,
+            mass_cancel_symbol: None,
+            mass_cancel_action: None,
+            session_id: 0,
+            heartbeat_timeout: None,
+            quotes: Vec::new(),
+            fee_tier_id: None,
+            fee_tier: None,
+            fee_override: None,
+            currency: 0,
+            amount: 0,
+            transaction_id: 0,
             matcher_events: Vec::with_capacity(4), // Cấp phát trước dung lượng cho 4 sự kiện
         }
     }
 }
+
+impl OrderCommand {
+    /// Lệnh hủy mọi lệnh đang treo của người dùng (không lọc theo công cụ / phía)
+    pub fn mass_cancel(uid: UserId) -> Self {
+        Self {
+            command: OrderCommandType::MassCancel,
+            uid,
+            ..Default::default()
+        }
+    }
+
+    /// Lệnh đặt GTC tương ứng với một phía của báo giá hai chiều
+    pub fn quote_order(uid: UserId, timestamp: i64, symbol: SymbolId, action: OrderAction, side: &QuoteSide) -> Self {
+        Self {
+            command: OrderCommandType::PlaceOrder,
+            uid,
+            order_id: side.order_id,
+            symbol,
+            price: side.price,
+            reserve_price: side.price,
+            size: side.size,
+            action,
+            order_type: OrderType::Gtc,
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    /// Lệnh định nghĩa (Some) / xóa (None) cấp phí
+    pub fn set_fee_tier(tier_id: u32, tier: Option<FeeTier>) -> Self {
+        Self {
+            command: OrderCommandType::SetFeeTier,
+            fee_tier_id: Some(tier_id),
+            fee_tier: tier,
+            ..Default::default()
+        }
+    }
+
+    /// Lệnh gán cấp phí cho người dùng (None = phí của công cụ)
+    pub fn set_user_fee_tier(uid: UserId, tier_id: Option<u32>) -> Self {
+        Self {
+            command: OrderCommandType::SetUserFeeTier,
+            uid,
+            fee_tier_id: tier_id,
+            ..Default::default()
+        }
+    }
+
+    /// Lệnh đặt (Some) / xóa (None) phí riêng của người dùng trên công cụ
+    pub fn set_fee_override(uid: UserId, symbol: SymbolId, fee: Option<FeeOverride>) -> Self {
+        Self {
+            command: OrderCommandType::SetFeeOverride,
+            uid,
+            symbol,
+            fee_override: fee,
+            ..Default::default()
+        }
+    }
+
+    /// Lệnh nạp (amount > 0) / rút (amount < 0) tiền, định danh bằng mã giao dịch
+    pub fn balance_adjustment(uid: UserId, currency: Currency, amount: i64, transaction_id: u64) -> Self {
+        Self {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            currency,
+            amount,
+            transaction_id,
+            ..Default::default()
+        }
+    }
+}
@@ -10,6 +10,17 @@ pub enum MatcherEventType {
     Trade,      // Khớp lệnh
     Reject,     // Từ chối
     Reduce,     // Giảm
+    Funding,    // Thanh toán funding
+    Settlement, // Quyết toán khi đáo hạn
+    Halt,       // Ngắt mạch: tạm dừng khớp lệnh trên công cụ
+    SessionChange(TradingSessionState), // Chuyển trạng thái phiên giao dịch (trạng thái mới)
+    AuctionTrade, // Khớp đấu giá giữa hai lệnh đang treo tại giá cân bằng
+    IcebergRefresh, // Lệnh iceberg hết phần hiển thị, làm mới và xuống cuối hàng đợi
+    GroupCancel(OrderAction), // Hủy lệnh cùng nhóm liên kết (phía của lệnh bị hủy)
+    PostOnlyReprice, // Lệnh Post-Only giao với phía đối ứng được dời giá thay vì bị từ chối
+    MassCancel(OrderAction), // Hủy hàng loạt: lệnh đang treo bị hủy (phía của lệnh bị hủy)
+    TriggeredTrade(OrderAction), // Khớp của lệnh được kích hoạt trong sổ lệnh (phía của lệnh chủ động)
+    InsuranceShortfall, // Quỹ bảo hiểm không đủ bù thâm hụt: phần còn lại là lỗ phải phân bổ (socialized loss)
 }
 
 /// Sự kiện khớp lệnh
@@ -23,6 +34,14 @@ pub struct MatcherTradeEvent {
     pub matched_order_id: OrderId,
     pub matched_order_uid: UserId,
     pub bidder_hold_price: Price, // Giá dự trữ của lệnh mua
+    pub amount: i64,              // Số tiền thanh toán (funding/quyết toán)
+    pub taker_fee: i64,           // Tổng phí taker của giao dịch (âm = hoàn phí)
+    pub maker_fee: i64,           // Tổng phí maker của giao dịch (âm = hoàn phí)
+    // Khớp đấu giá: lệnh mua (matched_order_* là lệnh bán)
+    pub bid_order_id: OrderId,
+    pub bid_uid: UserId,
+    pub group_id: u64, // Nhóm lệnh liên kết (OCO / bracket) của lệnh đang treo, 0 = không thuộc nhóm
+    pub symbol: SymbolId, // Công cụ của lệnh bị hủy hàng loạt (lệnh hủy hàng loạt không gắn với một công cụ)
 }
 
 impl Default for MatcherTradeEvent {
@@ -34,6 +53,13 @@ impl Default for MatcherTradeEvent {
             matched_order_id: 0,
             matched_order_uid: 0,
             bidder_hold_price: 0,
+            amount: 0,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id: 0,
+            symbol: 0,
         }
     }
 }
@@ -53,6 +79,21 @@ impl MatcherTradeEvent {
             matched_order_id,
             matched_order_uid,
             bidder_hold_price,
+            amount: 0,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id: 0,
+            symbol: 0,
+        }
+    }
+
+    /// Sự kiện giảm khối lượng lệnh đang treo (sửa lệnh): size là phần giảm được hoàn khoản giữ
+    pub fn new_reduce(size: Size, price: Price) -> Self {
+        Self {
+            event_type: MatcherEventType::Reduce,
+            ..Self::new_reject(size, price)
         }
     }
 
@@ -64,6 +105,228 @@ impl MatcherTradeEvent {
             matched_order_id: 0,
             matched_order_uid: 0,
             bidder_hold_price: 0,
+            amount: 0,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id: 0,
+            symbol: 0,
+        }
+    }
+
+    /// Sự kiện thanh toán funding: size là vị thế ròng (dương = long), amount dương = nhận
+    pub fn new_funding(uid: UserId, net_volume: Size, mark_price: Price, amount: i64) -> Self {
+        Self {
+            event_type: MatcherEventType::Funding,
+            size: net_volume,
+            price: mark_price,
+            matched_order_id: 0,
+            matched_order_uid: uid,
+            bidder_hold_price: 0,
+            amount,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id: 0,
+            symbol: 0,
+        }
+    }
+
+    /// Sự kiện quyết toán đáo hạn: size là vị thế ròng được quyết toán, amount là tiền quote (dương = nhận)
+    pub fn new_settlement(uid: UserId, net_volume: Size, settlement_price: Price, amount: i64) -> Self {
+        Self {
+            event_type: MatcherEventType::Settlement,
+            size: net_volume,
+            price: settlement_price,
+            matched_order_id: 0,
+            matched_order_uid: uid,
+            bidder_hold_price: 0,
+            amount,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id: 0,
+            symbol: 0,
+        }
+    }
+
+    /// Sự kiện thiếu hụt quỹ bảo hiểm: matched_order_uid là người dùng bị thâm hụt,
+    /// amount là phần thâm hụt quỹ không bù được
+    pub fn new_insurance_shortfall(uid: UserId, amount: i64) -> Self {
+        Self {
+            event_type: MatcherEventType::InsuranceShortfall,
+            matched_order_uid: uid,
+            amount,
+            ..Self::default()
+        }
+    }
+
+    /// Sự kiện ngắt mạch: price là giá khớp kích hoạt, amount là thời điểm mở lại khớp lệnh
+    pub fn new_halt(trigger_price: Price, halted_until: i64) -> Self {
+        Self {
+            event_type: MatcherEventType::Halt,
+            size: 0,
+            price: trigger_price,
+            matched_order_id: 0,
+            matched_order_uid: 0,
+            bidder_hold_price: 0,
+            amount: halted_until,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id: 0,
+            symbol: 0,
+        }
+    }
+
+    /// Sự kiện chuyển trạng thái phiên: amount là thời điểm chuyển
+    pub fn new_session_change(state: TradingSessionState, timestamp: i64) -> Self {
+        Self {
+            event_type: MatcherEventType::SessionChange(state),
+            size: 0,
+            price: 0,
+            matched_order_id: 0,
+            matched_order_uid: 0,
+            bidder_hold_price: 0,
+            amount: timestamp,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id: 0,
+            symbol: 0,
+        }
+    }
+
+    /// Sự kiện khớp đấu giá: cả hai bên là lệnh đang treo, khớp tại giá cân bằng.
+    /// Phí của bên mua ghi ở taker_fee, bên bán ở maker_fee.
+    pub fn new_auction_trade(
+        size: Size,
+        price: Price,
+        bid_order_id: OrderId,
+        bid_uid: UserId,
+        bidder_hold_price: Price,
+        ask_order_id: OrderId,
+        ask_uid: UserId,
+    ) -> Self {
+        Self {
+            event_type: MatcherEventType::AuctionTrade,
+            size,
+            price,
+            matched_order_id: ask_order_id,
+            matched_order_uid: ask_uid,
+            bidder_hold_price,
+            amount: 0,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id,
+            bid_uid,
+            group_id: 0,
+            symbol: 0,
+        }
+    }
+
+    /// Sự kiện khớp của lệnh được kích hoạt trong sổ lệnh (lệnh con bracket): lệnh chủ động không phải lệnh
+    /// đang xử lý nên hai bên được ghi như khớp đấu giá. Phí của bên mua ghi ở taker_fee, bên bán ở maker_fee.
+    pub fn new_triggered_trade(action: OrderAction, order_id: OrderId, uid: UserId, trade: &MatcherTradeEvent) -> Self {
+        let ((bid_order_id, bid_uid), (ask_order_id, ask_uid)) = match action {
+            OrderAction::Bid => ((order_id, uid), (trade.matched_order_id, trade.matched_order_uid)),
+            OrderAction::Ask => ((trade.matched_order_id, trade.matched_order_uid), (order_id, uid)),
+        };
+        Self {
+            event_type: MatcherEventType::TriggeredTrade(action),
+            group_id: trade.group_id,
+            ..Self::new_auction_trade(trade.size, trade.price, bid_order_id, bid_uid, trade.bidder_hold_price, ask_order_id, ask_uid)
+        }
+    }
+
+    /// Sự kiện làm mới lệnh iceberg: size là phần hiển thị mới, lệnh mất ưu tiên thời gian tại mức giá
+    pub fn new_iceberg_refresh(order_id: OrderId, uid: UserId, price: Price, peak: Size) -> Self {
+        Self {
+            event_type: MatcherEventType::IcebergRefresh,
+            size: peak,
+            price,
+            matched_order_id: order_id,
+            matched_order_uid: uid,
+            bidder_hold_price: 0,
+            amount: 0,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id: 0,
+            symbol: 0,
+        }
+    }
+
+    /// Sự kiện dời giá lệnh Post-Only: price là giá mới của lệnh, size là khối lượng lệnh
+    pub fn new_post_only_reprice(order_id: OrderId, uid: UserId, price: Price, size: Size) -> Self {
+        Self {
+            event_type: MatcherEventType::PostOnlyReprice,
+            size,
+            price,
+            matched_order_id: order_id,
+            matched_order_uid: uid,
+            bidder_hold_price: 0,
+            amount: 0,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id: 0,
+            symbol: 0,
+        }
+    }
+
+    /// Sự kiện hủy hàng loạt: matched_order_* là lệnh bị hủy trên công cụ symbol,
+    /// size là khối lượng còn lại được hoàn khoản giữ
+    pub fn new_mass_cancel(
+        action: OrderAction,
+        size: Size,
+        price: Price,
+        order_id: OrderId,
+        uid: UserId,
+        symbol: SymbolId,
+    ) -> Self {
+        Self {
+            event_type: MatcherEventType::MassCancel(action),
+            size,
+            price,
+            matched_order_id: order_id,
+            matched_order_uid: uid,
+            symbol,
+            ..Self::default()
+        }
+    }
+
+    /// Sự kiện hủy lệnh cùng nhóm khi một lệnh trong nhóm được khớp hoặc bị hủy:
+    /// matched_order_* là lệnh bị hủy, size là khối lượng còn lại được hoàn khoản giữ
+    pub fn new_group_cancel(
+        action: OrderAction,
+        size: Size,
+        price: Price,
+        order_id: OrderId,
+        uid: UserId,
+        group_id: u64,
+    ) -> Self {
+        Self {
+            event_type: MatcherEventType::GroupCancel(action),
+            size,
+            price,
+            matched_order_id: order_id,
+            matched_order_uid: uid,
+            bidder_hold_price: 0,
+            amount: 0,
+            taker_fee: 0,
+            maker_fee: 0,
+            bid_order_id: 0,
+            bid_uid: 0,
+            group_id,
+            symbol: 0,
         }
     }
 }
@@ -19,3 +19,11 @@ impl L2MarketData {
         }
     }
 }
+
+/// Kết quả đấu giá dự kiến tại giá cân bằng
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuctionIndicative {
+    pub price: Price,
+    pub volume: Size,    // Khối lượng khớp được
+    pub imbalance: Size, // Khối lượng mua - bán tại giá cân bằng (dương = dư mua)
+}
@@ -1,3 +1,4 @@
+use super::commands::OrderCommandType;
 use serde::{Deserialize, Serialize};
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 
@@ -7,6 +8,22 @@ pub type SymbolId = i32;
 pub type Currency = i32;
 pub type Price = i64;
 pub type Size = i64;
+pub type SessionId = u64;
+
+/// Hệ số tỷ lệ của tỷ lệ funding (1_000_000 = 100%)
+pub const FUNDING_RATE_SCALE: i64 = 1_000_000;
+
+/// Hệ số tỷ lệ của cấp phí (10_000 = 100% phí của công cụ)
+pub const FEE_RATE_SCALE: i64 = 10_000;
+
+/// Hệ số tỷ lệ phần khớp theo thời gian của thuật toán PriceTimeProRata (10_000 = 100%)
+pub const ALLOCATION_RATE_SCALE: i64 = 10_000;
+
+/// Hệ số tỷ lệ của dải giá động và ngưỡng ngắt mạch (10_000 = 100% giá tham chiếu)
+pub const PRICE_BAND_SCALE: i64 = 10_000;
+
+/// Hệ số tỷ lệ khoảng cách của lệnh cắt lỗ động (10_000 = 100% giá tham chiếu)
+pub const TRAILING_RATE_SCALE: i64 = 10_000;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
 #[archive(check_bytes)]
@@ -42,6 +59,113 @@ pub enum OrderType {
     Gtd(i64),         // Good-Till-Date (timestamp)
 }
 
+/// Khoảng cách giữa giá kích hoạt của lệnh cắt lỗ động và giá khớp tốt nhất đã đạt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum TrailingOffset {
+    Fixed(Price),     // Khoảng cách giá cố định
+    Rate(i64),        // Tỷ lệ theo giá tham chiếu (theo TRAILING_RATE_SCALE)
+}
+
+impl TrailingOffset {
+    /// Giá kích hoạt ứng với giá tham chiếu: cắt lỗ bán nằm dưới, cắt lỗ mua nằm trên
+    pub fn stop_price(self, action: OrderAction, reference: Price) -> Price {
+        let offset = match self {
+            TrailingOffset::Fixed(offset) => offset,
+            TrailingOffset::Rate(rate) => (reference as i128 * rate as i128 / TRAILING_RATE_SCALE as i128) as Price,
+        };
+        match action {
+            OrderAction::Ask => reference.saturating_sub(offset),
+            OrderAction::Bid => reference.saturating_add(offset),
+        }
+    }
+}
+
+/// Giá tham chiếu của lệnh neo giá
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum PegType {
+    Primary,  // Giá tốt nhất cùng phía
+    Market,   // Giá tốt nhất phía đối ứng
+    Midpoint, // Giá giữa của giá mua và giá bán tốt nhất
+}
+
+/// Lệnh neo giá: giá hiệu lực tính lại theo giá tốt nhất của sổ lệnh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Peg {
+    pub peg_type: PegType,
+    pub offset: Price,        // Khoảng cách về phía bị động (lệnh mua thấp hơn, lệnh bán cao hơn giá tham chiếu; âm = chủ động hơn)
+    pub limit: Option<Price>, // Giá giới hạn: lệnh mua không cao hơn, lệnh bán không thấp hơn
+}
+
+impl Peg {
+    /// Giá hiệu lực theo giá mua / bán tốt nhất (None khi thiếu giá tham chiếu).
+    /// Giá giữa khi tổng hai giá lẻ được làm tròn về phía bị động: lệnh mua làm tròn xuống
+    /// (`(bid + ask).div_euclid(2)`), lệnh bán làm tròn lên (`(bid + ask + 1).div_euclid(2)`).
+    /// Vì vậy khi chênh lệch chỉ một đơn vị giá, lệnh mua neo giá giữa nằm ở giá mua tốt nhất
+    /// và lệnh bán ở giá bán tốt nhất: lệnh neo giá giữa không bao giờ tự giao với phía đối ứng.
+    pub fn price(self, action: OrderAction, best_bid: Option<Price>, best_ask: Option<Price>) -> Option<Price> {
+        let reference = match (self.peg_type, action) {
+            (PegType::Primary, OrderAction::Bid) | (PegType::Market, OrderAction::Ask) => best_bid?,
+            (PegType::Primary, OrderAction::Ask) | (PegType::Market, OrderAction::Bid) => best_ask?,
+            (PegType::Midpoint, OrderAction::Bid) => (best_bid? + best_ask?).div_euclid(2),
+            (PegType::Midpoint, OrderAction::Ask) => (best_bid? + best_ask? + 1).div_euclid(2),
+        };
+        Some(match action {
+            OrderAction::Bid => {
+                let price = reference.saturating_sub(self.offset);
+                self.limit.map_or(price, |limit| price.min(limit))
+            }
+            OrderAction::Ask => {
+                let price = reference.saturating_add(self.offset);
+                self.limit.map_or(price, |limit| price.max(limit))
+            }
+        })
+    }
+}
+
+/// Một phía của báo giá: lệnh GTC chỉ treo, không khớp ngay (khối lượng 0 = không báo giá phía này)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct QuoteSide {
+    pub order_id: OrderId,
+    pub price: Price,
+    pub size: Size,
+    pub replaced: Option<OrderId>, // Báo giá cũ cùng phía bị thay thế (engine rủi ro điền ở R1)
+    pub replaced_size: Size,       // Khối lượng còn lại của báo giá cũ bị thay thế (engine rủi ro điền ở R1)
+}
+
+/// Báo giá hai chiều của nhà tạo lập thị trường trên một công cụ
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Quote {
+    pub symbol: SymbolId,
+    pub bid: QuoteSide,
+    pub ask: QuoteSide,
+}
+
+impl Quote {
+    pub fn side(&self, action: OrderAction) -> &QuoteSide {
+        match action {
+            OrderAction::Bid => &self.bid,
+            OrderAction::Ask => &self.ask,
+        }
+    }
+
+    pub fn side_mut(&mut self, action: OrderAction) -> &mut QuoteSide {
+        match action {
+            OrderAction::Bid => &mut self.bid,
+            OrderAction::Ask => &mut self.ask,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
 #[archive(check_bytes)]
 #[archive_attr(derive(Debug))]
@@ -53,6 +177,118 @@ pub enum SymbolType {
     PutOption,             // Quyền chọn bán
 }
 
+impl SymbolType {
+    /// Công cụ giao dịch ký quỹ theo vị thế (hợp đồng tương lai / vĩnh viễn)
+    pub fn is_futures(self) -> bool {
+        matches!(self, SymbolType::FuturesContract | SymbolType::PerpetualSwap)
+    }
+
+    /// Quyền chọn (mua / bán)
+    pub fn is_option(self) -> bool {
+        matches!(self, SymbolType::CallOption | SymbolType::PutOption)
+    }
+}
+
+/// Cách thanh toán quyền chọn khi đáo hạn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum OptionSettlement {
+    #[default]
+    Cash,     // Thanh toán tiền mặt phần giá trị nội tại
+    Physical, // Chuyển giao tài sản cơ sở theo giá thực hiện
+}
+
+/// Thuật toán phân bổ khối lượng giữa các lệnh cùng mức giá
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum MatchingAlgorithm {
+    #[default]
+    Fifo,             // Ưu tiên giá - thời gian
+    ProRata,          // Chia theo tỷ lệ khối lượng còn lại
+    PriceTimeProRata, // Một phần khớp theo thời gian, phần còn lại chia theo tỷ lệ
+}
+
+/// Trạng thái phiên giao dịch của công cụ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum TradingSessionState {
+    PreOpen,        // Trước giờ mở cửa
+    OpeningAuction, // Phiên đấu giá mở cửa
+    #[default]
+    Continuous,     // Khớp lệnh liên tục
+    Halted,         // Tạm dừng giao dịch
+    ClosingAuction, // Phiên đấu giá đóng cửa
+    Closed,         // Đóng cửa
+}
+
+impl TradingSessionState {
+    /// Lệnh được phép trong trạng thái phiên.
+    /// Phiên đấu giá nhận lệnh đặt mới (tích lũy, không khớp) nhưng không cho dời lệnh.
+    /// Lệnh thanh lý khớp liên tục nên chỉ được phép trong phiên liên tục; vị thế thiếu ký quỹ
+    /// được thanh lý lại ở lần cập nhật giá đánh dấu kế tiếp sau khi phiên mở lại.
+    pub fn allows(self, command: OrderCommandType) -> bool {
+        use OrderCommandType::*;
+        match self {
+            TradingSessionState::Continuous => true,
+            TradingSessionState::Closed => matches!(command, CancelOrder | MassCancel),
+            TradingSessionState::OpeningAuction | TradingSessionState::ClosingAuction => {
+                matches!(command, PlaceOrder | CancelOrder | MassCancel | ReduceOrder)
+            }
+            _ => matches!(command, CancelOrder | MassCancel | ReduceOrder),
+        }
+    }
+
+    /// Phiên đấu giá tập trung (mở cửa / đóng cửa)
+    pub fn is_auction(self) -> bool {
+        matches!(self, TradingSessionState::OpeningAuction | TradingSessionState::ClosingAuction)
+    }
+
+    /// Chuyển trạng thái hợp lệ
+    pub fn can_transition_to(self, next: TradingSessionState) -> bool {
+        use TradingSessionState::*;
+        matches!(
+            (self, next),
+            (PreOpen, OpeningAuction | Continuous | Closed)
+                | (OpeningAuction, Continuous | Halted | Closed)
+                | (Continuous, Halted | ClosingAuction | Closed)
+                | (Halted, OpeningAuction | Continuous | Closed)
+                | (ClosingAuction, Closed)
+                | (Closed, PreOpen)
+        )
+    }
+}
+
+/// Cách xử lý lệnh có giá vượt dải giá
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum PriceBandAction {
+    #[default]
+    Reject, // Từ chối lệnh
+    Cap,    // Kéo giá lệnh chủ động (mua trên biên trên / bán dưới biên dưới) về biên của dải
+}
+
+/// Cấp phí: tỷ lệ so với phí taker/maker của công cụ (theo FEE_RATE_SCALE, maker âm = hoàn phí)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct FeeTier {
+    pub taker_fee_rate: i64,
+    pub maker_fee_rate: i64,
+}
+
+/// Phí riêng của người dùng trên một công cụ: số tiền theo lot (âm = hoàn phí)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct FeeOverride {
+    pub taker_fee: i64,
+    pub maker_fee: i64,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
 #[archive(check_bytes)]
 #[archive_attr(derive(Debug))]
@@ -70,6 +306,9 @@ pub enum CommandResultCode {
     RiskInvalidReserveBidPrice,
     RiskAskPriceLowerThanFee,
     RiskMarginTradingDisabled,
+    RiskDuplicateOrderId,
+    RiskNotionalOverflow,
+    TradingHalted,
     
     // Matching
     MatchingInvalidOrderBookId,
@@ -78,6 +317,7 @@ pub enum CommandResultCode {
     MatchingMoveFailedPriceOverRiskLimit,
     MatchingReduceFailedWrongSize,
     MatchingInvalidOrderSize,
+    PostOnlyWouldTake,
     
     // State
     StatePersistRiskEngineFailed,
@@ -85,11 +325,33 @@ pub enum CommandResultCode {
     
     // User
     UserMgmtUserAlreadyExists,
+    UserMgmtBalanceAdjustmentZero,
+    UserMgmtBalanceAdjustmentNsf,
+    UserMgmtBalanceAdjustmentAlreadyApplied,
+    UserMgmtBalanceAdjustmentOverflow,
+    UserMgmtUserSuspended,
+    UserMgmtHeartbeatNotRegistered,
     
     // Other
+    InternalCommandRejected,
     InvalidSymbol,
     UnsupportedSymbolType,
+    SymbolNotExpired,
     BinaryCommandFailed,
+    InvalidFeeTier,
+
+    // Quy tắc giao dịch
+    InvalidPriceTick,
+    InvalidLotSize,
+    OrderSizeBelowMinimum,
+    OrderSizeAboveMaximum,
+    OrderNotionalBelowMinimum,
+    PriceOutsideBand,
+    CircuitBreakerHalted,
+
+    // Phiên giao dịch
+    SessionCommandNotAllowed,
+    InvalidSessionTransition,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
@@ -106,6 +368,32 @@ pub struct CoreSymbolSpecification {
     pub maker_fee: i64,
     pub margin_buy: i64,
     pub margin_sell: i64,
+    pub funding_interval: i64, // Chu kỳ thanh toán funding (0 = tắt)
+    pub maintenance_margin: i64, // Ký quỹ duy trì theo lot (0 = không thanh lý)
+    pub expiry_time: i64,        // Thời điểm đáo hạn, ngừng giao dịch (0 = không đáo hạn)
+    // Quyền chọn
+    pub underlying_symbol: SymbolId,        // Công cụ cơ sở (tham chiếu giá quyết toán)
+    pub strike_price: Price,                // Giá thực hiện
+    pub option_settlement: OptionSettlement,
+    // Quy tắc giao dịch (0 = không áp dụng)
+    pub tick_size: Price,    // Bước giá
+    pub lot_size: Size,      // Bước khối lượng
+    pub min_size: Size,      // Khối lượng tối thiểu
+    pub max_size: Size,      // Khối lượng tối đa
+    pub min_notional: i64,   // Giá trị tối thiểu (giá × khối lượng × quote_scale_k)
+    // Dải giá và ngắt mạch (0 = không áp dụng)
+    pub price_band_min: Price,            // Dải tĩnh: giá thấp nhất
+    pub price_band_max: Price,            // Dải tĩnh: giá cao nhất
+    pub price_band_rate: i64,             // Dải động quanh giá tham chiếu (theo PRICE_BAND_SCALE)
+    pub price_band_action: PriceBandAction,
+    pub circuit_breaker_rate: i64,        // Biến động giá kích hoạt ngắt mạch (theo PRICE_BAND_SCALE)
+    pub circuit_breaker_window: i64,      // Cửa sổ thời gian đo biến động
+    pub circuit_breaker_cooldown: i64,    // Thời gian tạm dừng khớp lệnh khi ngắt mạch
+    // Thuật toán khớp lệnh tại mỗi mức giá
+    pub matching_algorithm: MatchingAlgorithm,
+    pub pro_rata_top_order: bool,         // Lệnh sớm nhất của mức giá được khớp trước khi chia theo tỷ lệ
+    pub pro_rata_min_allocation: Size,    // Phần chia theo tỷ lệ nhỏ hơn mức này bị bỏ (0 = không áp dụng)
+    pub pro_rata_fifo_rate: i64,          // PriceTimeProRata: phần khớp theo thời gian (theo ALLOCATION_RATE_SCALE)
 }
 
 impl Default for CoreSymbolSpecification {
@@ -121,6 +409,65 @@ impl Default for CoreSymbolSpecification {
             maker_fee: 0,
             margin_buy: 0,
             margin_sell: 0,
+            funding_interval: 0,
+            maintenance_margin: 0,
+            expiry_time: 0,
+            underlying_symbol: 0,
+            strike_price: 0,
+            option_settlement: OptionSettlement::Cash,
+            tick_size: 0,
+            lot_size: 0,
+            min_size: 0,
+            max_size: 0,
+            min_notional: 0,
+            price_band_min: 0,
+            price_band_max: 0,
+            price_band_rate: 0,
+            price_band_action: PriceBandAction::Reject,
+            circuit_breaker_rate: 0,
+            circuit_breaker_window: 0,
+            circuit_breaker_cooldown: 0,
+            matching_algorithm: MatchingAlgorithm::Fifo,
+            pro_rata_top_order: false,
+            pro_rata_min_allocation: 0,
+            pro_rata_fifo_rate: 0,
+        }
+    }
+}
+
+impl CoreSymbolSpecification {
+    /// Kiểm tra quy tắc giao dịch của lệnh đặt: khối lượng dương, bước giá, bước khối lượng,
+    /// khối lượng tối thiểu/tối đa và giá trị tối thiểu
+    pub fn validate_order(&self, price: Price, size: Size) -> CommandResultCode {
+        if size <= 0 {
+            return CommandResultCode::MatchingInvalidOrderSize;
+        }
+        let result = self.validate_price(price);
+        if result != CommandResultCode::Success {
+            return result;
+        }
+        if self.lot_size > 0 && size % self.lot_size != 0 {
+            return CommandResultCode::InvalidLotSize;
+        }
+        if self.min_size > 0 && size < self.min_size {
+            return CommandResultCode::OrderSizeBelowMinimum;
+        }
+        if self.max_size > 0 && size > self.max_size {
+            return CommandResultCode::OrderSizeAboveMaximum;
+        }
+        if self.min_notional > 0
+            && (size as i128 * price as i128 * self.quote_scale_k as i128) < self.min_notional as i128
+        {
+            return CommandResultCode::OrderNotionalBelowMinimum;
+        }
+        CommandResultCode::Success
+    }
+
+    /// Kiểm tra bước giá (dùng cả khi dời lệnh)
+    pub fn validate_price(&self, price: Price) -> CommandResultCode {
+        if self.tick_size > 0 && price % self.tick_size != 0 {
+            return CommandResultCode::InvalidPriceTick;
         }
+        CommandResultCode::Success
     }
 }
@@ -0,0 +1,155 @@
+use crate::api::*;
+use crate::core::exchange::ExchangeState;
+use crate::core::processors::{matching_engine::MatchingEngineRouter, risk_engine::RiskEngine};
+use crate::core::users::UserProfile;
+use std::collections::BTreeMap;
+
+/// Tổng hợp số dư theo loại tiền trên mọi phân đoạn rủi ro.
+/// Cộng dồn bằng i128: tổng số dư của nhiều người dùng có thể vượt i64 dù từng số dư hợp lệ
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CurrencyAudit {
+    pub available: i128,
+    pub locked: i128,
+    /// Phần giá vốn của vị thế hợp đồng tương lai đang mở (giá bán - giá mua).
+    /// Lãi/lỗ chỉ được thanh toán khi đóng vị thế, nên tiền đã trả cho bên đóng trước
+    /// nằm ở giá vốn của các vị thế còn mở cho tới khi chúng được đóng/quyết toán.
+    pub position_basis: i128,
+    pub net_deposits: i128,
+}
+
+impl CurrencyAudit {
+    /// Chênh lệch so với tổng nạp - rút (0 = bảo toàn)
+    pub fn difference(&self) -> i128 {
+        self.available + self.locked + self.position_basis - self.net_deposits
+    }
+}
+
+/// Sai lệch phát hiện khi kiểm toán
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditDiscrepancy {
+    /// Tổng số dư của loại tiền không khớp tổng nạp - rút
+    Currency { currency: Currency, difference: i128 },
+    /// Số dư bị khóa của người dùng không khớp khoản giữ của lệnh + ký quỹ vị thế
+    LockedBalance { uid: UserId, currency: Currency, locked: i64, expected: i128 },
+    /// Khoản giữ không khớp lệnh đang treo trong sổ lệnh (0 = không có)
+    OrderHold { uid: UserId, symbol: SymbolId, order_id: OrderId, held_size: Size, resting_size: Size },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    pub currencies: BTreeMap<Currency, CurrencyAudit>,
+    pub discrepancies: Vec<AuditDiscrepancy>,
+}
+
+impl AuditReport {
+    pub fn is_balanced(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// Kiểm toán bảo toàn số dư trên toàn bộ phân đoạn rủi ro và sổ lệnh:
+/// theo loại tiền, tổng khả dụng + bị khóa + giá vốn vị thế == tổng nạp - rút
+/// (tài khoản phí, quỹ bảo hiểm là người dùng nên đã nằm trong tổng);
+/// theo người dùng, số dư bị khóa khớp khoản giữ + ký quỹ vị thế và khoản giữ khớp lệnh đang treo.
+pub fn audit(risk_engines: &[RiskEngine], matching_engines: &[MatchingEngineRouter]) -> AuditReport {
+    let mut resting: BTreeMap<(UserId, SymbolId, OrderId), Size> = matching_engines
+        .iter()
+        .flat_map(|engine| engine.get_resting_orders())
+        .map(|(symbol, order_id, uid, size)| ((uid, symbol, order_id), size))
+        .collect();
+
+    let mut profiles: Vec<&UserProfile> = risk_engines.iter().flat_map(|engine| engine.user_profiles()).collect();
+    profiles.sort_unstable_by_key(|profile| profile.uid);
+
+    let mut report = AuditReport::default();
+    for profile in profiles {
+        let expected_locked = audit_profile(profile, &risk_engines[0], &mut report);
+
+        for (&currency, &locked) in &profile.locked {
+            let expected = expected_locked.get(&currency).copied().unwrap_or(0);
+            if locked as i128 != expected {
+                report.discrepancies.push(AuditDiscrepancy::LockedBalance { uid: profile.uid, currency, locked, expected });
+            }
+        }
+        for (&currency, &expected) in &expected_locked {
+            if expected != 0 && !profile.locked.contains_key(&currency) {
+                report.discrepancies.push(AuditDiscrepancy::LockedBalance { uid: profile.uid, currency, locked: 0, expected });
+            }
+        }
+
+        let mut holds: Vec<_> = profile.holds.iter().collect();
+        holds.sort_unstable_by_key(|(&key, _)| key);
+        for (&(symbol, order_id), hold) in holds {
+            let resting_size = resting.remove(&(profile.uid, symbol, order_id)).unwrap_or(0);
+            if resting_size != hold.size {
+                report.discrepancies.push(AuditDiscrepancy::OrderHold {
+                    uid: profile.uid,
+                    symbol,
+                    order_id,
+                    held_size: hold.size,
+                    resting_size,
+                });
+            }
+        }
+    }
+
+    // Lệnh đang treo nhưng không có khoản giữ
+    for ((uid, symbol, order_id), resting_size) in resting {
+        report.discrepancies.push(AuditDiscrepancy::OrderHold { uid, symbol, order_id, held_size: 0, resting_size });
+    }
+
+    for (&currency, totals) in &report.currencies {
+        let difference = totals.difference();
+        if difference != 0 {
+            report.discrepancies.push(AuditDiscrepancy::Currency { currency, difference });
+        }
+    }
+    report
+}
+
+/// Kiểm toán ngoại tuyến trên snapshot
+pub fn audit_snapshot(state: ExchangeState) -> AuditReport {
+    let matching_engines: Vec<MatchingEngineRouter> = state
+        .pipeline_state
+        .matching_engines
+        .into_iter()
+        .map(MatchingEngineRouter::from_state)
+        .collect();
+    audit(&state.pipeline_state.risk_engines, &matching_engines)
+}
+
+/// Cộng số dư của người dùng vào tổng theo loại tiền (đặc tả công cụ được sao chép trên mọi phân đoạn), trả về số dư bị khóa kỳ vọng theo loại tiền
+fn audit_profile(profile: &UserProfile, engine: &RiskEngine, report: &mut AuditReport) -> BTreeMap<Currency, i128> {
+    for (&currency, &amount) in &profile.accounts {
+        report.currencies.entry(currency).or_default().available += amount as i128;
+    }
+    for (&currency, &amount) in &profile.locked {
+        report.currencies.entry(currency).or_default().locked += amount as i128;
+    }
+    for (&currency, &amount) in &profile.net_deposits {
+        report.currencies.entry(currency).or_default().net_deposits += amount as i128;
+    }
+
+    let mut expected_locked: BTreeMap<Currency, i128> = BTreeMap::new();
+    for hold in profile.holds.values() {
+        *expected_locked.entry(hold.currency).or_insert(0) += hold.amount_per_lot as i128 * hold.size as i128;
+    }
+
+    for position in profile.positions.values() {
+        let Some(spec) = engine.get_symbol_spec(position.symbol) else {
+            continue;
+        };
+        let collateral = if spec.symbol_type.is_futures() {
+            let basis = (position.open_price_short as i128 - position.open_price_long as i128) * spec.quote_scale_k as i128;
+            report.currencies.entry(spec.quote_currency).or_default().position_basis += basis;
+            position.open_volume_long as i128 * spec.margin_buy as i128
+                + position.open_volume_short as i128 * spec.margin_sell as i128
+        } else if spec.symbol_type.is_option() {
+            position.open_volume_short as i128 * spec.margin_sell as i128
+        } else {
+            0
+        };
+        *expected_locked.entry(spec.quote_currency).or_insert(0) += collateral;
+    }
+    expected_locked
+}
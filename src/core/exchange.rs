@@ -1,5 +1,8 @@
 use crate::api::*;
+use crate::core::audit::AuditReport;
 use crate::core::pipeline::Pipeline;
+use crate::core::users::{AccountBalance, UserProfile};
+use ahash::AHashMap;
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +14,10 @@ pub struct ExchangeConfig {
     pub risk_engines_num: usize,
     pub producer_type: ProducerType,
     pub wait_strategy: WaitStrategyType,
+    pub insurance_fund_uid: Option<UserId>, // Tài khoản quỹ bảo hiểm bù thâm hụt thanh lý
+    pub fee_account_uid: Option<UserId>,    // Tài khoản thu phí giao dịch của sàn
+    pub fee_accounts: AHashMap<Currency, UserId>, // Tài khoản thu phí riêng theo loại tiền (thay tài khoản chung)
+    pub audit_interval: u64,                // Kiểm toán bảo toàn số dư sau mỗi N lệnh (0 = tắt)
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -45,6 +52,10 @@ impl Default for ExchangeConfig {
             risk_engines_num: 1,
             producer_type: ProducerType::Single,
             wait_strategy: WaitStrategyType::BusySpin,
+            insurance_fund_uid: None,
+            fee_account_uid: None,
+            fee_accounts: AHashMap::new(),
+            audit_interval: 0,
         }
     }
 }
@@ -115,12 +126,12 @@ impl ExchangeCore {
             // Hiện tại 3.6.1 chỉ hỗ trợ rõ ràng một số chiến lược như BusySpin trong wait_strategies
             let producer: Box<dyn Publisher> = match self.config.producer_type {
                 ProducerType::Single => {
-                    Box::new(ProducerWrapper(disruptor::build_single_producer(ring_size, || OrderCommand::default(), disruptor::wait_strategies::BusySpin)
+                    Box::new(ProducerWrapper(disruptor::build_single_producer(ring_size, OrderCommand::default, disruptor::wait_strategies::BusySpin)
                         .handle_events_with(handler)
                         .build()))
                 },
                 ProducerType::Multi => {
-                    Box::new(ProducerWrapper(disruptor::build_multi_producer(ring_size, || OrderCommand::default(), disruptor::wait_strategies::BusySpin)
+                    Box::new(ProducerWrapper(disruptor::build_multi_producer(ring_size, OrderCommand::default, disruptor::wait_strategies::BusySpin)
                         .handle_events_with(handler)
                         .build()))
                 }
@@ -176,6 +187,53 @@ impl ExchangeCore {
         }
     }
 
+    /// Định nghĩa cấp phí. Gửi như lệnh (ghi nhật ký, dùng được sau khi khởi động), trả về lệnh như submit_command
+    pub fn set_fee_tier(&mut self, tier_id: u32, tier: FeeTier) -> OrderCommand {
+        self.submit_command(OrderCommand::set_fee_tier(tier_id, Some(tier)))
+    }
+
+    /// Gán cấp phí cho người dùng (None = phí của công cụ)
+    pub fn set_user_fee_tier(&mut self, uid: UserId, tier_id: Option<u32>) -> OrderCommand {
+        self.submit_command(OrderCommand::set_user_fee_tier(uid, tier_id))
+    }
+
+    /// Đặt/xóa phí riêng của người dùng trên công cụ
+    pub fn set_fee_override(&mut self, uid: UserId, symbol: SymbolId, fee: Option<FeeOverride>) -> OrderCommand {
+        self.submit_command(OrderCommand::set_fee_override(uid, symbol, fee))
+    }
+
+    /// Gỡ công cụ giao dịch (chỉ nên gọi sau khi đã quyết toán và không còn lệnh chờ)
+    pub fn remove_symbol(&mut self, symbol: SymbolId) {
+        if let Some(p) = &mut self.pipeline {
+            p.remove_symbol(symbol);
+        }
+    }
+
+    /// Truy vấn hồ sơ người dùng (chỉ khả dụng trước khi khởi động pipeline)
+    pub fn get_user_profile(&self, uid: UserId) -> Option<&UserProfile> {
+        self.pipeline.as_ref().and_then(|p| p.get_user_profile(uid))
+    }
+
+    /// Kiểm toán bảo toàn số dư (chỉ khả dụng trước khi khởi động pipeline)
+    pub fn audit(&self) -> Option<AuditReport> {
+        self.pipeline.as_ref().map(|p| p.audit())
+    }
+
+    /// Truy vấn số dư khả dụng và bị khóa (giữ cho lệnh chờ + ký quỹ vị thế) của người dùng
+    pub fn get_balance(&self, uid: UserId, currency: Currency) -> Option<AccountBalance> {
+        self.pipeline.as_ref().and_then(|p| p.get_balance(uid, currency))
+    }
+
+    /// Truy vấn trạng thái phiên giao dịch của công cụ
+    pub fn get_session_state(&self, symbol: SymbolId) -> Option<TradingSessionState> {
+        self.pipeline.as_ref().and_then(|p| p.get_session_state(symbol))
+    }
+
+    /// Truy vấn giá cân bằng và khối lượng dự kiến của phiên đấu giá
+    pub fn get_auction_indicative(&self, symbol: SymbolId) -> Option<AuctionIndicative> {
+        self.pipeline.as_ref().and_then(|p| p.get_auction_indicative(symbol))
+    }
+
     /// Gửi lệnh
     pub fn submit_command(&mut self, mut cmd: OrderCommand) -> OrderCommand {
         if let Some(j) = &mut self.journaler {
@@ -214,9 +272,11 @@ impl ExchangeCore {
     }
 
     pub fn from_state(state: ExchangeState) -> Self {
+        let mut pipeline = Pipeline::from_state(state.pipeline_state);
+        pipeline.set_audit_interval(state.config.audit_interval);
         Self {
             config: state.config,
-            pipeline: Some(Pipeline::from_state(state.pipeline_state)),
+            pipeline: Some(pipeline),
             producer: None,
             journaler: None,
             snapshot_store: None,
@@ -5,3 +5,4 @@ pub mod exchange;
 pub mod pipeline;
 pub mod journal;
 pub mod snapshot;
+pub mod audit;
@@ -5,6 +5,8 @@ pub mod naive;
 pub mod direct;
 pub mod direct_optimized;
 pub mod advanced;
+pub mod allocation;
+pub mod auction;
 
 pub use naive::NaiveOrderBook;
 pub use direct::DirectOrderBook;
@@ -24,16 +26,82 @@ pub trait OrderBook: Send {
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode;
     fn move_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode;
     fn reduce_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode;
+
+    /// Sửa lệnh đang treo: giá mới, khối lượng còn lại mới (tăng hoặc giảm), phần hiển thị mới.
+    /// Chỉ giảm khối lượng thì giữ ưu tiên thời gian; đổi giá hoặc tăng khối lượng thì xuống cuối hàng đợi
+    /// (và có thể khớp ngay nếu giá mới giao với phía đối ứng). Phần giảm phát sự kiện Reduce để hoàn khoản giữ.
+    fn amend_order(&mut self, _cmd: &mut OrderCommand) -> CommandResultCode {
+        CommandResultCode::MatchingUnsupportedCommand
+    }
+
+    /// Hủy mọi lệnh của người dùng (cmd.uid) trên sổ lệnh, chỉ lệnh đang treo phía cmd.mass_cancel_action nếu có.
+    /// Mỗi lệnh bị hủy (kể cả lệnh cùng nhóm bị hủy theo) phát một sự kiện MassCancel vào lệnh để hoàn khoản giữ.
+    fn cancel_user_orders(&mut self, cmd: &mut OrderCommand) {
+        let symbol = self.get_symbol_spec().symbol_id;
+        for order_id in self.get_user_orders(cmd.uid) {
+            if let Some(side) = cmd.mass_cancel_action {
+                if self.get_order_by_id(order_id).map(|(_, action)| action) != Some(side) {
+                    continue;
+                }
+            }
+            let mut cancel = OrderCommand {
+                command: OrderCommandType::CancelOrder,
+                uid: cmd.uid,
+                order_id,
+                symbol,
+                timestamp: cmd.timestamp,
+                ..Default::default()
+            };
+            // Lệnh có thể đã bị hủy theo lệnh cùng nhóm
+            if self.cancel_order(&mut cancel) != CommandResultCode::Success {
+                continue;
+            }
+            for event in cancel.matcher_events {
+                let (action, order_id, uid) = match event.event_type {
+                    MatcherEventType::Reject => (cancel.action, order_id, cmd.uid),
+                    MatcherEventType::GroupCancel(action) => (action, event.matched_order_id, event.matched_order_uid),
+                    _ => continue,
+                };
+                cmd.matcher_events.push(MatcherTradeEvent::new_mass_cancel(
+                    action,
+                    event.size,
+                    event.price,
+                    order_id,
+                    uid,
+                    symbol,
+                ));
+            }
+        }
+    }
     fn get_symbol_spec(&self) -> &CoreSymbolSpecification;
     fn get_l2_data(&self, depth: usize) -> L2MarketData;
     
     // Các phương thức truy vấn
     fn get_order_by_id(&self, order_id: OrderId) -> Option<(Price, OrderAction)>;
+    fn get_user_orders(&self, uid: UserId) -> Vec<OrderId>; // Sắp xếp theo ID lệnh (cũng là thứ tự hủy hàng loạt)
+    fn get_resting_orders(&self) -> Vec<(OrderId, UserId, Size)>; // (ID lệnh, người dùng, khối lượng còn lại), sắp xếp theo ID lệnh
     fn get_total_ask_volume(&self) -> Size;
     fn get_total_bid_volume(&self) -> Size;
     fn get_ask_buckets_count(&self) -> usize;
     fn get_bid_buckets_count(&self) -> usize;
 
+    // Đấu giá tập trung: lệnh tích lũy không khớp, khớp một lần tại giá cân bằng
+    /// Treo lệnh không khớp trong phiên đấu giá (mặc định: sổ lệnh không hỗ trợ đấu giá)
+    fn place_auction_order(&mut self, _cmd: &mut OrderCommand) -> CommandResultCode {
+        CommandResultCode::MatchingUnsupportedCommand
+    }
+
+    /// Giá cân bằng và khối lượng dự kiến nếu khớp đấu giá ngay lúc này
+    fn indicative_auction(&self, reference_price: Option<Price>) -> Option<AuctionIndicative> {
+        let depth = self.get_ask_buckets_count().max(self.get_bid_buckets_count());
+        auction::equilibrium(&self.get_l2_data(depth), reference_price)
+    }
+
+    /// Khớp đấu giá tại giá cân bằng, phát sự kiện AuctionTrade vào lệnh (mặc định: không hỗ trợ)
+    fn uncross(&mut self, _cmd: &mut OrderCommand, _reference_price: Option<Price>) -> Option<AuctionIndicative> {
+        None
+    }
+
     // Hỗ trợ serialize
     fn serialize_state(&self) -> OrderBookState;
 }
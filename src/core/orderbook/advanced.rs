@@ -1,9 +1,18 @@
 use crate::api::*;
+use super::allocation;
 use ahash::AHashMap;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
+mod groups;
+mod pegs;
+
+use groups::OrderGroup;
+
+/// Các lệnh đã rời mức giá trong một lần khớp (ID lệnh, người dùng)
+type RemovedOrders = SmallVec<[(OrderId, UserId); 4]>;
+
 /// Lệnh mở rộng (hỗ trợ tất cả loại lệnh)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AdvancedOrder {
@@ -19,11 +28,105 @@ struct AdvancedOrder {
     
     // Các trường mở rộng
     stop_price: Option<Price>,      // Giá kích hoạt cắt lỗ
+    trailing_offset: Option<TrailingOffset>, // Khoảng cách của lệnh cắt lỗ động
     visible_size: Option<Size>,     // Số lượng hiển thị của lệnh iceberg
+    peak_variance: Size,            // Biên độ ngẫu nhiên (±) của phần hiển thị iceberg
+    peak: Size,                     // Phần hiển thị iceberg còn lại (chỉ phần này được khớp trong một lượt)
+    hidden: bool,                   // Lệnh ẩn hoàn toàn
+    min_quantity: Size,             // Khối lượng tối thiểu của mỗi lần khớp (0 = không giới hạn)
+    peg: Option<Peg>,               // Lệnh neo giá (price là giá hiệu lực hiện tại)
     expire_time: Option<i64>,       // Thời gian hết hạn
     is_triggered: bool,             // Lệnh cắt lỗ đã được kích hoạt chưa
 }
 
+impl AdvancedOrder {
+    #[inline]
+    fn remaining(&self) -> Size {
+        self.size - self.filled
+    }
+
+    /// Khối lượng khớp được trong một lượt (lệnh iceberg chỉ có phần hiển thị hiện tại)
+    #[inline]
+    fn matchable(&self) -> Size {
+        if self.visible_size.is_some() {
+            self.peak
+        } else {
+            self.remaining()
+        }
+    }
+
+    /// Khối lượng hiển thị trên L2 (lệnh ẩn không hiển thị)
+    #[inline]
+    fn displayed(&self) -> Size {
+        if self.hidden {
+            0
+        } else {
+            self.matchable()
+        }
+    }
+
+    /// Khối lượng nhỏ nhất được phép khớp trong một lần
+    #[inline]
+    fn min_fill(&self) -> Size {
+        self.min_quantity.min(self.matchable())
+    }
+
+    /// Lệnh cắt lỗ có kích hoạt tại giá khớp này không
+    #[inline]
+    fn should_trigger(&self, last_price: Price) -> bool {
+        match (self.stop_price, self.action) {
+            (Some(stop_price), OrderAction::Bid) => last_price >= stop_price, // Cắt lỗ mua
+            (Some(stop_price), OrderAction::Ask) => last_price <= stop_price, // Cắt lỗ bán
+            (None, _) => false,
+        }
+    }
+
+    /// Cắt lỗ động: giá kích hoạt chỉ dịch theo hướng có lợi, giá giới hạn giữ nguyên khoảng cách với giá kích hoạt
+    fn trail(&mut self, last_price: Price) {
+        let (Some(offset), Some(stop_price)) = (self.trailing_offset, self.stop_price) else {
+            return;
+        };
+        let new_stop = offset.stop_price(self.action, last_price);
+        let improved = match self.action {
+            OrderAction::Ask => new_stop > stop_price,
+            OrderAction::Bid => new_stop < stop_price,
+        };
+        if improved {
+            self.price = self.price.saturating_add(new_stop - stop_price);
+            self.stop_price = Some(new_stop);
+        }
+    }
+
+    #[inline]
+    fn is_expired(&self, current_time: i64) -> bool {
+        self.expire_time.is_some_and(|expire| current_time > expire)
+    }
+
+    /// Làm mới phần hiển thị của lệnh iceberg. Biên độ ngẫu nhiên suy ra từ mã lệnh và khối lượng đã khớp
+    /// nên phát lại nhật ký cho cùng kết quả.
+    fn refresh_peak(&mut self) {
+        let Some(visible) = self.visible_size else {
+            return;
+        };
+        let mut peak = visible;
+        if self.peak_variance > 0 {
+            let span = 2 * self.peak_variance as u64 + 1;
+            let seed = splitmix64(self.order_id ^ (self.filled as u64).rotate_left(32));
+            peak += (seed % span) as Size - self.peak_variance;
+        }
+        self.peak = peak.max(1).min(self.remaining());
+    }
+}
+
+/// Bộ sinh số giả ngẫu nhiên SplitMix64 (xác định theo hạt giống)
+#[inline]
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
 /// Mức giá (hỗ trợ lệnh iceberg)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AdvancedBucket {
@@ -44,100 +147,133 @@ impl AdvancedBucket {
     }
 
     fn add(&mut self, order: AdvancedOrder) {
-        let remaining = order.size - order.filled;
-        self.total_volume += remaining;
-        
-        // Lệnh iceberg chỉ hiển thị một phần số lượng
-        if let Some(visible) = order.visible_size {
-            self.visible_volume += visible.min(remaining);
+        // Lệnh iceberg chỉ hiển thị một phần số lượng, lệnh ẩn không hiển thị
+        self.total_volume += order.remaining();
+        self.visible_volume += order.displayed();
+
+        // Lệnh ẩn xếp sau mọi lệnh hiển thị cùng giá
+        let pos = if order.hidden {
+            self.orders.len()
         } else {
-            self.visible_volume += remaining;
-        }
-        
-        self.orders.push(order);
+            self.orders.iter().position(|o| o.hidden).unwrap_or(self.orders.len())
+        };
+        self.orders.insert(pos, order);
     }
 
     fn remove(&mut self, order_id: OrderId) -> Option<AdvancedOrder> {
-        if let Some(pos) = self.orders.iter().position(|o| o.order_id == order_id) {
-            let order = self.orders.remove(pos);
-            let remaining = order.size - order.filled;
-            self.total_volume -= remaining;
-            
-            if let Some(visible) = order.visible_size {
-                self.visible_volume -= visible.min(remaining);
-            } else {
-                self.visible_volume -= remaining;
-            }
-            
-            Some(order)
-        } else {
-            None
-        }
+        let pos = self.orders.iter().position(|o| o.order_id == order_id)?;
+        let order = self.orders.remove(pos);
+        self.total_volume -= order.remaining();
+        self.visible_volume -= order.displayed();
+        Some(order)
     }
 
-    /// Khớp lệnh (hỗ trợ lệnh iceberg)
-    fn match_order(&mut self, taker_size: Size, _taker_uid: UserId, current_time: i64) 
-        -> (Size, SmallVec<[MatcherTradeEvent; 4]>) 
+    /// Khớp lệnh theo từng lượt cho đến khi đủ khối lượng hoặc hết lệnh tại mức giá.
+    /// Trả về khối lượng khớp, sự kiện và các lệnh đã rời mức giá (khớp hết hoặc hết hạn)
+    fn match_order(&mut self, taker_size: Size, spec: &CoreSymbolSpecification, current_time: i64)
+        -> (Size, SmallVec<[MatcherTradeEvent; 4]>, RemovedOrders)
     {
         let mut matched_size = 0;
         let mut events = SmallVec::new();
-        let mut to_remove = SmallVec::<[OrderId; 4]>::new();
-
-        for order in &mut self.orders {
-            // Kiểm tra lệnh đã hết hạn chưa
-            if let Some(expire) = order.expire_time {
-                if current_time > expire {
-                    to_remove.push(order.order_id);
-                    continue;
+        let mut removed = SmallVec::new();
+
+        // Lệnh hiển thị (kể cả phần làm mới của iceberg) được khớp hết trước lệnh ẩn
+        for hidden in [false, true] {
+            while matched_size < taker_size && self.orders.iter().any(|o| o.hidden == hidden) {
+                let matched = self.match_pass(taker_size - matched_size, spec, current_time, hidden, &mut events, &mut removed);
+                if matched == 0 {
+                    break;
                 }
+                matched_size += matched;
             }
+        }
 
-            let remaining = order.size - order.filled;
-            let match_size = remaining.min(taker_size - matched_size);
+        (matched_size, events, removed)
+    }
 
-            if match_size > 0 {
-                order.filled += match_size;
-                matched_size += match_size;
-                
-                // Cập nhật tổng lượng
-                self.total_volume -= match_size;
-                
-                // Cập nhật lượng hiển thị (xử lý đặc biệt cho lệnh iceberg)
-                if let Some(visible) = order.visible_size {
-                    let old_visible = visible.min(remaining);
-                    let new_remaining = order.size - order.filled;
-                    let new_visible = visible.min(new_remaining);
-                    self.visible_volume = self.visible_volume.saturating_sub(old_visible) + new_visible;
+    /// Một lượt khớp qua các lệnh cùng loại (hiển thị / ẩn) trong hàng đợi:
+    /// mỗi lệnh chỉ khớp phần hiển thị, phân bổ theo thuật toán khớp lệnh của công cụ.
+    /// Lệnh iceberg hết phần hiển thị được làm mới và xuống cuối hàng đợi (mất ưu tiên thời gian).
+    fn match_pass(
+        &mut self,
+        taker_size: Size,
+        spec: &CoreSymbolSpecification,
+        current_time: i64,
+        hidden: bool,
+        events: &mut SmallVec<[MatcherTradeEvent; 4]>,
+        removed: &mut RemovedOrders,
+    ) -> Size {
+        // Lệnh hết hạn không được phân bổ và bị loại khỏi mức giá;
+        // lệnh có khối lượng khớp tối thiểu chỉ nhận phần phân bổ đạt mức tối thiểu (phần còn lại phân bổ ở lượt sau)
+        let matchable: Vec<Size> = self
+            .orders
+            .iter()
+            .map(|o| {
+                if o.hidden != hidden || o.is_expired(current_time) || o.min_fill() > taker_size {
+                    0
                 } else {
-                    self.visible_volume -= match_size;
-                }
-
-                events.push(MatcherTradeEvent::new_trade(
-                    match_size,
-                    self.price,
-                    order.order_id,
-                    order.uid,
-                    order.reserve_price,
-                ));
-
-                if order.filled >= order.size {
-                    to_remove.push(order.order_id);
-                }
-
-                if matched_size >= taker_size {
-                    break;
+                    o.matchable()
                 }
+            })
+            .collect();
+        let mut allocations = allocation::allocate(spec, taker_size, &matchable);
+        for (allocated, order) in allocations.iter_mut().zip(&self.orders) {
+            if *allocated < order.min_fill() {
+                *allocated = 0;
             }
         }
 
-        // Loại bỏ các lệnh đã hoàn thành (không cập nhật tổng lượng, đã cập nhật ở trên)
-        for oid in to_remove {
-            if let Some(pos) = self.orders.iter().position(|o| o.order_id == oid) {
-                self.orders.remove(pos);
+        let mut matched_size = 0;
+        let mut kept = SmallVec::<[AdvancedOrder; 8]>::new();
+        let mut refreshed = SmallVec::<[AdvancedOrder; 4]>::new();
+
+        for (mut order, match_size) in std::mem::take(&mut self.orders).into_iter().zip(allocations) {
+            if order.is_expired(current_time) {
+                self.total_volume -= order.remaining();
+                self.visible_volume -= order.displayed();
+                removed.push((order.order_id, order.uid));
+                continue;
+            }
+            if match_size == 0 {
+                kept.push(order);
+                continue;
+            }
+
+            let old_displayed = order.displayed();
+            order.filled += match_size;
+            if order.visible_size.is_some() {
+                order.peak -= match_size;
+            }
+            matched_size += match_size;
+            self.total_volume -= match_size;
+
+            events.push(MatcherTradeEvent::new_trade(
+                match_size,
+                self.price,
+                order.order_id,
+                order.uid,
+                order.reserve_price,
+            ));
+
+            if order.remaining() == 0 {
+                self.visible_volume -= old_displayed;
+                removed.push((order.order_id, order.uid));
+            } else if order.visible_size.is_some() && order.peak == 0 {
+                order.refresh_peak();
+                self.visible_volume += order.displayed() - old_displayed;
+                events.push(MatcherTradeEvent::new_iceberg_refresh(order.order_id, order.uid, self.price, order.peak));
+                refreshed.push(order);
+            } else {
+                self.visible_volume -= old_displayed - order.displayed();
+                kept.push(order);
             }
         }
 
-        (matched_size, events)
+        // Lệnh iceberg làm mới xuống cuối phần lệnh hiển thị, lệnh ẩn luôn ở sau
+        kept.extend(refreshed);
+        kept.sort_by_key(|o| o.hidden);
+        self.orders = kept;
+        matched_size
     }
 }
 
@@ -150,16 +286,29 @@ pub struct AdvancedOrderBook {
     ask_buckets: BTreeMap<Price, AdvancedBucket>,
     bid_buckets: BTreeMap<Price, AdvancedBucket>,
     order_map: AHashMap<OrderId, (Price, OrderAction)>,
+
+    // Chỉ mục lệnh của người dùng (đang treo, chờ kích hoạt cắt lỗ, lệnh con bracket đang chờ)
+    user_orders: AHashMap<UserId, BTreeSet<OrderId>>,
     
     // Hồ chứa lệnh cắt lỗ (chưa kích hoạt)
     stop_orders: Vec<AdvancedOrder>,
     
+    // Nhóm lệnh liên kết (OCO) và nhóm của từng lệnh
+    groups: AHashMap<u64, OrderGroup>,
+    order_groups: AHashMap<OrderId, u64>,
+
+    // Lệnh con bracket chờ lệnh vào khớp đủ (theo thứ tự đặt)
+    dormant_orders: Vec<OrderCommand>,
+    
     // Giá khớp lệnh mới nhất (dùng để kích hoạt lệnh cắt lỗ)
     last_trade_price: Option<Price>,
     
     // Bộ nhớ đệm giá tối ưu
     best_ask_price: Option<Price>,
     best_bid_price: Option<Price>,
+
+    // Giá tham chiếu (mua, bán) lần tính lại giá lệnh neo gần nhất
+    last_peg_reference: (Option<Price>, Option<Price>),
 }
 
 impl AdvancedOrderBook {
@@ -169,10 +318,34 @@ impl AdvancedOrderBook {
             ask_buckets: BTreeMap::new(),
             bid_buckets: BTreeMap::new(),
             order_map: AHashMap::with_capacity(1024),
+            user_orders: AHashMap::new(),
             stop_orders: Vec::new(),
+            groups: AHashMap::new(),
+            order_groups: AHashMap::new(),
+            dormant_orders: Vec::new(),
             last_trade_price: None,
             best_ask_price: None,
             best_bid_price: None,
+            last_peg_reference: (None, None),
+        }
+    }
+
+    /// Giá kích hoạt và giá giới hạn hiện tại của lệnh cắt lỗ chưa kích hoạt
+    pub fn get_stop_order(&self, order_id: OrderId) -> Option<(Price, Price)> {
+        self.stop_orders
+            .iter()
+            .find(|o| o.order_id == order_id)
+            .and_then(|o| o.stop_price.map(|stop_price| (stop_price, o.price)))
+    }
+
+    /// Xóa lệnh khỏi chỉ mục ID lệnh và chỉ mục theo người dùng
+    fn release_order(&mut self, order_id: OrderId, uid: UserId) {
+        self.order_map.remove(&order_id);
+        if let Some(ids) = self.user_orders.get_mut(&uid) {
+            ids.remove(&order_id);
+            if ids.is_empty() {
+                self.user_orders.remove(&uid);
+            }
         }
     }
 
@@ -205,34 +378,29 @@ impl AdvancedOrderBook {
     /// Kiểm tra Post-Only
     fn check_post_only(&self, cmd: &OrderCommand) -> CommandResultCode {
         if self.would_match(cmd) {
-            CommandResultCode::MatchingUnsupportedCommand // Post-Only từ chối
+            CommandResultCode::PostOnlyWouldTake
         } else {
             CommandResultCode::ValidForMatchingEngine
         }
     }
 
-    /// Xử lý lệnh cắt lỗ
+    /// Xử lý lệnh cắt lỗ: dịch giá kích hoạt của lệnh cắt lỗ động, rồi đưa các lệnh kích hoạt vào sổ lệnh
+    /// (StopLimit thành lệnh giới hạn, StopMarket thành lệnh IOC với giá bảo vệ)
     fn process_stop_orders(&mut self, cmd: &mut OrderCommand) {
         if let Some(last_price) = self.last_trade_price {
-            let mut triggered = Vec::new();
-            
-            for (idx, stop_order) in self.stop_orders.iter_mut().enumerate() {
-                if let Some(stop_price) = stop_order.stop_price {
-                    let should_trigger = match stop_order.action {
-                        OrderAction::Bid => last_price >= stop_price,  // Cắt lỗ mua
-                        OrderAction::Ask => last_price <= stop_price,  // Cắt lỗ bán
-                    };
-
-                    if should_trigger && !stop_order.is_triggered {
-                        stop_order.is_triggered = true;
-                        triggered.push(idx);
-                    }
-                }
+            for stop_order in self.stop_orders.iter_mut() {
+                stop_order.trail(last_price);
             }
 
-            // Kích hoạt các lệnh cắt lỗ đã được kích hoạt
-            for idx in triggered.iter().rev() {
-                let order = self.stop_orders.remove(*idx);
+            // Tách lệnh kích hoạt khỏi hồ chứa trước khi đặt (lệnh kích hoạt có thể kích hoạt tiếp lệnh khác)
+            let (triggered, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.stop_orders)
+                .into_iter()
+                .partition(|o| !o.is_triggered && o.should_trigger(last_price));
+            self.stop_orders = pending;
+
+            for mut order in triggered {
+                self.release_order(order.order_id, order.uid);
+                order.is_triggered = true;
                 let mut activate_cmd = OrderCommand {
                     uid: order.uid,
                     order_id: order.order_id,
@@ -240,29 +408,123 @@ impl AdvancedOrderBook {
                     price: order.price,
                     size: order.size,
                     action: order.action,
-                    order_type: order.order_type,
+                    order_type: match order.order_type {
+                        OrderType::StopMarket => OrderType::Ioc,
+                        _ => OrderType::Gtc,
+                    },
                     reserve_price: order.reserve_price,
                     timestamp: order.timestamp,
+                    visible_size: order.visible_size,
+                    visible_variance: Some(order.peak_variance),
+                    hidden: order.hidden,
+                    min_quantity: Some(order.min_quantity),
                     ..Default::default()
                 };
                 
                 self.place_order_internal(&mut activate_cmd);
+                Self::forward_activated_events(&activate_cmd, &mut cmd.matcher_events);
             }
         }
     }
 
-    /// Đặt lệnh (tất cả loại)
-    fn place_order(&mut self, cmd: &mut OrderCommand) {
-        // Kiểm tra Post-Only
+    /// Chuyển sự kiện của lệnh được kích hoạt (cắt lỗ, lệnh con bracket) cho lệnh đang xử lý để R2 thanh toán:
+    /// khớp thành TriggeredTrade (hai bên ghi rõ), từ chối thành hủy theo nhóm của chính lệnh đó để hoàn khoản giữ
+    fn forward_activated_events(activated: &OrderCommand, events: &mut Vec<MatcherTradeEvent>) {
+        for event in &activated.matcher_events {
+            events.push(match event.event_type {
+                MatcherEventType::Trade => {
+                    MatcherTradeEvent::new_triggered_trade(activated.action, activated.order_id, activated.uid, event)
+                }
+                MatcherEventType::Reject => MatcherTradeEvent::new_group_cancel(
+                    activated.action,
+                    event.size,
+                    event.price,
+                    activated.order_id,
+                    activated.uid,
+                    activated.group_id.unwrap_or(0),
+                ),
+                _ => event.clone(),
+            });
+        }
+    }
+
+    /// Giá tham chiếu ban đầu của lệnh cắt lỗ động: giá khớp gần nhất, hoặc giá tốt nhất phía đối ứng
+    fn trailing_reference(&self, action: OrderAction) -> Option<Price> {
+        self.last_trade_price.or(match action {
+            OrderAction::Bid => self.best_ask_price,
+            OrderAction::Ask => self.best_bid_price,
+        })
+    }
+
+    /// Giá đặt của lệnh: giá hiệu lực của lệnh neo, rồi kiểm tra Post-Only
+    /// (giao với phía đối ứng: từ chối, hoặc dời giá vào trong giá tốt nhất đối ứng một bước giá)
+    fn price_order(&self, cmd: &mut OrderCommand) -> CommandResultCode {
+        // Lệnh neo giá không có giá tham chiếu (hoặc là lệnh cắt lỗ) bị từ chối
+        if let Some(peg) = cmd.peg {
+            let (best_bid, best_ask) = self.peg_reference();
+            match peg.price(cmd.action, best_bid, best_ask) {
+                Some(price) if !matches!(cmd.order_type, OrderType::StopLimit | OrderType::StopMarket) => {
+                    cmd.price = price;
+                }
+                _ => return CommandResultCode::MatchingUnsupportedCommand,
+            }
+        }
+
         if cmd.order_type == OrderType::PostOnly {
-            if self.check_post_only(cmd) != CommandResultCode::ValidForMatchingEngine {
+            let result = self.check_post_only(cmd);
+            if result != CommandResultCode::ValidForMatchingEngine {
+                if !cmd.post_only_reprice {
+                    return result;
+                }
+                let tick = self.symbol_spec.tick_size.max(1);
+                cmd.price = match cmd.action {
+                    OrderAction::Bid => self.best_ask_price.map_or(cmd.price, |ask| ask - tick),
+                    OrderAction::Ask => self.best_bid_price.map_or(cmd.price, |bid| bid + tick),
+                };
+                cmd.matcher_events.push(MatcherTradeEvent::new_post_only_reprice(cmd.order_id, cmd.uid, cmd.price, cmd.size));
+            }
+        }
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    /// Đặt lệnh (tất cả loại)
+    fn place_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        // Lệnh con bracket được định giá và kiểm tra Post-Only khi kích hoạt
+        if cmd.parent_order_id.is_none() {
+            let result = self.price_order(cmd);
+            if result != CommandResultCode::ValidForMatchingEngine {
                 cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
-                return;
+                return result;
             }
         }
 
+        // Nhóm lệnh liên kết / lệnh con bracket
+        if !self.link_order(cmd) {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+            return CommandResultCode::Success;
+        }
+        if cmd.parent_order_id.is_some() {
+            let mut child = cmd.clone();
+            child.matcher_events.clear();
+            self.user_orders.entry(child.uid).or_default().insert(child.order_id);
+            self.dormant_orders.push(child);
+            return CommandResultCode::Success;
+        }
+
         // Lệnh cắt lỗ: Lưu tạm vào hồ chứa cắt lỗ
         if matches!(cmd.order_type, OrderType::StopLimit | OrderType::StopMarket) {
+            // Cắt lỗ động không có giá kích hoạt ban đầu: tính từ giá tham chiếu, không có giá tham chiếu thì từ chối
+            let stop_price = match cmd.trailing_offset {
+                Some(offset) => cmd
+                    .stop_price
+                    .or_else(|| self.trailing_reference(cmd.action).map(|reference| offset.stop_price(cmd.action, reference))),
+                None => cmd.stop_price,
+            };
+            if cmd.trailing_offset.is_some() && stop_price.is_none() {
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+                return CommandResultCode::Success;
+            }
+
             let order = AdvancedOrder {
                 order_id: cmd.order_id,
                 uid: cmd.uid,
@@ -273,16 +535,24 @@ impl AdvancedOrderBook {
                 order_type: cmd.order_type,
                 reserve_price: cmd.reserve_price,
                 timestamp: cmd.timestamp,
-                stop_price: cmd.stop_price,
+                stop_price,
+                trailing_offset: cmd.trailing_offset,
                 visible_size: cmd.visible_size,
+                peak_variance: cmd.visible_variance.unwrap_or(0),
+                peak: 0,
+                hidden: cmd.hidden,
+                min_quantity: cmd.min_quantity.unwrap_or(0),
+                peg: None,
                 expire_time: cmd.expire_time,
                 is_triggered: false,
             };
+            self.user_orders.entry(order.uid).or_default().insert(order.order_id);
             self.stop_orders.push(order);
-            return;
+            return CommandResultCode::Success;
         }
 
         self.place_order_internal(cmd);
+        CommandResultCode::Success
     }
 
     /// Logic đặt lệnh nội bộ
@@ -297,18 +567,28 @@ impl AdvancedOrderBook {
         }
 
         // FOK: Khớp toàn bộ hoặc hủy toàn bộ
-        if cmd.order_type == OrderType::Fok {
-            if !self.can_fill_completely(cmd) {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
-                return;
-            }
+        if cmd.order_type == OrderType::Fok && !self.can_fill_completely(cmd) {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+            return;
         }
 
-        let filled = self.try_match(cmd);
+        // Khối lượng khớp tối thiểu của lệnh chủ động: không khớp được ngay đủ mức tối thiểu thì không khớp
+        // (IOC bị từ chối, lệnh còn lại được treo và áp dụng mức tối thiểu khi là lệnh đang treo)
+        let min_fill = cmd.min_quantity.map_or(0, |min| min.min(cmd.size));
+        let filled = if min_fill == 0 || self.executable_volume(cmd, min_fill) >= min_fill {
+            self.try_match(cmd)
+        } else {
+            0
+        };
 
         // Cập nhật giá khớp lệnh mới nhất
         if filled > 0 {
-            self.last_trade_price = Some(cmd.price);
+            self.last_trade_price = cmd
+                .matcher_events
+                .iter()
+                .rev()
+                .find(|e| e.event_type == MatcherEventType::Trade)
+                .map(|e| e.price);
             self.process_stop_orders(cmd);
         }
 
@@ -322,7 +602,7 @@ impl AdvancedOrderBook {
 
         // GTC/Day/GTD/PostOnly/Iceberg: Treo lệnh
         if filled < cmd.size {
-            let order = AdvancedOrder {
+            let mut order = AdvancedOrder {
                 order_id: cmd.order_id,
                 uid: cmd.uid,
                 price: cmd.price,
@@ -333,12 +613,20 @@ impl AdvancedOrderBook {
                 reserve_price: cmd.reserve_price,
                 timestamp: cmd.timestamp,
                 stop_price: None,
+                trailing_offset: None,
                 visible_size: cmd.visible_size,
+                peak_variance: cmd.visible_variance.unwrap_or(0),
+                peak: 0,
+                hidden: cmd.hidden,
+                min_quantity: cmd.min_quantity.unwrap_or(0),
+                peg: cmd.peg,
                 expire_time: cmd.expire_time,
                 is_triggered: false,
             };
+            order.refresh_peak();
 
             self.order_map.insert(cmd.order_id, (cmd.price, cmd.action));
+            self.user_orders.entry(cmd.uid).or_default().insert(cmd.order_id);
 
             match cmd.action {
                 OrderAction::Ask => {
@@ -365,32 +653,38 @@ impl AdvancedOrderBook {
 
     /// Kiểm tra xem có thể khớp hoàn toàn không (FOK)
     fn can_fill_completely(&self, cmd: &OrderCommand) -> bool {
-        let buckets = match cmd.action {
-            OrderAction::Bid => &self.ask_buckets,
-            OrderAction::Ask => &self.bid_buckets,
+        self.executable_volume(cmd, cmd.size) >= cmd.size
+    }
+
+    /// Khối lượng lệnh chủ động khớp được ngay (dừng khi đạt `target`),
+    /// bỏ qua lệnh đang treo có khối lượng khớp tối thiểu lớn hơn phần lệnh chủ động còn lại
+    fn executable_volume(&self, cmd: &OrderCommand, target: Size) -> Size {
+        let levels: Vec<&AdvancedBucket> = match cmd.action {
+            OrderAction::Bid => self.ask_buckets.range(..=cmd.price).map(|(_, b)| b).collect(),
+            OrderAction::Ask => self.bid_buckets.range(cmd.price..).rev().map(|(_, b)| b).collect(),
         };
 
-        let mut available = 0;
-        for (price, bucket) in buckets.iter() {
-            if (cmd.action == OrderAction::Bid && *price > cmd.price) ||
-               (cmd.action == OrderAction::Ask && *price < cmd.price) {
+        let mut left = cmd.size;
+        for order in levels.into_iter().flat_map(|b| b.orders.iter()) {
+            if cmd.size - left >= target {
                 break;
             }
-            available += bucket.total_volume;
-            if available >= cmd.size {
-                return true;
+            let remaining = order.remaining();
+            if !order.is_expired(cmd.timestamp) && left >= order.min_quantity.min(remaining) {
+                left -= remaining.min(left);
             }
         }
-        false
+        cmd.size - left
     }
 
     /// Thử khớp lệnh
     fn try_match(&mut self, cmd: &mut OrderCommand) -> Size {
         let mut filled = 0;
+        let first_event = cmd.matcher_events.len();
 
         // Kiểm tra đường dẫn nhanh
-        if (cmd.action == OrderAction::Bid && self.best_ask_price.map_or(true, |p| p > cmd.price)) ||
-           (cmd.action == OrderAction::Ask && self.best_bid_price.map_or(true, |p| p < cmd.price)) {
+        if (cmd.action == OrderAction::Bid && self.best_ask_price.is_none_or(|p| p > cmd.price)) ||
+           (cmd.action == OrderAction::Ask && self.best_bid_price.is_none_or(|p| p < cmd.price)) {
             return 0;
         }
 
@@ -406,13 +700,16 @@ impl AdvancedOrderBook {
                     }
 
                     if let Some(bucket) = self.ask_buckets.get_mut(&price) {
-                        let (matched, events) = bucket.match_order(cmd.size - filled, cmd.uid, current_time);
+                        let (matched, events, removed) = bucket.match_order(cmd.size - filled, &self.symbol_spec, current_time);
                         filled += matched;
                         cmd.matcher_events.extend(events);
 
                         if bucket.total_volume == 0 {
                             self.ask_buckets.remove(&price);
                         }
+                        for (order_id, uid) in removed {
+                            self.release_order(order_id, uid);
+                        }
                     }
                 }
                 self.update_best_prices();
@@ -426,68 +723,188 @@ impl AdvancedOrderBook {
                     }
 
                     if let Some(bucket) = self.bid_buckets.get_mut(&price) {
-                        let (matched, events) = bucket.match_order(cmd.size - filled, cmd.uid, current_time);
+                        let (matched, events, removed) = bucket.match_order(cmd.size - filled, &self.symbol_spec, current_time);
                         filled += matched;
                         cmd.matcher_events.extend(events);
 
                         if bucket.total_volume == 0 {
                             self.bid_buckets.remove(&price);
                         }
+                        for (order_id, uid) in removed {
+                            self.release_order(order_id, uid);
+                        }
                     }
                 }
                 self.update_best_prices();
             }
         }
 
+        self.settle_linked_orders(cmd, first_event, filled);
         filled
     }
 
     /// Hủy lệnh
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some((action, size, price)) = self.remove_order(cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        cmd.matcher_events.push(MatcherTradeEvent::new_reject(size, price));
+        cmd.action = action;
+        CommandResultCode::Success
+    }
+
+    /// Sửa lệnh đang treo. Giảm khối lượng / phần hiển thị tại chỗ thì giữ ưu tiên thời gian;
+    /// đổi giá, tăng khối lượng hoặc tăng phần hiển thị thì đặt lại lệnh (cùng thuộc tính) ở cuối hàng đợi
+    fn amend_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(&(price, action)) = self.order_map.get(&cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        if cmd.size <= 0 || cmd.visible_size.is_some_and(|visible| visible <= 0) {
+            return CommandResultCode::MatchingInvalidOrderSize;
+        }
+
+        let buckets = match action {
+            OrderAction::Ask => &mut self.ask_buckets,
+            OrderAction::Bid => &mut self.bid_buckets,
+        };
+        let Some(bucket) = buckets.get_mut(&price) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        let Some(order) = bucket.orders.iter_mut().find(|o| o.order_id == cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        if order.uid != cmd.uid || order.action != cmd.action {
+            return CommandResultCode::MatchingUnknownOrderId;
+        }
+
+        // Kiểm tra rủi ro: khoản giữ theo lot của lệnh mua (trừ ký quỹ) tính theo giá dự trữ
+        if !self.symbol_spec.symbol_type.is_futures() && action == OrderAction::Bid && cmd.price > order.reserve_price {
+            return CommandResultCode::RiskInvalidReserveBidPrice;
+        }
+
+        let remaining = order.remaining();
+        let visible_size = cmd.visible_size.or(order.visible_size);
+        let visible_grows = match (order.visible_size, visible_size) {
+            (Some(current), Some(visible)) => visible > current,
+            (None, Some(_)) => true,
+            _ => false,
+        };
+
+        // Chỉ giảm: sửa tại chỗ
+        if cmd.price == price && cmd.size <= remaining && !visible_grows {
+            if cmd.size < remaining {
+                cmd.matcher_events.push(MatcherTradeEvent::new_reduce(remaining - cmd.size, price));
+            }
+            let old_displayed = order.displayed();
+            order.size -= remaining - cmd.size;
+            order.visible_size = visible_size;
+            if let Some(visible) = visible_size {
+                order.peak = order.peak.min(visible).min(order.remaining());
+            }
+            bucket.total_volume -= remaining - cmd.size;
+            bucket.visible_volume -= old_displayed - order.displayed();
+            return CommandResultCode::Success;
+        }
+
+        // Đặt lại lệnh với cùng thuộc tính (lệnh Post-Only không được khớp theo giá mới)
+        let mut replace = OrderCommand {
+            uid: order.uid,
+            order_id: order.order_id,
+            symbol: cmd.symbol,
+            price: cmd.price,
+            reserve_price: order.reserve_price,
+            size: cmd.size,
+            action,
+            order_type: order.order_type,
+            timestamp: cmd.timestamp,
+            visible_size,
+            visible_variance: Some(order.peak_variance),
+            hidden: order.hidden,
+            min_quantity: Some(order.min_quantity),
+            peg: order.peg,
+            expire_time: order.expire_time,
+            ..Default::default()
+        };
+        if replace.order_type == OrderType::PostOnly && self.would_match(&replace) {
+            return CommandResultCode::PostOnlyWouldTake;
+        }
+
+        if cmd.size < remaining {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reduce(remaining - cmd.size, price));
+        }
+        let buckets = match action {
+            OrderAction::Ask => &mut self.ask_buckets,
+            OrderAction::Bid => &mut self.bid_buckets,
+        };
+        if let Some(bucket) = buckets.get_mut(&price) {
+            bucket.remove(cmd.order_id);
+            if bucket.total_volume == 0 {
+                buckets.remove(&price);
+            }
+        }
+        self.release_order(cmd.order_id, cmd.uid);
+        self.update_best_prices();
+
+        self.place_order_internal(&mut replace);
+        cmd.matcher_events.append(&mut replace.matcher_events);
+        CommandResultCode::Success
+    }
+
+    /// Gỡ lệnh đang treo, đang chờ kích hoạt cắt lỗ hoặc lệnh con bracket đang chờ.
+    /// Trả về (phía, khối lượng còn lại, giá)
+    fn remove_order(&mut self, order_id: OrderId) -> Option<(OrderAction, Size, Price)> {
         // Kiểm tra lệnh đang hoạt động
-        if let Some((price, action)) = self.order_map.remove(&cmd.order_id) {
+        if let Some(&(price, action)) = self.order_map.get(&order_id) {
             let buckets = match action {
                 OrderAction::Ask => &mut self.ask_buckets,
                 OrderAction::Bid => &mut self.bid_buckets,
             };
 
             if let Some(bucket) = buckets.get_mut(&price) {
-                if let Some(order) = bucket.remove(cmd.order_id) {
-                    cmd.matcher_events.push(MatcherTradeEvent::new_reject(
-                        order.size - order.filled,
-                        price
-                    ));
-                    cmd.action = action;
-
+                if let Some(order) = bucket.remove(order_id) {
                     if bucket.total_volume == 0 {
                         buckets.remove(&price);
                         self.update_best_prices();
                     }
-
-                    return CommandResultCode::Success;
+                    self.release_order(order_id, order.uid);
+                    return Some((action, order.size - order.filled, price));
                 }
             }
         }
 
         // Kiểm tra hồ chứa lệnh cắt lỗ
-        if let Some(pos) = self.stop_orders.iter().position(|o| o.order_id == cmd.order_id) {
+        if let Some(pos) = self.stop_orders.iter().position(|o| o.order_id == order_id) {
             let order = self.stop_orders.remove(pos);
-            cmd.matcher_events.push(MatcherTradeEvent::new_reject(order.size, order.price));
-            return CommandResultCode::Success;
+            self.release_order(order_id, order.uid);
+            return Some((order.action, order.size, order.price));
         }
 
-        CommandResultCode::MatchingUnknownOrderId
+        // Lệnh con bracket đang chờ
+        let pos = self.dormant_orders.iter().position(|c| c.order_id == order_id)?;
+        let child = self.dormant_orders.remove(pos);
+        self.release_order(order_id, child.uid);
+        Some((child.action, child.size, child.price))
     }
 }
 
 impl super::OrderBook for AdvancedOrderBook {
     fn new_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
-        self.place_order(cmd);
-        CommandResultCode::Success
+        let result = self.place_order(cmd);
+        self.reprice_pegged_orders();
+        result
     }
 
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
-        self.cancel_order(cmd)
+        let result = self.cancel_order(cmd);
+        if result == CommandResultCode::Success {
+            // Hủy một lệnh trong nhóm hủy các lệnh còn lại; hủy lệnh vào hủy các lệnh con bracket
+            let mut events = Vec::new();
+            self.cancel_group(cmd.order_id, &mut events);
+            self.cancel_children(cmd.order_id, &mut events);
+            cmd.matcher_events.extend(events);
+            self.reprice_pegged_orders();
+        }
+        result
     }
 
     fn move_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
@@ -495,6 +912,7 @@ impl super::OrderBook for AdvancedOrderBook {
         let cancel_result = self.cancel_order(cmd);
         if cancel_result == CommandResultCode::Success {
             self.place_order(cmd);
+            self.reprice_pegged_orders();
         }
         cancel_result
     }
@@ -503,6 +921,14 @@ impl super::OrderBook for AdvancedOrderBook {
         CommandResultCode::MatchingUnsupportedCommand
     }
 
+    fn amend_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let result = self.amend_order(cmd);
+        if result == CommandResultCode::Success {
+            self.reprice_pegged_orders();
+        }
+        result
+    }
+
     fn get_symbol_spec(&self) -> &CoreSymbolSpecification {
         &self.symbol_spec
     }
@@ -510,12 +936,13 @@ impl super::OrderBook for AdvancedOrderBook {
     fn get_l2_data(&self, depth: usize) -> L2MarketData {
         let mut data = L2MarketData::new(depth);
 
-        for (price, bucket) in self.ask_buckets.iter().take(depth) {
+        // Mức giá chỉ có lệnh ẩn không hiển thị
+        for (price, bucket) in self.ask_buckets.iter().filter(|(_, b)| b.visible_volume > 0).take(depth) {
             data.ask_prices.push(*price);
             data.ask_volumes.push(bucket.visible_volume); // Lượng hiển thị
         }
 
-        for (price, bucket) in self.bid_buckets.iter().rev().take(depth) {
+        for (price, bucket) in self.bid_buckets.iter().rev().filter(|(_, b)| b.visible_volume > 0).take(depth) {
             data.bid_prices.push(*price);
             data.bid_volumes.push(bucket.visible_volume); // Lượng hiển thị
         }
@@ -527,6 +954,22 @@ impl super::OrderBook for AdvancedOrderBook {
         self.order_map.get(&order_id).copied()
     }
 
+    fn get_user_orders(&self, uid: UserId) -> Vec<OrderId> {
+        self.user_orders.get(&uid).map(|ids| ids.iter().copied().collect()).unwrap_or_default()
+    }
+
+    fn get_resting_orders(&self) -> Vec<(OrderId, UserId, Size)> {
+        let mut orders: Vec<(OrderId, UserId, Size)> = self.ask_buckets.values()
+            .chain(self.bid_buckets.values())
+            .flat_map(|b| b.orders.iter())
+            .chain(self.stop_orders.iter())
+            .map(|o| (o.order_id, o.uid, o.size - o.filled))
+            .chain(self.dormant_orders.iter().map(|c| (c.order_id, c.uid, c.size)))
+            .collect();
+        orders.sort_unstable();
+        orders
+    }
+
     fn get_total_ask_volume(&self) -> Size {
         self.ask_buckets.values().map(|b| b.total_volume).sum()
     }
@@ -0,0 +1,132 @@
+use super::AdvancedOrderBook;
+use crate::api::*;
+use serde::{Deserialize, Serialize};
+
+/// Nhóm lệnh liên kết (OCO): khớp hoặc hủy một lệnh sẽ hủy các lệnh còn lại
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct OrderGroup {
+    uid: UserId,
+    legs: Vec<OrderId>, // Các lệnh còn hiệu lực của nhóm (theo thứ tự đặt)
+}
+
+impl AdvancedOrderBook {
+    /// Liên kết lệnh vào nhóm và kiểm tra lệnh vào của lệnh con bracket.
+    /// Lệnh trong nhóm phải cùng người dùng; lệnh vào phải còn hiệu lực (đang treo hoặc chờ kích hoạt cắt lỗ).
+    pub(super) fn link_order(&mut self, cmd: &OrderCommand) -> bool {
+        if let Some(parent) = cmd.parent_order_id {
+            if self.order_owner(parent) != Some(cmd.uid) {
+                return false;
+            }
+        }
+
+        let Some(group_id) = cmd.group_id else {
+            return true;
+        };
+        // Lệnh con bracket đã liên kết khi đặt, lúc kích hoạt không liên kết lại
+        if self.order_groups.contains_key(&cmd.order_id) {
+            return true;
+        }
+        let group = self.groups.entry(group_id).or_insert_with(|| OrderGroup { uid: cmd.uid, legs: Vec::new() });
+        if group.uid != cmd.uid {
+            return false;
+        }
+        group.legs.push(cmd.order_id);
+        self.order_groups.insert(cmd.order_id, group_id);
+        true
+    }
+
+    /// Người đặt lệnh đang treo hoặc đang chờ kích hoạt cắt lỗ
+    fn order_owner(&self, order_id: OrderId) -> Option<UserId> {
+        if let Some(&(price, action)) = self.order_map.get(&order_id) {
+            let buckets = match action {
+                OrderAction::Ask => &self.ask_buckets,
+                OrderAction::Bid => &self.bid_buckets,
+            };
+            if let Some(order) = buckets.get(&price).and_then(|b| b.orders.iter().find(|o| o.order_id == order_id)) {
+                return Some(order.uid);
+            }
+        }
+        self.stop_orders.iter().find(|o| o.order_id == order_id).map(|o| o.uid)
+    }
+
+    /// Sau khi khớp: ghi nhóm của lệnh đang treo vào sự kiện, hủy phần còn lại của các nhóm có lệnh vừa khớp
+    /// và kích hoạt lệnh con bracket của các lệnh đã khớp đủ
+    pub(super) fn settle_linked_orders(&mut self, cmd: &mut OrderCommand, first_event: usize, filled: Size) {
+        if filled == 0 {
+            return;
+        }
+
+        let mut makers: Vec<OrderId> = Vec::new();
+        for event in cmd.matcher_events[first_event..].iter_mut() {
+            if event.event_type != MatcherEventType::Trade {
+                continue;
+            }
+            if let Some(&group_id) = self.order_groups.get(&event.matched_order_id) {
+                event.group_id = group_id;
+            }
+            if !makers.contains(&event.matched_order_id) {
+                makers.push(event.matched_order_id);
+            }
+        }
+
+        let mut events = Vec::new();
+        for &order_id in makers.iter().chain(std::iter::once(&cmd.order_id)) {
+            self.cancel_group(order_id, &mut events);
+        }
+
+        let mut completed: Vec<OrderId> = makers.into_iter().filter(|&id| self.order_owner(id).is_none()).collect();
+        if filled == cmd.size {
+            completed.push(cmd.order_id);
+        }
+        for parent in completed {
+            self.activate_children(parent, &mut events);
+        }
+        cmd.matcher_events.extend(events);
+    }
+
+    /// Hủy các lệnh còn lại trong nhóm của lệnh (kể cả lệnh con bracket của chúng)
+    pub(super) fn cancel_group(&mut self, order_id: OrderId, events: &mut Vec<MatcherTradeEvent>) {
+        let Some(group_id) = self.order_groups.remove(&order_id) else {
+            return;
+        };
+        let Some(group) = self.groups.remove(&group_id) else {
+            return;
+        };
+        for leg in group.legs.into_iter().filter(|&leg| leg != order_id) {
+            self.order_groups.remove(&leg);
+            if let Some((action, size, price)) = self.remove_order(leg) {
+                events.push(MatcherTradeEvent::new_group_cancel(action, size, price, leg, group.uid, group_id));
+                self.cancel_children(leg, events);
+            }
+        }
+    }
+
+    /// Hủy các lệnh con bracket đang chờ của lệnh vào (lệnh vào bị hủy hoặc bị hủy theo nhóm)
+    pub(super) fn cancel_children(&mut self, parent: OrderId, events: &mut Vec<MatcherTradeEvent>) {
+        while let Some(pos) = self.dormant_orders.iter().position(|c| c.parent_order_id == Some(parent)) {
+            let child = self.dormant_orders.remove(pos);
+            self.release_order(child.order_id, child.uid);
+            events.push(MatcherTradeEvent::new_group_cancel(
+                child.action,
+                child.size,
+                child.price,
+                child.order_id,
+                child.uid,
+                child.group_id.unwrap_or(0),
+            ));
+            self.cancel_group(child.order_id, events);
+        }
+    }
+
+    /// Đưa các lệnh con bracket của lệnh vào đã khớp đủ vào sổ lệnh (theo thứ tự đặt).
+    /// Như lệnh cắt lỗ được kích hoạt, mọi sự kiện của lệnh con được chuyển cho lệnh đang xử lý.
+    fn activate_children(&mut self, parent: OrderId, events: &mut Vec<MatcherTradeEvent>) {
+        while let Some(pos) = self.dormant_orders.iter().position(|c| c.parent_order_id == Some(parent)) {
+            let mut child = self.dormant_orders.remove(pos);
+            self.release_order(child.order_id, child.uid);
+            child.parent_order_id = None;
+            self.place_order(&mut child);
+            Self::forward_activated_events(&child, events);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::{AdvancedBucket, AdvancedOrderBook};
+use crate::api::*;
+
+impl AdvancedOrderBook {
+    /// Giá mua / bán tốt nhất làm tham chiếu cho lệnh neo giá.
+    /// Chỉ tính lệnh hiển thị không neo giá, để lệnh neo không tự kéo giá tham chiếu của chính nó.
+    pub(super) fn peg_reference(&self) -> (Option<Price>, Option<Price>) {
+        let anchors = |bucket: &&AdvancedBucket| bucket.orders.iter().any(|o| o.peg.is_none() && o.displayed() > 0);
+        (
+            self.bid_buckets.values().rev().find(anchors).map(|b| b.price),
+            self.ask_buckets.values().find(anchors).map(|b| b.price),
+        )
+    }
+
+    /// Tính lại giá hiệu lực của các lệnh neo khi giá tham chiếu thay đổi.
+    /// Lệnh đổi giá xuống cuối hàng đợi của mức giá mới; khi thiếu giá tham chiếu lệnh giữ giá cũ.
+    /// Lệnh neo không được giao với phía đối ứng: dừng cách giá tốt nhất đối ứng một bước giá
+    /// (lệnh neo giá giữa: một đơn vị giá).
+    pub(super) fn reprice_pegged_orders(&mut self) {
+        let reference = self.peg_reference();
+        if reference == self.last_peg_reference {
+            return;
+        }
+        self.last_peg_reference = reference;
+        let (best_bid, best_ask) = reference;
+
+        let pegged: Vec<(OrderId, OrderAction, Price, Peg)> = self
+            .ask_buckets
+            .values()
+            .chain(self.bid_buckets.values().rev())
+            .flat_map(|b| b.orders.iter())
+            .filter_map(|o| o.peg.map(|peg| (o.order_id, o.action, o.price, peg)))
+            .collect();
+
+        for (order_id, action, price, peg) in pegged {
+            let Some(target) = peg.price(action, best_bid, best_ask) else {
+                continue;
+            };
+            let step = match peg.peg_type {
+                PegType::Midpoint => 1,
+                _ => self.symbol_spec.tick_size.max(1),
+            };
+            let target = match action {
+                OrderAction::Bid => self.best_ask_price.map_or(target, |ask| target.min(ask - step)),
+                OrderAction::Ask => self.best_bid_price.map_or(target, |bid| target.max(bid + step)),
+            };
+            if target != price {
+                self.move_pegged_order(order_id, action, price, target);
+            }
+        }
+    }
+
+    /// Chuyển lệnh neo sang mức giá mới (giữ nguyên khối lượng đã khớp và phần hiển thị iceberg)
+    fn move_pegged_order(&mut self, order_id: OrderId, action: OrderAction, from: Price, to: Price) {
+        let buckets = match action {
+            OrderAction::Ask => &mut self.ask_buckets,
+            OrderAction::Bid => &mut self.bid_buckets,
+        };
+        let Some(bucket) = buckets.get_mut(&from) else {
+            return;
+        };
+        let Some(mut order) = bucket.remove(order_id) else {
+            return;
+        };
+        if bucket.total_volume == 0 {
+            buckets.remove(&from);
+        }
+
+        order.price = to;
+        buckets.entry(to).or_insert_with(|| AdvancedBucket::new(to)).add(order);
+        self.order_map.insert(order_id, (to, action));
+        self.update_best_prices();
+    }
+}
@@ -0,0 +1,58 @@
+use crate::api::*;
+
+/// Phân bổ khối lượng của lệnh chủ động cho các lệnh đang treo cùng mức giá (theo thứ tự thời gian).
+/// Trả về khối lượng khớp của từng lệnh, tổng luôn bằng min(quantity, tổng khối lượng còn lại).
+///
+/// Với ProRata / PriceTimeProRata, khi mức giá không đủ khớp hết lệnh chủ động:
+/// lệnh sớm nhất được khớp trước (nếu bật ưu tiên lệnh đầu); PriceTimeProRata khớp tiếp một phần theo thời gian;
+/// phần còn lại chia theo tỷ lệ khối lượng còn lại (làm tròn xuống, bỏ phần nhỏ hơn mức phân bổ tối thiểu);
+/// lot dư do làm tròn được phân bổ theo thứ tự thời gian nên kết quả luôn xác định.
+pub fn allocate(spec: &CoreSymbolSpecification, quantity: Size, resting: &[Size]) -> Vec<Size> {
+    let total: Size = resting.iter().sum();
+    let mut allocations = vec![0; resting.len()];
+    let mut remaining = quantity.min(total);
+
+    if spec.matching_algorithm == MatchingAlgorithm::Fifo || remaining == total {
+        fill_fifo(&mut allocations, resting, remaining);
+        return allocations;
+    }
+
+    if spec.pro_rata_top_order {
+        remaining -= fill_fifo(&mut allocations[..1], &resting[..1], remaining);
+    }
+
+    if spec.matching_algorithm == MatchingAlgorithm::PriceTimeProRata {
+        let rate = spec.pro_rata_fifo_rate.clamp(0, ALLOCATION_RATE_SCALE);
+        let fifo_quantity = (remaining as i128 * rate as i128 / ALLOCATION_RATE_SCALE as i128) as Size;
+        remaining -= fill_fifo(&mut allocations, resting, fifo_quantity);
+    }
+
+    let open_total: Size = resting.iter().zip(&allocations).map(|(size, allocated)| size - allocated).sum();
+    if remaining > 0 && open_total > 0 {
+        let pro_rata_quantity = remaining;
+        for (allocated, &size) in allocations.iter_mut().zip(resting) {
+            let share = (pro_rata_quantity as i128 * (size - *allocated) as i128 / open_total as i128) as Size;
+            if share > 0 && share >= spec.pro_rata_min_allocation {
+                *allocated += share;
+                remaining -= share;
+            }
+        }
+    }
+
+    fill_fifo(&mut allocations, resting, remaining);
+    allocations
+}
+
+/// Phân bổ theo thứ tự thời gian phần khối lượng còn trống của từng lệnh, trả về khối lượng đã phân bổ
+fn fill_fifo(allocations: &mut [Size], resting: &[Size], quantity: Size) -> Size {
+    let mut left = quantity;
+    for (allocated, &size) in allocations.iter_mut().zip(resting) {
+        if left == 0 {
+            break;
+        }
+        let fill = left.min(size - *allocated);
+        *allocated += fill;
+        left -= fill;
+    }
+    quantity - left
+}
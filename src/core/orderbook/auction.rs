@@ -0,0 +1,50 @@
+use crate::api::*;
+
+/// Tìm giá cân bằng của phiên đấu giá từ độ sâu L2 (lệnh mua giảm dần, lệnh bán tăng dần).
+/// Chọn theo thứ tự: khối lượng khớp lớn nhất; chênh lệch mua - bán nhỏ nhất;
+/// áp lực thị trường (toàn dư mua → giá cao nhất, toàn dư bán → giá thấp nhất);
+/// gần giá tham chiếu nhất (không có giá tham chiếu → giá thấp nhất).
+pub fn equilibrium(l2: &L2MarketData, reference_price: Option<Price>) -> Option<AuctionIndicative> {
+    let mut prices: Vec<Price> = l2.ask_prices.iter().chain(&l2.bid_prices).copied().collect();
+    prices.sort_unstable();
+    prices.dedup();
+
+    // Duyệt giá tăng dần: khối lượng bán cộng dồn tăng, khối lượng mua cộng dồn giảm
+    let mut bid_volume: Size = l2.bid_volumes.iter().sum();
+    let mut ask_volume: Size = 0;
+    let mut asks = l2.ask_prices.iter().zip(&l2.ask_volumes).peekable();
+    let mut bids = l2.bid_prices.iter().zip(&l2.bid_volumes).rev().peekable();
+
+    let mut candidates: Vec<AuctionIndicative> = Vec::new();
+    for price in prices {
+        while let Some((_, &volume)) = asks.next_if(|&(&ask, _)| ask <= price) {
+            ask_volume += volume;
+        }
+        let volume = bid_volume.min(ask_volume);
+        if volume > 0 {
+            let candidate = AuctionIndicative { price, volume, imbalance: bid_volume - ask_volume };
+            match candidates.first() {
+                Some(best) if best.volume > volume => {}
+                Some(best) if best.volume == volume => candidates.push(candidate),
+                _ => candidates = vec![candidate],
+            }
+        }
+        while let Some((_, &volume)) = bids.next_if(|&(&bid, _)| bid <= price) {
+            bid_volume -= volume;
+        }
+    }
+
+    let min_imbalance = candidates.iter().map(|c| c.imbalance.abs()).min()?;
+    candidates.retain(|c| c.imbalance.abs() == min_imbalance);
+
+    if candidates.iter().all(|c| c.imbalance > 0) {
+        return candidates.last().copied();
+    }
+    if candidates.iter().all(|c| c.imbalance < 0) {
+        return candidates.first().copied();
+    }
+    match reference_price {
+        Some(reference) => candidates.into_iter().min_by_key(|c| (c.price.abs_diff(reference), c.price)),
+        None => candidates.first().copied(),
+    }
+}
@@ -1,7 +1,9 @@
 use crate::api::*;
+use super::allocation;
 use ahash::AHashMap;
 use slab::Slab;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
 use serde::{Deserialize, Serialize};
 
 type OrderIdx = usize;
@@ -48,6 +50,9 @@ pub struct DirectOrderBook {
     
     // Chỉ mục nhanh ID lệnh
     order_id_index: AHashMap<OrderId, OrderIdx>,
+
+    // Chỉ mục lệnh đang treo theo người dùng (hủy hàng loạt, sắp xếp theo ID lệnh)
+    user_orders: AHashMap<UserId, BTreeSet<OrderId>>,
     
     // Tham chiếu nhanh đến lệnh tối ưu, tương tự đường dẫn nhanh của LMAX Disruptor
     best_ask_order: Option<OrderIdx>, // Lệnh bán một
@@ -63,6 +68,7 @@ impl DirectOrderBook {
             ask_price_buckets: BTreeMap::new(),
             bid_price_buckets: BTreeMap::new(),
             order_id_index: AHashMap::new(),
+            user_orders: AHashMap::new(),
             best_ask_order: None,
             best_bid_order: None,
         }
@@ -84,23 +90,57 @@ impl DirectOrderBook {
 
         // Chưa khớp hoàn toàn, treo lệnh
         if filled < cmd.size {
-            let order_idx = self.orders.insert(DirectOrder {
-                order_id: cmd.order_id,
-                uid: cmd.uid,
-                price: cmd.price,
-                size: cmd.size,
-                filled,
-                action: cmd.action,
-                reserve_price: cmd.reserve_price,
-                timestamp: cmd.timestamp,
-                next: None,
-                prev: None,
-                parent: 0, // Giá trị tạm thời
-            });
+            self.rest_order(cmd, filled);
+        }
+    }
 
-            self.order_id_index.insert(cmd.order_id, order_idx);
-            self.insert_order(order_idx);
+    /// Treo phần còn lại của lệnh vào sổ lệnh
+    fn rest_order(&mut self, cmd: &OrderCommand, filled: Size) {
+        let order_idx = self.orders.insert(DirectOrder {
+            order_id: cmd.order_id,
+            uid: cmd.uid,
+            price: cmd.price,
+            size: cmd.size,
+            filled,
+            action: cmd.action,
+            reserve_price: cmd.reserve_price,
+            timestamp: cmd.timestamp,
+            next: None,
+            prev: None,
+            parent: 0, // Giá trị tạm thời
+        });
+
+        self.order_id_index.insert(cmd.order_id, order_idx);
+        self.user_orders.entry(cmd.uid).or_default().insert(cmd.order_id);
+        self.insert_order(order_idx);
+    }
+
+    /// Xóa lệnh khỏi chỉ mục ID lệnh và chỉ mục theo người dùng, giải phóng ô nhớ của lệnh
+    fn release_order(&mut self, order_idx: OrderIdx) {
+        let order = self.orders.remove(order_idx);
+        self.order_id_index.remove(&order.order_id);
+        if let Some(ids) = self.user_orders.get_mut(&order.uid) {
+            ids.remove(&order.order_id);
+            if ids.is_empty() {
+                self.user_orders.remove(&order.uid);
+            }
+        }
+    }
+
+    /// Khớp một phần lệnh đang treo (đấu giá), gỡ lệnh khỏi sổ khi đã khớp hết
+    fn fill_resting_order(&mut self, order_idx: OrderIdx, size: Size) {
+        let order = &mut self.orders[order_idx];
+        order.filled += size;
+        if order.filled < order.size {
+            let parent = order.parent;
+            self.buckets[parent].volume -= size;
+            return;
         }
+
+        // Lệnh đã khớp hết: khôi phục phần vừa khớp để remove_order trừ đúng khối lượng của thùng
+        order.filled -= size;
+        self.remove_order(order_idx);
+        self.release_order(order_idx);
     }
 
     /// Đặt lệnh IOC
@@ -146,14 +186,18 @@ impl DirectOrderBook {
             let available = bucket.volume;
             let price = order.price;
 
+            // Tràn số: vượt quá mọi ngân sách biểu diễn được
             if size > available {
                 size -= available;
-                budget += (available * price) as i64;
+                let Some(next) = available.checked_mul(price).and_then(|v| budget.checked_add(v)) else {
+                    return Some(i64::MAX);
+                };
+                budget = next;
                 // Di chuyển đến lệnh trước lệnh cuối thùng
                 let tail_idx = bucket.tail;
                 maker_idx = self.orders[tail_idx].prev;
             } else {
-                return Some(budget + (size * price) as i64);
+                return Some(size.checked_mul(price).and_then(|v| budget.checked_add(v)).unwrap_or(i64::MAX));
             }
         }
 
@@ -205,6 +249,20 @@ impl DirectOrderBook {
                 (order.price, order.filled, order.size, order.parent, order.prev)
             };
 
+            // Chia theo tỷ lệ: mức giá không đủ khớp hết lệnh chủ động (idx là lệnh sớm nhất của mức giá)
+            if self.symbol_spec.matching_algorithm != MatchingAlgorithm::Fifo
+                && remaining < self.buckets[maker_parent].volume
+            {
+                self.orders[idx].next = None;
+                if is_bid {
+                    self.best_ask_order = Some(idx);
+                } else {
+                    self.best_bid_order = Some(idx);
+                }
+                self.match_level_pro_rata(idx, remaining, cmd);
+                return taker_size;
+            }
+
             let trade_size = remaining.min(maker_size - maker_filled);
 
             // Cập nhật lệnh maker
@@ -241,8 +299,7 @@ impl DirectOrderBook {
             let should_remove_bucket = idx == bucket_tail;
 
             let next_maker = maker_prev;
-            self.order_id_index.remove(&self.orders[idx].order_id);
-            self.orders.remove(idx);
+            self.release_order(idx);
 
             if should_remove_bucket {
                 let price = maker_price;
@@ -257,7 +314,10 @@ impl DirectOrderBook {
             maker_idx = next_maker;
         }
 
-        // Cập nhật lệnh tối ưu
+        // Cập nhật lệnh tối ưu (lệnh đứng đầu không còn lệnh nào tốt hơn phía trước)
+        if let Some(idx) = maker_idx {
+            self.orders[idx].next = None;
+        }
         if is_bid {
             self.best_ask_order = maker_idx;
         } else {
@@ -267,6 +327,31 @@ impl DirectOrderBook {
         filled
     }
 
+    /// Khớp phần còn lại của lệnh chủ động với một mức giá theo thuật toán phân bổ của công cụ
+    fn match_level_pro_rata(&mut self, first_idx: OrderIdx, quantity: Size, cmd: &mut OrderCommand) {
+        let parent = self.orders[first_idx].parent;
+        let mut level = Vec::with_capacity(self.buckets[parent].num_orders);
+        let mut order_idx = Some(first_idx);
+        while let Some(idx) = order_idx.filter(|&idx| self.orders[idx].parent == parent) {
+            level.push(idx);
+            order_idx = self.orders[idx].prev;
+        }
+
+        let resting: Vec<Size> = level.iter().map(|&idx| self.orders[idx].size - self.orders[idx].filled).collect();
+        let allocations = allocation::allocate(&self.symbol_spec, quantity, &resting);
+        for (idx, size) in level.into_iter().zip(allocations).filter(|&(_, size)| size > 0) {
+            let order = &self.orders[idx];
+            cmd.matcher_events.push(MatcherTradeEvent::new_trade(
+                size,
+                order.price,
+                order.order_id,
+                order.uid,
+                if cmd.action == OrderAction::Bid { cmd.reserve_price } else { order.reserve_price },
+            ));
+            self.fill_resting_order(idx, size);
+        }
+    }
+
     /// Chèn lệnh vào danh sách liên kết
     fn insert_order(&mut self, order_idx: OrderIdx) {
         let (price, action) = {
@@ -310,7 +395,7 @@ impl DirectOrderBook {
             let lower_bucket_idx = if is_ask {
                 buckets_map.range(..price).next_back().map(|(_, &idx)| idx)
             } else {
-                buckets_map.range((price + 1)..).next().map(|(_, &idx)| idx)
+                buckets_map.range((Bound::Excluded(price), Bound::Unbounded)).next().map(|(_, &idx)| idx)
             };
 
             if let Some(lower_idx) = lower_bucket_idx {
@@ -419,9 +504,7 @@ impl super::OrderBook for DirectOrderBook {
                 self.place_fok_budget(cmd);
                 CommandResultCode::Success
             }
-            _ => {
-                return CommandResultCode::MatchingUnsupportedCommand;
-            }
+            _ => CommandResultCode::MatchingUnsupportedCommand,
         }
     }
 
@@ -438,9 +521,8 @@ impl super::OrderBook for DirectOrderBook {
             (order.action, order.size - order.filled, order.price)
         };
 
-        self.order_id_index.remove(&cmd.order_id);
         self.remove_order(order_idx);
-        self.orders.remove(order_idx);
+        self.release_order(order_idx);
 
         cmd.action = action;
         cmd.matcher_events.push(MatcherTradeEvent::new_reject(remaining, price));
@@ -493,8 +575,7 @@ impl super::OrderBook for DirectOrderBook {
 
         if filled == self.orders[order_idx].size {
             // Khớp hoàn toàn
-            self.order_id_index.remove(&cmd.order_id);
-            self.orders.remove(order_idx);
+            self.release_order(order_idx);
         } else {
             // Khớp một phần, treo lệnh lại
             self.orders[order_idx].filled = filled;
@@ -525,9 +606,8 @@ impl super::OrderBook for DirectOrderBook {
         let can_remove = reduce_by == remaining;
 
         if can_remove {
-            self.order_id_index.remove(&cmd.order_id);
             self.remove_order(order_idx);
-            self.orders.remove(order_idx);
+            self.release_order(order_idx);
         } else {
             let order = &mut self.orders[order_idx];
             order.size -= reduce_by;
@@ -540,6 +620,74 @@ impl super::OrderBook for DirectOrderBook {
         CommandResultCode::Success
     }
 
+    fn amend_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(&order_idx) = self.order_id_index.get(&cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+
+        // Sổ lệnh không hỗ trợ lệnh iceberg
+        if cmd.visible_size.is_some() {
+            return CommandResultCode::MatchingUnsupportedCommand;
+        }
+        if cmd.size <= 0 {
+            return CommandResultCode::MatchingInvalidOrderSize;
+        }
+
+        let (uid, action, price, reserve_price, remaining, parent_idx) = {
+            let order = &self.orders[order_idx];
+            if order.uid != cmd.uid || order.action != cmd.action {
+                return CommandResultCode::MatchingUnknownOrderId;
+            }
+            (order.uid, order.action, order.price, order.reserve_price, order.size - order.filled, order.parent)
+        };
+
+        // Kiểm tra rủi ro: khoản giữ theo lot của lệnh mua (trừ ký quỹ) tính theo giá dự trữ
+        if !self.symbol_spec.symbol_type.is_futures() && action == OrderAction::Bid && cmd.price > reserve_price {
+            return CommandResultCode::RiskInvalidReserveBidPrice;
+        }
+
+        if cmd.size < remaining {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reduce(remaining - cmd.size, price));
+        }
+
+        // Chỉ giảm khối lượng: giữ ưu tiên thời gian
+        if cmd.price == price && cmd.size <= remaining {
+            self.orders[order_idx].size -= remaining - cmd.size;
+            self.buckets[parent_idx].volume -= remaining - cmd.size;
+            return CommandResultCode::Success;
+        }
+
+        // Đổi giá hoặc tăng khối lượng: gỡ lệnh, khớp theo giá mới rồi treo lại ở cuối hàng đợi
+        self.remove_order(order_idx);
+        {
+            let order = &mut self.orders[order_idx];
+            order.price = cmd.price;
+            order.size = order.filled + cmd.size;
+        }
+
+        let mut temp_cmd = OrderCommand {
+            uid,
+            order_id: cmd.order_id,
+            symbol: cmd.symbol,
+            price: cmd.price,
+            size: cmd.size,
+            action,
+            reserve_price,
+            ..Default::default()
+        };
+        let filled = self.try_match(&mut temp_cmd);
+        cmd.matcher_events.extend(temp_cmd.matcher_events);
+
+        if filled == cmd.size {
+            self.release_order(order_idx);
+        } else {
+            self.orders[order_idx].filled += filled;
+            self.insert_order(order_idx);
+        }
+
+        CommandResultCode::Success
+    }
+
     fn get_symbol_spec(&self) -> &CoreSymbolSpecification {
         &self.symbol_spec
     }
@@ -567,6 +715,18 @@ impl super::OrderBook for DirectOrderBook {
         })
     }
 
+    fn get_user_orders(&self, uid: UserId) -> Vec<OrderId> {
+        self.user_orders.get(&uid).map(|ids| ids.iter().copied().collect()).unwrap_or_default()
+    }
+
+    fn get_resting_orders(&self) -> Vec<(OrderId, UserId, Size)> {
+        let mut orders: Vec<(OrderId, UserId, Size)> = self.order_id_index.iter()
+            .map(|(&order_id, &idx)| (order_id, self.orders[idx].uid, self.orders[idx].size - self.orders[idx].filled))
+            .collect();
+        orders.sort_unstable();
+        orders
+    }
+
     fn get_total_ask_volume(&self) -> Size {
         self.ask_price_buckets.values().map(|&idx| self.buckets[idx].volume).sum()
     }
@@ -583,6 +743,44 @@ impl super::OrderBook for DirectOrderBook {
         self.bid_price_buckets.len()
     }
 
+    fn place_auction_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        // Phiên đấu giá chỉ nhận lệnh giới hạn treo trên sổ
+        if cmd.order_type != OrderType::Gtc || self.order_id_index.contains_key(&cmd.order_id) {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+            return CommandResultCode::MatchingUnsupportedCommand;
+        }
+        self.rest_order(cmd, 0);
+        CommandResultCode::Success
+    }
+
+    fn uncross(&mut self, cmd: &mut OrderCommand, reference_price: Option<Price>) -> Option<AuctionIndicative> {
+        let result = self.indicative_auction(reference_price)?;
+
+        // Lệnh mua giá cao / lệnh bán giá thấp được khớp trước, cùng giá theo thứ tự thời gian
+        let mut remaining = result.volume;
+        while remaining > 0 {
+            let (Some(bid_idx), Some(ask_idx)) = (self.best_bid_order, self.best_ask_order) else {
+                break;
+            };
+            let (bid, ask) = (&self.orders[bid_idx], &self.orders[ask_idx]);
+            let size = remaining.min(bid.size - bid.filled).min(ask.size - ask.filled);
+            cmd.matcher_events.push(MatcherTradeEvent::new_auction_trade(
+                size,
+                result.price,
+                bid.order_id,
+                bid.uid,
+                bid.reserve_price,
+                ask.order_id,
+                ask.uid,
+            ));
+
+            self.fill_resting_order(bid_idx, size);
+            self.fill_resting_order(ask_idx, size);
+            remaining -= size;
+        }
+        Some(result)
+    }
+
     fn serialize_state(&self) -> crate::core::orderbook::OrderBookState {
         crate::core::orderbook::OrderBookState::Direct(self.clone())
     }
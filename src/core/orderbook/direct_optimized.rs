@@ -1,6 +1,6 @@
 use crate::api::*;
 use ahash::AHashMap;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use serde::{Deserialize, Serialize};
 
 type OrderIdx = usize;
@@ -100,6 +100,9 @@ pub struct DirectOrderBookOptimized {
     
     // Chỉ mục ID lệnh
     order_index: AHashMap<OrderId, OrderIdx>,
+
+    // Chỉ mục lệnh đang treo theo người dùng
+    user_orders: AHashMap<UserId, BTreeSet<OrderId>>,
     
     // Bộ nhớ đệm giá tối ưu
     best_ask: Option<Price>,
@@ -114,6 +117,7 @@ impl DirectOrderBookOptimized {
             ask_buckets: BTreeMap::new(),
             bid_buckets: BTreeMap::new(),
             order_index: AHashMap::with_capacity(100_000),
+            user_orders: AHashMap::new(),
             best_ask: None,
             best_bid: None,
         }
@@ -149,6 +153,7 @@ impl DirectOrderBookOptimized {
                 };
 
                 self.order_index.insert(cmd.order_id, idx);
+                self.user_orders.entry(cmd.uid).or_default().insert(cmd.order_id);
                 self.insert_to_bucket(idx, cmd.price, cmd.action);
             }
         }
@@ -227,6 +232,7 @@ impl DirectOrderBookOptimized {
                     if self.order_pool.hot.filled[current_idx] >= self.order_pool.hot.sizes[current_idx] {
                         let order_id = self.order_pool.hot.order_ids[current_idx];
                         self.order_index.remove(&order_id);
+                        Self::remove_user_order(&mut self.user_orders, maker_uid, order_id);
                         self.order_pool.dealloc(current_idx);
                     }
 
@@ -312,6 +318,7 @@ impl DirectOrderBookOptimized {
                     if self.order_pool.hot.filled[current_idx] >= self.order_pool.hot.sizes[current_idx] {
                         let order_id = self.order_pool.hot.order_ids[current_idx];
                         self.order_index.remove(&order_id);
+                        Self::remove_user_order(&mut self.user_orders, maker_uid, order_id);
                         self.order_pool.dealloc(current_idx);
                     }
 
@@ -371,6 +378,16 @@ impl DirectOrderBookOptimized {
         }
     }
 
+    /// Xóa lệnh khỏi chỉ mục theo người dùng (hàm liên kết để mượn riêng trường khi đang giữ thùng giá)
+    fn remove_user_order(user_orders: &mut AHashMap<UserId, BTreeSet<OrderId>>, uid: UserId, order_id: OrderId) {
+        if let Some(ids) = user_orders.get_mut(&uid) {
+            ids.remove(&order_id);
+            if ids.is_empty() {
+                user_orders.remove(&uid);
+            }
+        }
+    }
+
     /// Cập nhật bộ nhớ đệm giá tối ưu
     fn update_best_price(&mut self, is_ask: bool) {
         if is_ask {
@@ -391,6 +408,7 @@ impl DirectOrderBookOptimized {
             cmd.action = action;
 
             self.order_index.remove(&cmd.order_id);
+            Self::remove_user_order(&mut self.user_orders, self.order_pool.cold[order_idx].uid, cmd.order_id);
             self.order_pool.dealloc(order_idx);
 
             CommandResultCode::Success
@@ -455,6 +473,19 @@ impl super::OrderBook for DirectOrderBookOptimized {
         })
     }
 
+    fn get_user_orders(&self, uid: UserId) -> Vec<OrderId> {
+        self.user_orders.get(&uid).map(|ids| ids.iter().copied().collect()).unwrap_or_default()
+    }
+
+    fn get_resting_orders(&self) -> Vec<(OrderId, UserId, Size)> {
+        let hot = &self.order_pool.hot;
+        let mut orders: Vec<(OrderId, UserId, Size)> = self.order_index.iter()
+            .map(|(&order_id, &idx)| (order_id, self.order_pool.cold[idx].uid, hot.sizes[idx] - hot.filled[idx]))
+            .collect();
+        orders.sort_unstable();
+        orders
+    }
+
     fn get_total_ask_volume(&self) -> Size {
         self.ask_buckets.values().map(|b| b.volume).sum()
     }
@@ -1,8 +1,11 @@
 use crate::api::*;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
+/// Các lệnh đã rời mức giá trong một lần khớp (ID lệnh, người dùng)
+type RemovedOrders = SmallVec<[(OrderId, UserId); 4]>;
+
 /// Bản ghi lệnh
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
@@ -55,11 +58,11 @@ impl OrdersBucket {
         }
     }
 
-    /// Khớp lệnh: Trả về khối lượng khớp và sự kiện
-    fn match_order(&mut self, taker_size: Size, _taker_uid: UserId) -> (Size, SmallVec<[MatcherTradeEvent; 4]>) {
+    /// Khớp lệnh: Trả về khối lượng khớp, sự kiện và các lệnh đã khớp hết (ID lệnh, người dùng)
+    fn match_order(&mut self, taker_size: Size, _taker_uid: UserId) -> (Size, SmallVec<[MatcherTradeEvent; 4]>, RemovedOrders) {
         let mut matched_size = 0;
         let mut events = SmallVec::new();
-        let mut to_remove = SmallVec::<[OrderId; 4]>::new();
+        let mut to_remove = RemovedOrders::new();
 
         for order in &mut self.orders {
             let remaining = order.remaining();
@@ -78,7 +81,7 @@ impl OrdersBucket {
                 ));
 
                 if order.filled == order.size {
-                    to_remove.push(order.order_id);
+                    to_remove.push((order.order_id, order.uid));
                 }
 
                 if matched_size == taker_size {
@@ -88,11 +91,11 @@ impl OrdersBucket {
         }
 
         // Loại bỏ các lệnh đã khớp hoàn toàn
-        for oid in to_remove {
+        for &(oid, _) in &to_remove {
             self.remove(oid);
         }
 
-        (matched_size, events)
+        (matched_size, events, to_remove)
     }
 }
 
@@ -105,6 +108,7 @@ pub struct NaiveOrderBook {
     ask_buckets: BTreeMap<Price, OrdersBucket>, // Lệnh bán (giá tăng dần)
     bid_buckets: BTreeMap<Price, OrdersBucket>, // Lệnh mua (giá giảm dần)
     order_map: AHashMap<OrderId, (Price, OrderAction)>, // Sử dụng AHashMap khi chạy
+    user_orders: AHashMap<UserId, BTreeSet<OrderId>>, // Chỉ mục lệnh đang treo theo người dùng
     
     // Tối ưu hiệu năng: Bộ nhớ đệm giá tối ưu
     best_ask_price: Option<Price>,
@@ -118,11 +122,23 @@ impl NaiveOrderBook {
             ask_buckets: BTreeMap::new(),
             bid_buckets: BTreeMap::new(),
             order_map: AHashMap::with_capacity(1024), // Cấp phát trước dung lượng
+            user_orders: AHashMap::new(),
             best_ask_price: None,
             best_bid_price: None,
         }
     }
     
+    /// Xóa lệnh khỏi chỉ mục ID lệnh và chỉ mục theo người dùng
+    fn release_order(&mut self, order_id: OrderId, uid: UserId) {
+        self.order_map.remove(&order_id);
+        if let Some(ids) = self.user_orders.get_mut(&uid) {
+            ids.remove(&order_id);
+            if ids.is_empty() {
+                self.user_orders.remove(&uid);
+            }
+        }
+    }
+
     #[inline]
     fn update_best_prices(&mut self) {
         self.best_ask_price = self.ask_buckets.keys().next().copied();
@@ -158,6 +174,7 @@ impl NaiveOrderBook {
             };
 
             self.order_map.insert(cmd.order_id, (cmd.price, cmd.action));
+            self.user_orders.entry(cmd.uid).or_default().insert(cmd.order_id);
 
             match cmd.action {
                 OrderAction::Ask => {
@@ -212,7 +229,7 @@ impl NaiveOrderBook {
 
     /// Kiểm tra ngân sách có đủ không
     fn is_budget_satisfied(&self, action: OrderAction, calculated: i64, limit: i64) -> bool {
-        calculated == limit || (action == OrderAction::Bid) != (calculated > limit)
+        calculated != i64::MAX && (calculated == limit || (action == OrderAction::Bid) != (calculated > limit))
     }
 
     /// Tính toán ngân sách cần thiết để lấp đầy lệnh
@@ -227,12 +244,15 @@ impl NaiveOrderBook {
         for (price, bucket) in buckets.iter() {
             let available = bucket.total_volume;
 
+            // Tràn số: vượt quá mọi ngân sách biểu diễn được
             if size > available {
                 size -= available;
-                budget += (available * price) as i64;
+                let Some(next) = available.checked_mul(*price).and_then(|v| budget.checked_add(v)) else {
+                    return Some(i64::MAX);
+                };
+                budget = next;
             } else {
-                budget += (size * price) as i64;
-                return Some(budget);
+                return Some(size.checked_mul(*price).and_then(|v| budget.checked_add(v)).unwrap_or(i64::MAX));
             }
         }
 
@@ -270,13 +290,16 @@ impl NaiveOrderBook {
                     }
 
                     if let Some(bucket) = self.ask_buckets.get_mut(&price) {
-                        let (matched, events) = bucket.match_order(cmd.size - filled, cmd.uid);
+                        let (matched, events, completed) = bucket.match_order(cmd.size - filled, cmd.uid);
                         filled += matched;
                         cmd.matcher_events.extend(events);
 
                         if bucket.total_volume == 0 {
                             self.ask_buckets.remove(&price);
                         }
+                        for (order_id, uid) in completed {
+                            self.release_order(order_id, uid);
+                        }
                     }
                 }
                 self.update_best_prices();
@@ -305,13 +328,16 @@ impl NaiveOrderBook {
                     }
 
                     if let Some(bucket) = self.bid_buckets.get_mut(&price) {
-                        let (matched, events) = bucket.match_order(cmd.size - filled, cmd.uid);
+                        let (matched, events, completed) = bucket.match_order(cmd.size - filled, cmd.uid);
                         filled += matched;
                         cmd.matcher_events.extend(events);
 
                         if bucket.total_volume == 0 {
                             self.bid_buckets.remove(&price);
                         }
+                        for (order_id, uid) in completed {
+                            self.release_order(order_id, uid);
+                        }
                     }
                 }
                 self.update_best_prices();
@@ -342,7 +368,7 @@ impl super::OrderBook for NaiveOrderBook {
     }
 
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
-        let Some((price, action)) = self.order_map.remove(&cmd.order_id) else {
+        let Some((price, action)) = self.order_map.get(&cmd.order_id).copied() else {
             return CommandResultCode::MatchingUnknownOrderId;
         };
 
@@ -360,6 +386,7 @@ impl super::OrderBook for NaiveOrderBook {
                     buckets.remove(&price);
                     self.update_best_prices(); // Cập nhật bộ nhớ đệm giá tối ưu
                 }
+                self.release_order(order.order_id, order.uid);
 
                 return CommandResultCode::Success;
             }
@@ -447,7 +474,7 @@ impl super::OrderBook for NaiveOrderBook {
             }
             self.update_best_prices();
         } else {
-            self.order_map.remove(&cmd.order_id);
+            self.release_order(cmd.order_id, order.uid);
         }
 
         CommandResultCode::Success
@@ -470,15 +497,15 @@ impl super::OrderBook for NaiveOrderBook {
 
                 if reduce_by == remaining {
                     // Loại bỏ hoàn toàn
-                    let _order = bucket.remove(cmd.order_id).unwrap();
+                    let order = bucket.remove(cmd.order_id).unwrap();
                     cmd.matcher_events.push(MatcherTradeEvent::new_reject(reduce_by, price));
                     cmd.action = action;
-                    self.order_map.remove(&cmd.order_id);
 
                     if bucket.total_volume == 0 {
                         buckets.remove(&price);
                         self.update_best_prices();
                     }
+                    self.release_order(order.order_id, order.uid);
                 } else {
                     // Giảm một phần
                     order.size -= reduce_by;
@@ -520,6 +547,20 @@ impl super::OrderBook for NaiveOrderBook {
         self.order_map.get(&order_id).copied()
     }
 
+    fn get_user_orders(&self, uid: UserId) -> Vec<OrderId> {
+        self.user_orders.get(&uid).map(|ids| ids.iter().copied().collect()).unwrap_or_default()
+    }
+
+    fn get_resting_orders(&self) -> Vec<(OrderId, UserId, Size)> {
+        let mut orders: Vec<(OrderId, UserId, Size)> = self.ask_buckets.values()
+            .chain(self.bid_buckets.values())
+            .flat_map(|b| b.orders.iter())
+            .map(|o| (o.order_id, o.uid, o.remaining()))
+            .collect();
+        orders.sort_unstable();
+        orders
+    }
+
     fn get_total_ask_volume(&self) -> Size {
         self.ask_buckets.values().map(|b| b.total_volume).sum()
     }
@@ -1,7 +1,10 @@
 use crate::api::*;
+use crate::core::audit::{self, AuditReport};
 use crate::core::exchange::{ExchangeConfig, ResultConsumer};
 use crate::core::processors::{matching_engine::{MatchingEngineRouter, MatchingEngineState}, risk_engine::RiskEngine};
+use crate::core::users::{AccountBalance, UserProfile};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 #[derive(Serialize, Deserialize)]
 pub struct PipelineState {
@@ -14,20 +17,105 @@ pub struct Pipeline {
     risk_engines: Vec<RiskEngine>,
     matching_engines: Vec<MatchingEngineRouter>,
     result_consumer: Option<ResultConsumer>,
+    pending_commands: VecDeque<OrderCommand>, // Lệnh nội bộ (thanh lý, bù bảo hiểm) chờ xử lý
+    audit_interval: u64,                      // Kiểm toán định kỳ sau mỗi N lệnh (0 = tắt)
+    commands_since_audit: u64,
 }
 
 impl Pipeline {
-    /// Xử lý một lệnh đơn (pipeline đầy đủ)
+    /// Xử lý một lệnh đơn (pipeline đầy đủ), sau đó xử lý các lệnh nội bộ phát sinh
     pub fn handle_event(&mut self, cmd: &mut OrderCommand, _sequence: i64, _end_of_batch: bool) {
+        // Lệnh nội bộ không được gửi từ bên ngoài
+        if cmd.command.is_internal() {
+            cmd.result_code = CommandResultCode::InternalCommandRejected;
+            if let Some(consumer) = &self.result_consumer {
+                consumer(cmd);
+            }
+            return;
+        }
+
+        // Phiên quá hạn heartbeat tại thời điểm của lệnh: hủy lệnh của phiên đó trước khi xử lý lệnh
+        self.cancel_expired_sessions(cmd.timestamp);
+
+        // Công cụ tới thời điểm đáo hạn: hủy lệnh chờ ngay (lệnh mới đã bị engine rủi ro từ chối)
+        self.cancel_expired_symbol_orders(cmd.timestamp);
+
+        // Quyết toán đáo hạn (lệnh chờ đã được hủy ở trên): quyết toán xong thì gỡ sổ lệnh
+        let expiry = matches!(cmd.command, OrderCommandType::ExpireOption | OrderCommandType::SettleFutures)
+            && self.risk_engines[0].check_expiry(cmd) == CommandResultCode::Success;
+
+        self.process_command(cmd);
+
+        if expiry {
+            for engine in &mut self.matching_engines {
+                engine.remove_symbol(cmd.symbol);
+            }
+        }
+
+        while let Some(mut internal) = self.pending_commands.pop_front() {
+            self.process_command(&mut internal);
+        }
+
+        self.periodic_audit();
+    }
+
+    pub fn set_audit_interval(&mut self, interval: u64) {
+        self.audit_interval = interval;
+        self.commands_since_audit = 0;
+    }
+
+    /// Kiểm toán bảo toàn số dư trên toàn bộ phân đoạn
+    pub fn audit(&self) -> AuditReport {
+        audit::audit(&self.risk_engines, &self.matching_engines)
+    }
+
+    /// Kiểm toán định kỳ, ghi cảnh báo cho từng sai lệch
+    fn periodic_audit(&mut self) {
+        if self.audit_interval == 0 {
+            return;
+        }
+        self.commands_since_audit += 1;
+        if self.commands_since_audit < self.audit_interval {
+            return;
+        }
+        self.commands_since_audit = 0;
+
+        for discrepancy in self.audit().discrepancies {
+            tracing::warn!(?discrepancy, "Kiểm toán số dư phát hiện sai lệch");
+        }
+    }
+
+    /// Giao thức nhiều phân đoạn rủi ro (người dùng thuộc phân đoạn `uid & (n - 1)`):
+    /// - R1: chỉ phân đoạn sở hữu taker kiểm tra rủi ro và giữ tiền; lệnh quyết toán/giá đánh dấu
+    ///   chạy trên mọi phân đoạn, mỗi phân đoạn xử lý người dùng của mình.
+    /// - ME: sự kiện khớp mang đủ ngữ cảnh cho mọi bên (uid, mã lệnh maker); trước R2, mỗi phân đoạn ghi phí
+    ///   của các bên thuộc mình vào sự kiện theo phí đã ghi cùng khoản giữ của lệnh.
+    /// - R2: mọi phân đoạn nhận cùng lệnh và cùng sự kiện, mỗi bên chỉ được thanh toán bởi phân đoạn sở hữu,
+    ///   nên taker và maker đều được thanh toán đúng một lần dù nằm ở phân đoạn khác nhau.
+    /// - Lệnh nội bộ phát sinh được xử lý theo thứ tự uid, không phụ thuộc số phân đoạn.
+    fn process_command(&mut self, cmd: &mut OrderCommand) {
         // 1. Risk R1 (Xử lý trước)
         for engine in &mut self.risk_engines {
             engine.pre_process(cmd);
         }
 
-        // 2. Matching Engine
+        // 2. Matching Engine (báo giá hai chiều được kiểm tra trên mọi phân đoạn trước khi phân đoạn nào treo lệnh)
+        if cmd.command == OrderCommandType::MassQuote && cmd.result_code == CommandResultCode::ValidForMatchingEngine {
+            let rejected = self
+                .matching_engines
+                .iter()
+                .map(|engine| engine.check_mass_quote(cmd))
+                .find(|&result| result != CommandResultCode::Success);
+            if let Some(result) = rejected {
+                cmd.result_code = result;
+            }
+        }
         for engine in &mut self.matching_engines {
             engine.process_order(cmd);
         }
+        for engine in &self.risk_engines {
+            engine.assign_trade_fees(cmd);
+        }
 
         // 3. Risk R2 (Xử lý sau)
         for engine in &mut self.risk_engines {
@@ -38,7 +126,110 @@ impl Pipeline {
         if let Some(consumer) = &self.result_consumer {
             consumer(cmd);
         }
+
+        // 5. Thu thập lệnh nội bộ phát sinh từ các phân đoạn rủi ro (theo thứ tự uid trên mọi phân đoạn)
+        let mut derived_commands: Vec<OrderCommand> = self
+            .risk_engines
+            .iter_mut()
+            .flat_map(|engine| engine.take_pending_commands())
+            .collect();
+        derived_commands.sort_by_key(|derived| derived.uid);
+        for derived in derived_commands {
+            if derived.command == OrderCommandType::Liquidate {
+                self.enqueue_liquidation_cancels(&derived);
+            }
+            self.pending_commands.push_back(derived);
+        }
+    }
+
+    /// Hủy các lệnh đang chờ của người dùng bị thanh lý trước khi đóng vị thế
+    fn enqueue_liquidation_cancels(&mut self, liquidation: &OrderCommand) {
+        for engine in &self.matching_engines {
+            for order_id in engine.get_user_orders(liquidation.symbol, liquidation.uid) {
+                self.pending_commands.push_back(OrderCommand {
+                    command: OrderCommandType::CancelOrder,
+                    uid: liquidation.uid,
+                    order_id,
+                    symbol: liquidation.symbol,
+                    timestamp: liquidation.timestamp,
+                    ..Default::default()
+                });
+            }
+        }
     }
+
+    /// Hủy lệnh đặt từ các phiên quá hạn heartbeat (theo thứ tự uid, mã phiên trên mọi phân đoạn).
+    /// Lệnh của phiên khác của cùng người dùng vẫn được giữ.
+    fn cancel_expired_sessions(&mut self, timestamp: i64) {
+        let mut sessions: Vec<(UserId, SessionId)> = self
+            .risk_engines
+            .iter_mut()
+            .flat_map(|engine| engine.take_expired_heartbeats(timestamp))
+            .collect();
+        sessions.sort_unstable();
+
+        for (uid, session_id) in sessions {
+            let orders: Vec<(SymbolId, OrderId)> = self
+                .risk_engines
+                .iter()
+                .flat_map(|engine| engine.session_orders(uid, session_id))
+                .collect();
+            for (symbol, order_id) in orders {
+                let mut cancel = OrderCommand {
+                    command: OrderCommandType::CancelOrder,
+                    uid,
+                    order_id,
+                    symbol,
+                    session_id,
+                    timestamp,
+                    ..Default::default()
+                };
+                self.process_command(&mut cancel);
+            }
+        }
+    }
+
+    /// Hủy lệnh chờ của các công cụ vừa tới thời điểm đáo hạn (theo thứ tự ID công cụ).
+    /// Mọi phân đoạn rủi ro có cùng danh sách công cụ: đánh dấu trên tất cả, dùng kết quả của phân đoạn cuối.
+    fn cancel_expired_symbol_orders(&mut self, timestamp: i64) {
+        let mut symbols = Vec::new();
+        for engine in &mut self.risk_engines {
+            symbols = engine.take_expired_symbols(timestamp);
+        }
+        for symbol in symbols {
+            self.cancel_symbol_orders(symbol, timestamp);
+        }
+    }
+
+    /// Hủy toàn bộ lệnh chờ trên công cụ (theo thứ tự uid, order id)
+    fn cancel_symbol_orders(&mut self, symbol: SymbolId, timestamp: i64) {
+        let mut uids: Vec<UserId> = self
+            .risk_engines
+            .iter()
+            .flat_map(|engine| engine.users_with_pending_orders(symbol))
+            .collect();
+        uids.sort_unstable();
+
+        for uid in uids {
+            let order_ids: Vec<OrderId> = self
+                .matching_engines
+                .iter()
+                .flat_map(|engine| engine.get_user_orders(symbol, uid))
+                .collect();
+            for order_id in order_ids {
+                let mut cancel = OrderCommand {
+                    command: OrderCommandType::CancelOrder,
+                    uid,
+                    order_id,
+                    symbol,
+                    timestamp,
+                    ..Default::default()
+                };
+                self.process_command(&mut cancel);
+            }
+        }
+    }
+
     pub fn serialize_state(&self) -> PipelineState {
         PipelineState {
             risk_engines: self.risk_engines.clone(),
@@ -51,12 +242,23 @@ impl Pipeline {
             risk_engines: state.risk_engines,
             matching_engines: state.matching_engines.into_iter().map(MatchingEngineRouter::from_state).collect(),
             result_consumer: None,
+            pending_commands: VecDeque::new(),
+            audit_interval: 0,
+            commands_since_audit: 0,
         }
     }
     pub fn new(config: &ExchangeConfig) -> Self {
         // Tạo các phân đoạn engine rủi ro
         let risk_engines = (0..config.risk_engines_num)
-            .map(|shard_id| RiskEngine::new(shard_id, config.risk_engines_num))
+            .map(|shard_id| {
+                let mut engine = RiskEngine::new(shard_id, config.risk_engines_num);
+                engine.set_insurance_fund_uid(config.insurance_fund_uid);
+                engine.set_fee_account_uid(config.fee_account_uid);
+                for (&currency, &uid) in &config.fee_accounts {
+                    engine.set_currency_fee_account(currency, uid);
+                }
+                engine
+            })
             .collect();
 
         // Tạo các phân đoạn engine khớp lệnh
@@ -68,6 +270,9 @@ impl Pipeline {
             risk_engines,
             matching_engines,
             result_consumer: None,
+            pending_commands: VecDeque::new(),
+            audit_interval: config.audit_interval,
+            commands_since_audit: 0,
         }
     }
 
@@ -75,6 +280,26 @@ impl Pipeline {
         self.result_consumer = Some(consumer);
     }
 
+    /// Truy vấn hồ sơ người dùng trên phân đoạn rủi ro tương ứng
+    pub fn get_user_profile(&self, uid: UserId) -> Option<&UserProfile> {
+        self.risk_engines.iter().find_map(|engine| engine.get_user_profile(uid))
+    }
+
+    /// Truy vấn số dư khả dụng và bị khóa của người dùng
+    pub fn get_balance(&self, uid: UserId, currency: Currency) -> Option<AccountBalance> {
+        self.get_user_profile(uid).map(|profile| profile.balance(currency))
+    }
+
+    /// Truy vấn trạng thái phiên giao dịch trên phân đoạn khớp lệnh sở hữu công cụ
+    pub fn get_session_state(&self, symbol: SymbolId) -> Option<TradingSessionState> {
+        self.matching_engines.iter().find_map(|engine| engine.get_session_state(symbol))
+    }
+
+    /// Truy vấn giá cân bằng và khối lượng dự kiến của phiên đấu giá
+    pub fn get_auction_indicative(&self, symbol: SymbolId) -> Option<AuctionIndicative> {
+        self.matching_engines.iter().find_map(|engine| engine.get_auction_indicative(symbol))
+    }
+
     pub fn add_symbol(&mut self, spec: CoreSymbolSpecification) {
         for engine in &mut self.risk_engines {
             engine.add_symbol(spec.clone());
@@ -83,4 +308,13 @@ impl Pipeline {
             engine.add_symbol(spec.clone());
         }
     }
+
+    pub fn remove_symbol(&mut self, symbol: SymbolId) {
+        for engine in &mut self.risk_engines {
+            engine.remove_symbol(symbol);
+        }
+        for engine in &mut self.matching_engines {
+            engine.remove_symbol(symbol);
+        }
+    }
 }
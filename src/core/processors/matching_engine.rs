@@ -1,5 +1,6 @@
 use crate::api::*;
 use crate::core::orderbook::{OrderBook, OrderBookState};
+use crate::core::processors::price_band::PriceBandState;
 use ahash::AHashMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -9,12 +10,16 @@ pub struct MatchingEngineState {
     pub shard_id: usize,
     pub shard_mask: i32,
     pub order_books: HashMap<SymbolId, OrderBookState>, // Sử dụng HashMap chuẩn khi serialize
+    pub price_bands: HashMap<SymbolId, PriceBandState>,
+    pub sessions: HashMap<SymbolId, TradingSessionState>,
 }
 
 pub struct MatchingEngineRouter {
     shard_id: usize,
     shard_mask: i32,
     order_books: AHashMap<SymbolId, Box<dyn OrderBook>>,
+    price_bands: AHashMap<SymbolId, PriceBandState>, // Dải giá / ngắt mạch theo công cụ
+    sessions: AHashMap<SymbolId, TradingSessionState>, // Trạng thái phiên theo công cụ (mặc định khớp liên tục)
 }
 
 impl MatchingEngineRouter {
@@ -27,6 +32,8 @@ impl MatchingEngineRouter {
             shard_id: self.shard_id,
             shard_mask: self.shard_mask,
             order_books: books_state,
+            price_bands: self.price_bands.iter().map(|(&symbol, band)| (symbol, band.clone())).collect(),
+            sessions: self.sessions.iter().map(|(&symbol, &state)| (symbol, state)).collect(),
         }
     }
 
@@ -45,6 +52,8 @@ impl MatchingEngineRouter {
             shard_id: state.shard_id,
             shard_mask: state.shard_mask,
             order_books,
+            price_bands: state.price_bands.into_iter().collect(),
+            sessions: state.sessions.into_iter().collect(),
         }
     }
 
@@ -54,6 +63,8 @@ impl MatchingEngineRouter {
             shard_id,
             shard_mask: (num_shards - 1) as i32,
             order_books: AHashMap::new(),
+            price_bands: AHashMap::new(),
+            sessions: AHashMap::new(),
         }
     }
 
@@ -66,6 +77,62 @@ impl MatchingEngineRouter {
         self.order_books.insert(spec.symbol_id, Box::new(DirectOrderBook::new(spec)));
     }
 
+    /// Gỡ sổ lệnh của công cụ giao dịch (sau khi đã quyết toán)
+    pub fn remove_symbol(&mut self, symbol: SymbolId) -> bool {
+        self.price_bands.remove(&symbol);
+        self.sessions.remove(&symbol);
+        self.order_books.remove(&symbol).is_some()
+    }
+
+    /// Danh sách lệnh đang treo của người dùng trên công cụ giao dịch (rỗng nếu không thuộc phân đoạn này)
+    pub fn get_user_orders(&self, symbol: SymbolId, uid: UserId) -> Vec<OrderId> {
+        if !self.symbol_for_this_shard(symbol) {
+            return Vec::new();
+        }
+        self.order_books
+            .get(&symbol)
+            .map(|book| book.get_user_orders(uid))
+            .unwrap_or_default()
+    }
+
+    /// Toàn bộ lệnh đang treo trên các sổ lệnh của phân đoạn: (công cụ, ID lệnh, người dùng, khối lượng còn lại)
+    pub fn get_resting_orders(&self) -> Vec<(SymbolId, OrderId, UserId, Size)> {
+        let mut orders: Vec<(SymbolId, OrderId, UserId, Size)> = self
+            .order_books
+            .iter()
+            .filter(|(&symbol, _)| self.symbol_for_this_shard(symbol))
+            .flat_map(|(&symbol, book)| {
+                book.get_resting_orders()
+                    .into_iter()
+                    .map(move |(order_id, uid, size)| (symbol, order_id, uid, size))
+            })
+            .collect();
+        orders.sort_unstable();
+        orders
+    }
+
+    /// Trạng thái dải giá / ngắt mạch của công cụ (None nếu công cụ chưa nhận lệnh nào)
+    pub fn get_price_band(&self, symbol: SymbolId) -> Option<&PriceBandState> {
+        self.price_bands.get(&symbol)
+    }
+
+    /// Trạng thái phiên giao dịch của công cụ (None nếu công cụ không thuộc phân đoạn này)
+    pub fn get_session_state(&self, symbol: SymbolId) -> Option<TradingSessionState> {
+        if !self.symbol_for_this_shard(symbol) || !self.order_books.contains_key(&symbol) {
+            return None;
+        }
+        Some(self.sessions.get(&symbol).copied().unwrap_or_default())
+    }
+
+    /// Kết quả đấu giá dự kiến của công cụ đang trong phiên đấu giá (None nếu không có lệnh giao nhau)
+    pub fn get_auction_indicative(&self, symbol: SymbolId) -> Option<AuctionIndicative> {
+        if !self.get_session_state(symbol)?.is_auction() {
+            return None;
+        }
+        let reference_price = self.price_bands.get(&symbol).and_then(|band| band.reference_price);
+        self.order_books.get(&symbol)?.indicative_auction(reference_price)
+    }
+
     pub fn process_order(&mut self, cmd: &mut OrderCommand) {
         // Nếu đã có mã kết quả (dùng cho test), bỏ qua khớp lệnh
         if cmd.result_code == CommandResultCode::Success {
@@ -73,43 +140,356 @@ impl MatchingEngineRouter {
         }
 
         match cmd.command {
+            // Hủy hàng loạt / kill switch không gắn với một công cụ: mọi phân đoạn hủy lệnh trên các sổ lệnh của mình
+            OrderCommandType::MassCancel | OrderCommandType::KillSwitch if cmd.result_code == CommandResultCode::ValidForMatchingEngine => {
+                self.mass_cancel(cmd);
+            }
+            OrderCommandType::MassQuote => {
+                self.mass_quote(cmd);
+            }
             OrderCommandType::PlaceOrder
+            | OrderCommandType::Liquidate
             | OrderCommandType::CancelOrder
             | OrderCommandType::MoveOrder
-            | OrderCommandType::ReduceOrder => {
-                if self.symbol_for_this_shard(cmd.symbol) {
-                    self.process_matching_command(cmd);
-                }
+            | OrderCommandType::ReduceOrder
+            | OrderCommandType::AmendOrder
+            | OrderCommandType::SetReferencePrice
+            | OrderCommandType::SetSessionState
+                if self.symbol_for_this_shard(cmd.symbol) =>
+            {
+                self.process_matching_command(cmd);
             }
             _ => {}
         }
     }
 
+    /// Hủy lệnh của người dùng trên các sổ lệnh của phân đoạn (theo thứ tự công cụ, lọc theo mass_cancel_symbol).
+    /// Hủy lệnh được phép trong mọi trạng thái phiên và khi đang ngắt mạch.
+    fn mass_cancel(&mut self, cmd: &mut OrderCommand) {
+        let mut symbols: Vec<SymbolId> = self
+            .order_books
+            .keys()
+            .copied()
+            .filter(|&symbol| self.symbol_for_this_shard(symbol))
+            .filter(|&symbol| cmd.mass_cancel_symbol.is_none_or(|filter| filter == symbol))
+            .collect();
+        symbols.sort_unstable();
+        for symbol in symbols {
+            if let Some(book) = self.order_books.get_mut(&symbol) {
+                book.cancel_user_orders(cmd);
+            }
+        }
+    }
+
+    /// Kiểm tra báo giá hai chiều trên các công cụ của phân đoạn. Báo giá được treo tất cả hoặc không, nên pipeline
+    /// kiểm tra trên mọi phân đoạn trước khi phân đoạn nào treo lệnh.
+    pub fn check_mass_quote(&self, cmd: &OrderCommand) -> CommandResultCode {
+        let default_band = PriceBandState::default();
+        for quote in cmd.quotes.iter().filter(|quote| self.symbol_for_this_shard(quote.symbol)) {
+            let Some(book) = self.order_books.get(&quote.symbol) else {
+                return CommandResultCode::MatchingInvalidOrderBookId;
+            };
+            let session = self.sessions.get(&quote.symbol).copied().unwrap_or_default();
+            let band = self.price_bands.get(&quote.symbol).unwrap_or(&default_band);
+            let result = Self::check_quote(session, band, book.as_ref(), cmd.timestamp, quote);
+            if result != CommandResultCode::Success {
+                return result;
+            }
+        }
+        CommandResultCode::Success
+    }
+
+    /// Báo giá hai chiều trên các công cụ của phân đoạn: kiểm tra lại phần của phân đoạn, hủy báo giá cũ do engine
+    /// rủi ro chọn rồi treo báo giá mới. Khoản giữ được đổi ở R2, chỉ khi toàn bộ báo giá được treo.
+    fn mass_quote(&mut self, cmd: &mut OrderCommand) {
+        if cmd.result_code != CommandResultCode::ValidForMatchingEngine {
+            return;
+        }
+        let result = self.check_mass_quote(cmd);
+        if result != CommandResultCode::Success {
+            cmd.result_code = result;
+            return;
+        }
+        for quote in &cmd.quotes {
+            if !self.symbol_for_this_shard(quote.symbol) {
+                continue;
+            }
+            let Some(book) = self.order_books.get_mut(&quote.symbol) else {
+                continue;
+            };
+            for action in [OrderAction::Bid, OrderAction::Ask] {
+                if let Some(order_id) = quote.side(action).replaced {
+                    let mut cancel = OrderCommand {
+                        command: OrderCommandType::CancelOrder,
+                        uid: cmd.uid,
+                        order_id,
+                        symbol: quote.symbol,
+                        ..Default::default()
+                    };
+                    book.cancel_order(&mut cancel);
+                }
+            }
+            for action in [OrderAction::Bid, OrderAction::Ask] {
+                let side = quote.side(action);
+                if side.size != 0 {
+                    book.new_order(&mut OrderCommand::quote_order(cmd.uid, cmd.timestamp, quote.symbol, action, side));
+                }
+            }
+        }
+    }
+
+    /// Kiểm tra báo giá trên một công cụ: chỉ trong phiên khớp liên tục, không ngắt mạch; mỗi phía trong dải giá
+    /// (không kéo về biên), đúng quy tắc giao dịch, không trùng ID lệnh đang treo và không giao với phía đối ứng
+    /// (báo giá không khớp ngay). Báo giá cũ bị thay không tính vào ID trùng và giá tốt nhất phía đối ứng.
+    fn check_quote(
+        session: TradingSessionState,
+        band: &PriceBandState,
+        book: &dyn OrderBook,
+        timestamp: i64,
+        quote: &Quote,
+    ) -> CommandResultCode {
+        if session != TradingSessionState::Continuous {
+            return CommandResultCode::SessionCommandNotAllowed;
+        }
+        if band.is_halted(timestamp) {
+            return CommandResultCode::CircuitBreakerHalted;
+        }
+        if quote.bid.size != 0 && quote.ask.size != 0 && quote.bid.price >= quote.ask.price {
+            return CommandResultCode::PostOnlyWouldTake;
+        }
+
+        let spec = book.get_symbol_spec();
+        // Báo giá cũ bị thay chiếm nhiều nhất một mức giá mỗi phía: hai mức giá tốt nhất là đủ
+        let l2 = book.get_l2_data(2);
+        for action in [OrderAction::Bid, OrderAction::Ask] {
+            let side = quote.side(action);
+            if side.size == 0 {
+                continue;
+            }
+            if band.check_price(spec, action, side.price) != Ok(side.price) {
+                return CommandResultCode::PriceOutsideBand;
+            }
+            let result = spec.validate_order(side.price, side.size);
+            if result != CommandResultCode::Success {
+                return result;
+            }
+            let replaced = [quote.bid.replaced, quote.ask.replaced];
+            if book.get_order_by_id(side.order_id).is_some() && !replaced.contains(&Some(side.order_id)) {
+                return CommandResultCode::RiskDuplicateOrderId;
+            }
+
+            let opposite = quote.side(action.opposite());
+            let replaced_level = opposite
+                .replaced
+                .and_then(|order_id| book.get_order_by_id(order_id))
+                .map(|(price, _)| (price, opposite.replaced_size));
+            let (prices, volumes) = match action {
+                OrderAction::Bid => (&l2.ask_prices, &l2.ask_volumes),
+                OrderAction::Ask => (&l2.bid_prices, &l2.bid_volumes),
+            };
+            let best = prices
+                .iter()
+                .zip(volumes)
+                .find(|&(&price, &volume)| match replaced_level {
+                    Some((replaced_price, replaced_size)) if replaced_price == price => volume > replaced_size,
+                    _ => volume > 0,
+                })
+                .map(|(&price, _)| price);
+            let crosses = best.is_some_and(|best| match action {
+                OrderAction::Bid => side.price >= best,
+                OrderAction::Ask => side.price <= best,
+            });
+            if crosses {
+                return CommandResultCode::PostOnlyWouldTake;
+            }
+        }
+        CommandResultCode::Success
+    }
+
     fn process_matching_command(&mut self, cmd: &mut OrderCommand) {
+        // Lệnh đặt / sửa / dời lệnh đã bị engine rủi ro từ chối: giữ nguyên mã kết quả
+        if matches!(
+            cmd.command,
+            OrderCommandType::PlaceOrder
+                | OrderCommandType::Liquidate
+                | OrderCommandType::AmendOrder
+                | OrderCommandType::MoveOrder
+        ) && cmd.result_code != CommandResultCode::ValidForMatchingEngine
+        {
+            return;
+        }
+
         let Some(book) = self.order_books.get_mut(&cmd.symbol) else {
             cmd.result_code = CommandResultCode::MatchingInvalidOrderBookId;
             return;
         };
 
+        let session = self.sessions.entry(cmd.symbol).or_default();
+        let band = self.price_bands.entry(cmd.symbol).or_default();
+        // Lệnh điều hành công cụ không phụ thuộc trạng thái phiên (giá tham chiếu còn dùng để chọn giá đấu giá)
+        match cmd.command {
+            OrderCommandType::SetSessionState => {
+                cmd.result_code = Self::change_session_state(session, band, book.as_mut(), cmd);
+                return;
+            }
+            OrderCommandType::SetReferencePrice => {
+                // Giá <= 0 xóa giá tham chiếu (tắt dải động tới giao dịch kế tiếp)
+                band.reference_price = (cmd.price > 0).then_some(cmd.price);
+                cmd.result_code = CommandResultCode::Success;
+                return;
+            }
+            _ => {}
+        }
+        let auction = session.is_auction();
+        if !session.allows(cmd.command) {
+            // Lệnh đặt bị từ chối phát sự kiện từ chối toàn bộ để hoàn phần đã giữ
+            if cmd.command == OrderCommandType::PlaceOrder {
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+            }
+            cmd.result_code = CommandResultCode::SessionCommandNotAllowed;
+            return;
+        }
+
         match cmd.command {
             OrderCommandType::PlaceOrder => {
-                if cmd.result_code == CommandResultCode::ValidForMatchingEngine {
-                    book.new_order(cmd);
-                    cmd.result_code = CommandResultCode::Success;
+                // Kiểm tra ngắt mạch, dải giá và quy tắc giao dịch (theo giá sau khi kéo về biên);
+                // lệnh bị từ chối phát sự kiện từ chối toàn bộ để hoàn phần đã giữ
+                let spec = book.get_symbol_spec();
+                let result = if band.is_halted(cmd.timestamp) {
+                    CommandResultCode::CircuitBreakerHalted
+                } else {
+                    match band.check_price(spec, cmd.action, cmd.price) {
+                        Ok(price) => {
+                            cmd.price = price;
+                            spec.validate_order(cmd.price, cmd.size)
+                        }
+                        Err(result) => result,
+                    }
+                };
+                if result != CommandResultCode::Success {
+                    cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+                    cmd.result_code = result;
+                    return;
+                }
+                if auction {
+                    cmd.result_code = book.place_auction_order(cmd);
+                    return;
                 }
+                book.new_order(cmd);
+                Self::record_trades(band, book.get_symbol_spec(), cmd);
+                cmd.result_code = CommandResultCode::Success;
+            }
+            OrderCommandType::Liquidate => {
+                // Lệnh thanh lý không bị chặn bởi dải giá / ngắt mạch
+                book.new_order(cmd);
+                Self::record_trades(band, book.get_symbol_spec(), cmd);
+                cmd.result_code = CommandResultCode::Success;
             }
             OrderCommandType::CancelOrder => {
                 cmd.result_code = book.cancel_order(cmd);
             }
             OrderCommandType::MoveOrder => {
-                cmd.result_code = book.move_order(cmd);
+                cmd.result_code = Self::check_move(band, book.as_ref(), cmd);
+                if cmd.result_code == CommandResultCode::Success {
+                    cmd.result_code = book.move_order(cmd);
+                    Self::record_trades(band, book.get_symbol_spec(), cmd);
+                }
             }
             OrderCommandType::ReduceOrder => {
                 cmd.result_code = book.reduce_order(cmd);
             }
+            OrderCommandType::AmendOrder => {
+                // Sửa lệnh thất bại: engine rủi ro hoàn phần đã giữ thêm (hold_increase)
+                cmd.result_code = Self::check_amend(band, book.as_ref(), cmd);
+                if cmd.result_code == CommandResultCode::Success {
+                    cmd.result_code = book.amend_order(cmd);
+                    Self::record_trades(band, book.get_symbol_spec(), cmd);
+                }
+            }
             _ => {
                 cmd.result_code = CommandResultCode::MatchingUnsupportedCommand;
             }
         }
     }
+
+    /// Chuyển trạng thái phiên theo lệnh quản trị và phát sự kiện chuyển trạng thái.
+    /// Khi vào khớp liên tục hoặc đóng cửa, sổ lệnh còn giao nhau (lệnh tích lũy của phiên đấu giá)
+    /// được khớp đấu giá tại giá cân bằng trước khi chuyển trạng thái.
+    fn change_session_state(
+        session: &mut TradingSessionState,
+        band: &mut PriceBandState,
+        book: &mut dyn OrderBook,
+        cmd: &mut OrderCommand,
+    ) -> CommandResultCode {
+        let Some(next) = cmd.session_state else {
+            return CommandResultCode::InvalidSessionTransition;
+        };
+        if !session.can_transition_to(next) {
+            return CommandResultCode::InvalidSessionTransition;
+        }
+
+        if matches!(next, TradingSessionState::Continuous | TradingSessionState::Closed) {
+            if let Some(result) = book.uncross(cmd, band.reference_price) {
+                tracing::info!(symbol = cmd.symbol, price = result.price, volume = result.volume, "Khớp đấu giá");
+                Self::record_trades(band, book.get_symbol_spec(), cmd);
+            }
+        }
+
+        tracing::info!(symbol = cmd.symbol, from = ?*session, to = ?next, "Chuyển trạng thái phiên giao dịch");
+        *session = next;
+        cmd.matcher_events.push(MatcherTradeEvent::new_session_change(next, cmd.timestamp));
+        CommandResultCode::Success
+    }
+
+    /// Kiểm tra dời lệnh: ngắt mạch, bước giá và dải giá theo phía của lệnh đang treo
+    fn check_move(band: &PriceBandState, book: &dyn OrderBook, cmd: &mut OrderCommand) -> CommandResultCode {
+        if band.is_halted(cmd.timestamp) {
+            return CommandResultCode::CircuitBreakerHalted;
+        }
+        let spec = book.get_symbol_spec();
+        let result = spec.validate_price(cmd.price);
+        if result != CommandResultCode::Success {
+            return result;
+        }
+        let Some((_, action)) = book.get_order_by_id(cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        match band.check_price(spec, action, cmd.price) {
+            Ok(price) => {
+                cmd.price = price;
+                CommandResultCode::Success
+            }
+            Err(result) => result,
+        }
+    }
+
+    /// Kiểm tra sửa lệnh: như dời lệnh, thêm quy tắc khối lượng của công cụ cho khối lượng còn lại mới
+    fn check_amend(band: &PriceBandState, book: &dyn OrderBook, cmd: &mut OrderCommand) -> CommandResultCode {
+        let result = Self::check_move(band, book, cmd);
+        if result != CommandResultCode::Success {
+            return result;
+        }
+        book.get_symbol_spec().validate_order(cmd.price, cmd.size)
+    }
+
+    /// Ghi nhận các giao dịch vừa khớp vào dải giá; khi biến động vượt ngưỡng thì phát sự kiện ngắt mạch.
+    /// Các giao dịch của lệnh kích hoạt vẫn giữ nguyên, ngắt mạch áp dụng từ lệnh kế tiếp.
+    fn record_trades(band: &mut PriceBandState, spec: &CoreSymbolSpecification, cmd: &mut OrderCommand) {
+        let mut halt = None;
+        let trades = cmd.matcher_events.iter().filter(|e| {
+            matches!(
+                e.event_type,
+                MatcherEventType::Trade | MatcherEventType::AuctionTrade | MatcherEventType::TriggeredTrade(_)
+            )
+        });
+        for event in trades {
+            if let Some(halted_until) = band.record_trade(spec, cmd.timestamp, event.price) {
+                halt.get_or_insert(MatcherTradeEvent::new_halt(event.price, halted_until));
+            }
+        }
+        if let Some(event) = halt {
+            tracing::warn!(symbol = cmd.symbol, price = event.price, halted_until = event.amount, "Ngắt mạch công cụ giao dịch");
+            cmd.matcher_events.push(event);
+        }
+    }
 }
@@ -1,3 +1,4 @@
 pub mod grouping;
 pub mod risk_engine;
 pub mod matching_engine;
+pub mod price_band;
@@ -0,0 +1,105 @@
+use crate::api::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Trạng thái dải giá và ngắt mạch của một công cụ giao dịch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceBandState {
+    pub reference_price: Option<Price>, // Giá khớp gần nhất, hoặc giá tham chiếu đặt thủ công
+    pub halted_until: i64,              // Thời điểm mở lại khớp lệnh (0 = chưa từng ngắt mạch)
+    // Giá khớp trong cửa sổ ngắt mạch (thời điểm, giá): hàng đợi đơn điệu giữ giá cao nhất / thấp nhất
+    window_max: VecDeque<(i64, Price)>,
+    window_min: VecDeque<(i64, Price)>,
+}
+
+impl PriceBandState {
+    /// Công cụ đang bị ngắt mạch tại thời điểm của lệnh
+    pub fn is_halted(&self, timestamp: i64) -> bool {
+        timestamp < self.halted_until
+    }
+
+    /// Biên dưới / biên trên hiệu lực: giao của dải tĩnh và dải động, làm tròn vào trong theo bước giá
+    pub fn limits(&self, spec: &CoreSymbolSpecification) -> (Price, Price) {
+        let mut low = spec.price_band_min.max(0);
+        let mut high = if spec.price_band_max > 0 { spec.price_band_max } else { Price::MAX };
+
+        if let (true, Some(reference)) = (spec.price_band_rate > 0, self.reference_price) {
+            let delta = reference as i128 * spec.price_band_rate as i128 / PRICE_BAND_SCALE as i128;
+            let delta = delta.min(Price::MAX as i128) as Price;
+            low = low.max(reference.saturating_sub(delta));
+            high = high.min(reference.saturating_add(delta));
+        }
+
+        if spec.tick_size > 0 {
+            let low_rem = low.rem_euclid(spec.tick_size);
+            if low_rem != 0 {
+                low = low.saturating_add(spec.tick_size - low_rem);
+            }
+            high -= high.rem_euclid(spec.tick_size);
+        }
+        (low, high)
+    }
+
+    /// Kiểm tra giá lệnh theo dải giá, trả về giá sau khi kéo về biên (chế độ Cap).
+    /// Chỉ phía chủ động được kéo về biên (giá mua thấp hơn / giá bán cao hơn nên khoản giữ vẫn đủ);
+    /// lệnh mua dưới biên dưới / bán trên biên trên luôn bị từ chối.
+    pub fn check_price(
+        &self,
+        spec: &CoreSymbolSpecification,
+        action: OrderAction,
+        price: Price,
+    ) -> Result<Price, CommandResultCode> {
+        let (low, high) = self.limits(spec);
+        let price = match (spec.price_band_action, action) {
+            (PriceBandAction::Cap, OrderAction::Bid) if price > high => high,
+            (PriceBandAction::Cap, OrderAction::Ask) if price < low => low,
+            _ => price,
+        };
+
+        if price < low || price > high {
+            return Err(CommandResultCode::PriceOutsideBand);
+        }
+        Ok(price)
+    }
+
+    /// Ghi nhận giá khớp làm giá tham chiếu và đưa vào cửa sổ ngắt mạch.
+    /// Trả về thời điểm mở lại khớp lệnh nếu biến động so với giá cao nhất / thấp nhất trong cửa sổ vượt ngưỡng.
+    pub fn record_trade(&mut self, spec: &CoreSymbolSpecification, timestamp: i64, price: Price) -> Option<i64> {
+        self.reference_price = Some(price);
+        if spec.circuit_breaker_rate <= 0 || spec.circuit_breaker_cooldown <= 0 {
+            return None;
+        }
+
+        let window_start = timestamp.saturating_sub(spec.circuit_breaker_window);
+        for window in [&mut self.window_max, &mut self.window_min] {
+            while window.front().is_some_and(|&(time, _)| time < window_start) {
+                window.pop_front();
+            }
+        }
+
+        let exceeds = |from: Price, to: Price| {
+            (to as i128 - from as i128).abs() * PRICE_BAND_SCALE as i128
+                > from as i128 * spec.circuit_breaker_rate as i128
+        };
+        let triggered = self.window_max.front().is_some_and(|&(_, max)| exceeds(max, price))
+            || self.window_min.front().is_some_and(|&(_, min)| exceeds(min, price));
+
+        if triggered {
+            // Cửa sổ bắt đầu lại sau khi mở cửa, biến động vừa rồi không kích hoạt thêm lần nữa
+            self.window_max.clear();
+            self.window_min.clear();
+            self.halted_until = timestamp.saturating_add(spec.circuit_breaker_cooldown);
+            return Some(self.halted_until);
+        }
+
+        while self.window_max.back().is_some_and(|&(_, max)| max <= price) {
+            self.window_max.pop_back();
+        }
+        self.window_max.push_back((timestamp, price));
+        while self.window_min.back().is_some_and(|&(_, min)| min >= price) {
+            self.window_min.pop_back();
+        }
+        self.window_min.push_back((timestamp, price));
+        None
+    }
+}
@@ -1,14 +1,37 @@
 use crate::api::*;
-use crate::core::users::UserProfileService;
-use ahash::AHashMap;
+use crate::core::users::{UserProfile, UserProfileService};
+use ahash::{AHashMap, AHashSet};
 use serde::{Deserialize, Serialize};
 
+mod fees;
+mod funding;
+mod heartbeat;
+mod liquidation;
+mod margin;
+mod options;
+mod quotes;
+mod settlement;
+
+pub use fees::FeeSchedule;
+pub use funding::MarkPriceState;
+pub use heartbeat::Heartbeat;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct RiskEngine {
     shard_id: usize,
     shard_mask: u64,
     user_service: UserProfileService,
     symbols: AHashMap<SymbolId, CoreSymbolSpecification>, // Sử dụng AHashMap khi chạy
+    mark_prices: AHashMap<SymbolId, MarkPriceState>,      // Giá đánh dấu / funding theo công cụ ký quỹ
+    insurance_fund_uid: Option<UserId>,
+    fee_account_uid: Option<UserId>, // Tài khoản thu phí của sàn
+    fee_accounts: AHashMap<Currency, UserId>, // Tài khoản thu phí riêng theo loại tiền (thay tài khoản chung)
+    fee_schedule: FeeSchedule,
+    heartbeats: AHashMap<(UserId, SessionId), Heartbeat>, // Hạn heartbeat theo phiên kết nối của người dùng thuộc phân đoạn
+    quotes: AHashMap<(UserId, SymbolId), (Option<OrderId>, Option<OrderId>)>, // Báo giá đang hiệu lực (ID lệnh mua, bán)
+    expired_symbols: AHashSet<SymbolId>, // Công cụ đã tới hạn và đã được hủy lệnh chờ, chờ quyết toán
+    #[serde(skip)]
+    pending_commands: Vec<OrderCommand>, // Lệnh nội bộ phát sinh, pipeline sẽ xử lý tiếp
 }
 
 impl RiskEngine {
@@ -19,9 +42,27 @@ impl RiskEngine {
             shard_mask: (num_shards - 1) as u64,
             user_service: UserProfileService::new(),
             symbols: AHashMap::new(),
+            mark_prices: AHashMap::new(),
+            insurance_fund_uid: None,
+            fee_account_uid: None,
+            fee_accounts: AHashMap::new(),
+            fee_schedule: FeeSchedule::default(),
+            heartbeats: AHashMap::new(),
+            quotes: AHashMap::new(),
+            expired_symbols: AHashSet::new(),
+            pending_commands: Vec::new(),
         }
     }
 
+    pub fn set_insurance_fund_uid(&mut self, uid: Option<UserId>) {
+        self.insurance_fund_uid = uid;
+    }
+
+    /// Lấy ra các lệnh nội bộ phát sinh trong lần xử lý vừa rồi
+    pub fn take_pending_commands(&mut self) -> Vec<OrderCommand> {
+        std::mem::take(&mut self.pending_commands)
+    }
+
     fn uid_for_this_shard(&self, uid: UserId) -> bool {
         self.shard_mask == 0 || (uid & self.shard_mask) == self.shard_id as u64
     }
@@ -30,75 +71,247 @@ impl RiskEngine {
         self.symbols.insert(spec.symbol_id, spec);
     }
 
+    /// Gỡ công cụ giao dịch (sau khi đã quyết toán)
+    pub fn remove_symbol(&mut self, symbol: SymbolId) -> Option<CoreSymbolSpecification> {
+        self.mark_prices.remove(&symbol);
+        self.expired_symbols.remove(&symbol);
+        self.symbols.remove(&symbol)
+    }
+
+    pub fn get_user_profile(&self, uid: UserId) -> Option<&UserProfile> {
+        self.user_service.get_user(uid)
+    }
+
+    /// Hồ sơ người dùng thuộc phân đoạn này (thứ tự không xác định)
+    pub fn user_profiles(&self) -> impl Iterator<Item = &UserProfile> {
+        self.user_service.profiles()
+    }
+
+    pub fn get_symbol_spec(&self, symbol: SymbolId) -> Option<&CoreSymbolSpecification> {
+        self.symbols.get(&symbol)
+    }
+
+    pub fn get_mark_price_state(&self, symbol: SymbolId) -> Option<&MarkPriceState> {
+        self.mark_prices.get(&symbol)
+    }
+
     // R1: Pre-process
     pub fn pre_process(&mut self, cmd: &mut OrderCommand) {
         match cmd.command {
-            OrderCommandType::PlaceOrder => {
-                if self.uid_for_this_shard(cmd.uid) {
-                    cmd.result_code = self.place_order_risk_check(cmd);
-                }
+            OrderCommandType::PlaceOrder if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.place_order_risk_check(cmd);
             }
-            OrderCommandType::AddUser => {
-                if self.uid_for_this_shard(cmd.uid) {
-                    cmd.result_code = if self.user_service.add_user(cmd.uid) {
-                        CommandResultCode::Success
-                    } else {
-                        CommandResultCode::UserMgmtUserAlreadyExists
-                    };
-                }
+            OrderCommandType::AmendOrder if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.amend_order_risk_check(cmd);
             }
-            OrderCommandType::BalanceAdjustment => {
-                if self.uid_for_this_shard(cmd.uid) {
-                    cmd.result_code = self.user_service.balance_adjustment(
-                        cmd.uid,
-                        cmd.symbol,
-                        cmd.price,
-                        cmd.order_id as i64,
-                    );
-                }
+            OrderCommandType::MoveOrder if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.move_order_risk_check(cmd);
+            }
+            OrderCommandType::MassCancel if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = if self.user_service.get_user(cmd.uid).is_some() {
+                    CommandResultCode::ValidForMatchingEngine
+                } else {
+                    CommandResultCode::AuthInvalidUser
+                };
+            }
+            OrderCommandType::MassQuote if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.mass_quote_risk_check(cmd);
+            }
+            OrderCommandType::Heartbeat if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.process_heartbeat(cmd);
+            }
+            OrderCommandType::SuspendUser if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.set_user_suspended(cmd.uid, true);
+            }
+            OrderCommandType::ResumeUser if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.set_user_suspended(cmd.uid, false);
+            }
+            OrderCommandType::KillSwitch if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.kill_switch(cmd);
+            }
+            OrderCommandType::AddUser if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = if self.user_service.add_user(cmd.uid) {
+                    CommandResultCode::Success
+                } else {
+                    CommandResultCode::UserMgmtUserAlreadyExists
+                };
+            }
+            OrderCommandType::BalanceAdjustment if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.user_service.balance_adjustment(
+                    cmd.uid,
+                    cmd.currency,
+                    cmd.amount,
+                    cmd.transaction_id,
+                );
+            }
+            OrderCommandType::UpdateMarkPrice => {
+                cmd.result_code = self.update_mark_price(cmd);
+            }
+            OrderCommandType::ExpireOption => {
+                cmd.result_code = self.expire_option(cmd);
+            }
+            OrderCommandType::SettleFutures => {
+                cmd.result_code = self.settle_futures(cmd);
+            }
+            OrderCommandType::Liquidate if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.prepare_liquidation_order(cmd);
+            }
+            OrderCommandType::InsuranceCover => {
+                cmd.result_code = self.apply_insurance_cover(cmd);
+            }
+            OrderCommandType::SetFeeTier => {
+                cmd.result_code = self.set_fee_tier(cmd);
+            }
+            OrderCommandType::SetUserFeeTier => {
+                cmd.result_code = self.set_user_fee_tier(cmd);
+            }
+            OrderCommandType::SetFeeOverride => {
+                cmd.result_code = self.set_fee_override(cmd);
             }
             _ => {}
         }
     }
 
-    fn place_order_risk_check(&mut self, cmd: &OrderCommand) -> CommandResultCode {
+    fn place_order_risk_check(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
         let Some(profile) = self.user_service.get_user_mut(cmd.uid) else {
             return CommandResultCode::AuthInvalidUser;
         };
+        if profile.suspended {
+            return CommandResultCode::UserMgmtUserSuspended;
+        }
 
         let Some(spec) = self.symbols.get(&cmd.symbol) else {
             return CommandResultCode::InvalidSymbol;
         };
 
-        let currency = match cmd.action {
-            OrderAction::Bid => spec.quote_currency,
-            OrderAction::Ask => spec.base_currency,
-        };
+        if Self::is_expired(spec, cmd.timestamp) {
+            return CommandResultCode::TradingHalted;
+        }
+
+        let result = spec.validate_order(cmd.price, cmd.size);
+        if result != CommandResultCode::Success {
+            return result;
+        }
+
+        // Giá trị danh nghĩa của lệnh phải biểu diễn được bằng i64,
+        // nhờ đó mọi khoản thanh toán của giao dịch khớp từ lệnh này đều không tràn số
+        if Self::order_notional(spec, cmd).is_none() {
+            return CommandResultCode::RiskNotionalOverflow;
+        }
 
-        let hold_amount = match cmd.action {
+        let fees = self.fee_schedule.fees(cmd.uid, spec);
+        if spec.symbol_type.is_futures() {
+            return Self::place_margin_order_risk_check(profile, spec, cmd, fees);
+        }
+        if spec.symbol_type.is_option() {
+            return Self::place_option_order_risk_check(profile, spec, cmd, fees);
+        }
+        let hold_fee = FeeSchedule::hold_fee(fees);
+
+        let (currency, hold_per_lot) = match cmd.action {
             OrderAction::Bid => {
                 let price = if matches!(cmd.order_type, OrderType::FokBudget | OrderType::IocBudget) {
                     cmd.price
                 } else {
                     cmd.reserve_price
                 };
-                cmd.size * price * spec.quote_scale_k + cmd.size * spec.taker_fee
+                let hold_per_lot = price.checked_mul(spec.quote_scale_k).and_then(|v| v.checked_add(hold_fee));
+                (spec.quote_currency, hold_per_lot)
             }
-            OrderAction::Ask => cmd.size * spec.base_scale_k,
+            OrderAction::Ask => (spec.base_currency, Some(spec.base_scale_k)),
+        };
+
+        let Some(hold_per_lot) = hold_per_lot else {
+            return CommandResultCode::RiskNotionalOverflow;
+        };
+        profile.place_hold(cmd, currency, hold_per_lot, fees)
+    }
+
+    /// Kiểm tra rủi ro sửa lệnh: tăng khối lượng thì giữ thêm phần tăng (cùng mức giữ mỗi lot của lệnh),
+    /// giảm khối lượng được hoàn khi engine khớp lệnh xác nhận (sự kiện Reduce).
+    /// Khoản giữ của lệnh là khối lượng còn lại của lệnh trên sổ; lệnh không có khoản giữ không được sửa.
+    fn amend_order_risk_check(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        cmd.hold_increase = 0;
+        let Some(profile) = self.user_service.get_user_mut(cmd.uid) else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+        if profile.suspended {
+            return CommandResultCode::UserMgmtUserSuspended;
+        }
+        let Some(spec) = self.symbols.get(&cmd.symbol) else {
+            return CommandResultCode::InvalidSymbol;
+        };
+        if Self::is_expired(spec, cmd.timestamp) {
+            return CommandResultCode::TradingHalted;
+        }
+        let Some(held) = profile.holds.get(&(cmd.symbol, cmd.order_id)).map(|hold| hold.size) else {
+            return CommandResultCode::MatchingUnknownOrderId;
         };
+        if cmd.size <= 0 {
+            return CommandResultCode::MatchingInvalidOrderSize;
+        }
+        if Self::order_notional(spec, cmd).is_none() {
+            return CommandResultCode::RiskNotionalOverflow;
+        }
+
+        let increase = cmd.size - held;
+        if increase <= 0 {
+            return CommandResultCode::ValidForMatchingEngine;
+        }
+        let result = profile.increase_hold(cmd.symbol, cmd.order_id, increase);
+        if result != CommandResultCode::ValidForMatchingEngine {
+            return result;
+        }
+        if spec.symbol_type.is_futures() || spec.symbol_type.is_option() {
+            let position = Self::position_entry(profile, spec);
+            match cmd.action {
+                OrderAction::Bid => position.pending_buy_size += increase,
+                OrderAction::Ask => position.pending_sell_size += increase,
+            }
+        }
+        cmd.hold_increase = increase;
+        CommandResultCode::ValidForMatchingEngine
+    }
 
-        let balance = profile.accounts.entry(currency).or_insert(0);
-        if *balance >= hold_amount {
-            *balance -= hold_amount;
-            CommandResultCode::ValidForMatchingEngine
-        } else {
-            CommandResultCode::RiskNsf
+    /// Kiểm tra rủi ro dời lệnh: khoản giữ không đổi, chỉ chặn dời lệnh trên công cụ đã tới hạn
+    /// (công cụ không tồn tại do engine khớp lệnh báo lỗi như trước)
+    fn move_order_risk_check(&self, cmd: &OrderCommand) -> CommandResultCode {
+        if self.symbols.get(&cmd.symbol).is_some_and(|spec| Self::is_expired(spec, cmd.timestamp)) {
+            return CommandResultCode::TradingHalted;
         }
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    /// Giá trị danh nghĩa (tiền quote) của lệnh theo giá cao hơn giữa giá lệnh và giá dự trữ,
+    /// None nếu tràn số (kể cả khối lượng quy đổi ra tiền base)
+    fn order_notional(spec: &CoreSymbolSpecification, cmd: &OrderCommand) -> Option<i64> {
+        cmd.size.checked_mul(spec.base_scale_k)?;
+        cmd.size
+            .checked_mul(cmd.price.max(cmd.reserve_price))?
+            .checked_mul(spec.quote_scale_k)
+    }
+
+    /// Chuyển kết quả tính bằng i128 về i64, chặn ở biên thay vì quay vòng
+    #[inline]
+    pub(super) fn saturating_i64(value: i128) -> i64 {
+        value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
     }
 
     // R2: Xử lý sau - Thanh toán
     pub fn post_process(&mut self, cmd: &mut OrderCommand) {
-        if cmd.matcher_events.is_empty() {
+        if matches!(cmd.command, OrderCommandType::MassCancel | OrderCommandType::KillSwitch) {
+            self.handle_mass_cancel_events(cmd);
+            return;
+        }
+        if cmd.command == OrderCommandType::MassQuote {
+            self.apply_mass_quote(cmd);
+            return;
+        }
+        if cmd.command == OrderCommandType::InsuranceCover {
+            self.credit_insurance_cover(cmd);
+            return;
+        }
+        if cmd.matcher_events.is_empty() && cmd.hold_increase == 0 {
             return;
         }
 
@@ -106,6 +319,16 @@ impl RiskEngine {
             return;
         };
 
+        // Sửa lệnh thất bại: hoàn phần đã giữ thêm khi kiểm tra rủi ro
+        if cmd.command == OrderCommandType::AmendOrder
+            && cmd.hold_increase > 0
+            && cmd.result_code != CommandResultCode::Success
+        {
+            let release = MatcherTradeEvent::new_reject(cmd.hold_increase, cmd.price);
+            self.handle_reject_event(cmd, &release, &spec);
+        }
+
+        self.collect_trade_fees(cmd, &spec);
         let taker_sell = cmd.action == OrderAction::Ask;
 
         for event in &cmd.matcher_events {
@@ -113,12 +336,33 @@ impl RiskEngine {
                 MatcherEventType::Trade => {
                     self.handle_trade_event(cmd, event, &spec, taker_sell);
                 }
+                MatcherEventType::AuctionTrade | MatcherEventType::TriggeredTrade(_) => {
+                    self.handle_auction_trade_event(event, &spec);
+                }
                 MatcherEventType::Reject | MatcherEventType::Reduce => {
-                    self.handle_reject_event(cmd, event, &spec, taker_sell);
+                    self.handle_reject_event(cmd, event, &spec);
                 }
+                MatcherEventType::GroupCancel(action) => {
+                    self.handle_group_cancel_event(event, action, &spec);
+                }
+                MatcherEventType::Funding
+                | MatcherEventType::Settlement
+                | MatcherEventType::Halt
+                | MatcherEventType::SessionChange(_)
+                | MatcherEventType::IcebergRefresh
+                | MatcherEventType::PostOnlyReprice
+                | MatcherEventType::MassCancel(_)
+                | MatcherEventType::InsuranceShortfall => {}
             }
         }
-        cmd.result_code = CommandResultCode::Success;
+
+        if cmd.command == OrderCommandType::Liquidate {
+            self.check_liquidation_shortfall(cmd, &spec);
+        }
+        // Giữ nguyên mã từ chối của engine khớp lệnh (sự kiện từ chối chỉ để hoàn phần đã giữ)
+        if cmd.result_code == CommandResultCode::ValidForMatchingEngine {
+            cmd.result_code = CommandResultCode::Success;
+        }
     }
 
     /// Xử lý sự kiện khớp lệnh
@@ -129,37 +373,93 @@ impl RiskEngine {
         spec: &CoreSymbolSpecification,
         taker_sell: bool,
     ) {
+        if spec.symbol_type.is_futures() {
+            self.handle_margin_trade_event(cmd, event, spec);
+            return;
+        }
+        if spec.symbol_type.is_option() {
+            self.handle_option_trade_event(cmd, event, spec);
+            return;
+        }
+
         // Thanh toán cho Taker
         if self.uid_for_this_shard(cmd.uid) {
             if let Some(taker) = self.user_service.get_user_mut(cmd.uid) {
-                if taker_sell {
-                    // Lệnh bán: Thu về tiền quote
-                    let amount = event.size * event.price * spec.quote_scale_k - event.size * spec.taker_fee;
-                    *taker.accounts.entry(spec.quote_currency).or_insert(0) += amount;
-                } else {
-                    // Lệnh mua: Hoàn lại chênh lệch giá + Thu về tiền base
-                    let price_diff = event.bidder_hold_price - event.price;
-                    let refund = event.size * price_diff * spec.quote_scale_k;
-                    *taker.accounts.entry(spec.quote_currency).or_insert(0) += refund;
-                    *taker.accounts.entry(spec.base_currency).or_insert(0) += event.size * spec.base_scale_k;
-                }
+                let action = if taker_sell { OrderAction::Ask } else { OrderAction::Bid };
+                Self::settle_spot_trade(taker, spec, action, cmd.order_id, event, event.taker_fee);
             }
         }
 
         // Thanh toán cho Maker
         if self.uid_for_this_shard(event.matched_order_uid) {
             if let Some(maker) = self.user_service.get_user_mut(event.matched_order_uid) {
-                if taker_sell {
-                    // Taker bán => Maker mua
-                    let price_diff = event.bidder_hold_price - event.price;
-                    let refund = event.size * price_diff * spec.quote_scale_k;
-                    *maker.accounts.entry(spec.quote_currency).or_insert(0) += refund;
-                    *maker.accounts.entry(spec.base_currency).or_insert(0) += event.size * spec.base_scale_k;
-                } else {
-                    // Taker mua => Maker bán
-                    let amount = event.size * event.price * spec.quote_scale_k - event.size * spec.maker_fee;
-                    *maker.accounts.entry(spec.quote_currency).or_insert(0) += amount;
-                }
+                let action = if taker_sell { OrderAction::Bid } else { OrderAction::Ask };
+                Self::settle_spot_trade(maker, spec, action, event.matched_order_id, event, event.maker_fee);
+            }
+        }
+    }
+
+    /// Khớp đấu giá giữa hai lệnh đang treo (hoặc khớp của lệnh được kích hoạt):
+    /// thanh toán như giao dịch có lệnh mua ở vai trò taker
+    fn handle_auction_trade_event(&mut self, event: &MatcherTradeEvent, spec: &CoreSymbolSpecification) {
+        let bid = OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid: event.bid_uid,
+            order_id: event.bid_order_id,
+            symbol: spec.symbol_id,
+            action: OrderAction::Bid,
+            ..Default::default()
+        };
+        self.handle_trade_event(&bid, event, spec, false);
+    }
+
+    /// Lệnh cùng nhóm bị hủy theo lệnh khác: hoàn khoản giữ như khi chính lệnh đó bị hủy
+    fn handle_group_cancel_event(&mut self, event: &MatcherTradeEvent, action: OrderAction, spec: &CoreSymbolSpecification) {
+        let leg = OrderCommand {
+            command: OrderCommandType::CancelOrder,
+            uid: event.matched_order_uid,
+            order_id: event.matched_order_id,
+            symbol: spec.symbol_id,
+            action,
+            ..Default::default()
+        };
+        self.handle_reject_event(&leg, event, spec);
+    }
+
+    /// Hủy hàng loạt / kill switch: mỗi sự kiện mang công cụ của lệnh bị hủy, hoàn khoản giữ như khi hủy từng lệnh
+    fn handle_mass_cancel_events(&mut self, cmd: &mut OrderCommand) {
+        for event in &cmd.matcher_events {
+            let MatcherEventType::MassCancel(action) = event.event_type else {
+                continue;
+            };
+            if let Some(spec) = self.symbols.get(&event.symbol).cloned() {
+                self.handle_group_cancel_event(event, action, &spec);
+            }
+        }
+        if cmd.result_code == CommandResultCode::ValidForMatchingEngine {
+            cmd.result_code = CommandResultCode::Success;
+        }
+    }
+
+    /// Thanh toán giao ngay của một bên: lấy phần đã giữ của khối lượng khớp,
+    /// bên mua được hoàn phần giữ dư (chênh lệch giá, phí) và nhận tiền base, bên bán nhận tiền quote
+    fn settle_spot_trade(
+        profile: &mut UserProfile,
+        spec: &CoreSymbolSpecification,
+        action: OrderAction,
+        order_id: OrderId,
+        event: &MatcherTradeEvent,
+        fee: i64,
+    ) {
+        let held = profile.take_hold(spec.symbol_id, order_id, event.size).unwrap_or(0) as i128;
+        let notional = (event.size as i128 * event.price as i128).saturating_mul(spec.quote_scale_k as i128);
+        match action {
+            OrderAction::Bid => {
+                profile.credit(spec.quote_currency, held - notional - fee as i128);
+                profile.credit(spec.base_currency, event.size as i128 * spec.base_scale_k as i128);
+            }
+            OrderAction::Ask => {
+                profile.credit(spec.quote_currency, notional - fee as i128);
             }
         }
     }
@@ -170,7 +470,6 @@ impl RiskEngine {
         cmd: &OrderCommand,
         event: &MatcherTradeEvent,
         spec: &CoreSymbolSpecification,
-        taker_sell: bool,
     ) {
         if !self.uid_for_this_shard(cmd.uid) {
             return;
@@ -180,14 +479,18 @@ impl RiskEngine {
             return;
         };
 
-        // Hoàn lại tiền đã đóng băng
-        if taker_sell {
-            let refund = event.size * spec.base_scale_k;
-            *profile.accounts.entry(spec.base_currency).or_insert(0) += refund;
-        } else {
-            let refund = event.size * event.bidder_hold_price * spec.quote_scale_k + event.size * spec.taker_fee;
-            *profile.accounts.entry(spec.quote_currency).or_insert(0) += refund;
+        if spec.symbol_type.is_futures() || spec.symbol_type.is_option() {
+            Self::release_position_order_hold(profile, spec, cmd.action, cmd.order_id, event.size);
+            return;
+        }
+
+        // Hoàn lại đúng phần đã giữ của khối lượng bị hủy/từ chối
+        if let Some(released) = profile.take_hold(spec.symbol_id, cmd.order_id, event.size) {
+            let currency = match cmd.action {
+                OrderAction::Bid => spec.quote_currency,
+                OrderAction::Ask => spec.base_currency,
+            };
+            profile.credit(currency, released as i128);
         }
     }
 }
-
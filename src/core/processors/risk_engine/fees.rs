@@ -0,0 +1,175 @@
+use super::RiskEngine;
+use crate::api::*;
+use ahash::AHashMap;
+use serde::{Deserialize, Serialize};
+
+/// Biểu phí, được sao chép trên mọi phân đoạn rủi ro (lệnh quản trị phí chạy trên mọi phân đoạn)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    tiers: AHashMap<u32, FeeTier>,
+    user_tiers: AHashMap<UserId, u32>,
+    overrides: AHashMap<(UserId, SymbolId), FeeOverride>,
+}
+
+impl FeeSchedule {
+    /// Phí (taker, maker) theo lot của người dùng: phí riêng > cấp phí > phí của công cụ.
+    /// Phí theo cấp tính bằng i128 rồi giới hạn về i64 để phí lớn nhân tỷ lệ lớn không tràn số
+    pub fn fees(&self, uid: UserId, spec: &CoreSymbolSpecification) -> (i64, i64) {
+        if let Some(fee) = self.overrides.get(&(uid, spec.symbol_id)) {
+            return (fee.taker_fee, fee.maker_fee);
+        }
+        if let Some(tier) = self.user_tiers.get(&uid).and_then(|id| self.tiers.get(id)) {
+            return (
+                Self::scale_fee(spec.taker_fee, tier.taker_fee_rate),
+                Self::scale_fee(spec.maker_fee, tier.maker_fee_rate),
+            );
+        }
+        (spec.taker_fee, spec.maker_fee)
+    }
+
+    fn scale_fee(fee: i64, rate: i64) -> i64 {
+        RiskEngine::saturating_i64(fee as i128 * rate as i128 / FEE_RATE_SCALE as i128)
+    }
+
+    /// Phí giữ theo lot khi đặt lệnh, đủ cho cả khi lệnh khớp ở vai trò taker lẫn maker theo phí (taker, maker)
+    /// của lệnh. Phí được ghi cùng khoản giữ, nên đổi biểu phí khi lệnh đang treo không làm sai phần hoàn.
+    pub fn hold_fee((taker_fee, maker_fee): (i64, i64)) -> i64 {
+        taker_fee.max(maker_fee).max(0)
+    }
+}
+
+impl RiskEngine {
+    /// Đặt tài khoản thu phí chung của sàn (phân đoạn sở hữu sẽ tạo hồ sơ nếu chưa có)
+    pub fn set_fee_account_uid(&mut self, uid: Option<UserId>) {
+        self.fee_account_uid = uid;
+        if let Some(uid) = uid {
+            self.add_fee_account(uid);
+        }
+    }
+
+    /// Đặt tài khoản thu phí riêng của một loại tiền (phí bằng loại tiền khác vẫn vào tài khoản chung)
+    pub fn set_currency_fee_account(&mut self, currency: Currency, uid: UserId) {
+        self.fee_accounts.insert(currency, uid);
+        self.add_fee_account(uid);
+    }
+
+    fn add_fee_account(&mut self, uid: UserId) {
+        if self.uid_for_this_shard(uid) {
+            self.user_service.add_user(uid);
+        }
+    }
+
+    /// Tài khoản nhận phí thu bằng loại tiền
+    fn fee_account(&self, currency: Currency) -> Option<UserId> {
+        self.fee_accounts.get(&currency).copied().or(self.fee_account_uid)
+    }
+
+    /// Định nghĩa / xóa cấp phí (mọi phân đoạn giữ cùng biểu phí)
+    pub(super) fn set_fee_tier(&mut self, cmd: &OrderCommand) -> CommandResultCode {
+        let Some(tier_id) = cmd.fee_tier_id else {
+            return CommandResultCode::InvalidFeeTier;
+        };
+        match cmd.fee_tier {
+            Some(tier) => self.fee_schedule.tiers.insert(tier_id, tier),
+            None => self.fee_schedule.tiers.remove(&tier_id),
+        };
+        CommandResultCode::Success
+    }
+
+    /// Gán cấp phí đã định nghĩa cho người dùng (None = quay về phí của công cụ)
+    pub(super) fn set_user_fee_tier(&mut self, cmd: &OrderCommand) -> CommandResultCode {
+        match cmd.fee_tier_id {
+            Some(tier_id) if !self.fee_schedule.tiers.contains_key(&tier_id) => {
+                return CommandResultCode::InvalidFeeTier;
+            }
+            Some(tier_id) => self.fee_schedule.user_tiers.insert(cmd.uid, tier_id),
+            None => self.fee_schedule.user_tiers.remove(&cmd.uid),
+        };
+        CommandResultCode::Success
+    }
+
+    /// Đặt / xóa phí riêng của người dùng trên công cụ
+    pub(super) fn set_fee_override(&mut self, cmd: &OrderCommand) -> CommandResultCode {
+        if !self.symbols.contains_key(&cmd.symbol) {
+            return CommandResultCode::InvalidSymbol;
+        }
+        match cmd.fee_override {
+            Some(fee) => self.fee_schedule.overrides.insert((cmd.uid, cmd.symbol), fee),
+            None => self.fee_schedule.overrides.remove(&(cmd.uid, cmd.symbol)),
+        };
+        CommandResultCode::Success
+    }
+
+    /// Ghi phí vào từng sự kiện khớp lệnh, chạy trên mọi phân đoạn trước R2: mỗi phân đoạn ghi phí của các bên
+    /// thuộc mình theo phí đã ghi cùng khoản giữ của lệnh (lệnh không có khoản giữ, như lệnh thanh lý, theo biểu phí
+    /// hiện tại). Nhờ đó R2 của mọi phân đoạn thấy cùng phí dù khoản giữ chỉ nằm ở phân đoạn sở hữu.
+    /// Khớp đấu giá không có bên chủ động: cả hai bên chịu phí maker (bên mua ghi ở taker_fee).
+    /// Khớp của lệnh được kích hoạt: lệnh được kích hoạt chịu phí taker, lệnh đang treo chịu phí maker.
+    pub fn assign_trade_fees(&self, cmd: &mut OrderCommand) {
+        let Some(spec) = self.symbols.get(&cmd.symbol) else {
+            return;
+        };
+        for event in cmd.matcher_events.iter_mut() {
+            // (người dùng, lệnh, chịu phí taker) của bên ghi ở taker_fee và bên ghi ở maker_fee
+            let (first, second) = match event.event_type {
+                MatcherEventType::Trade => (
+                    (cmd.uid, cmd.order_id, true),
+                    (event.matched_order_uid, event.matched_order_id, false),
+                ),
+                MatcherEventType::AuctionTrade => (
+                    (event.bid_uid, event.bid_order_id, false),
+                    (event.matched_order_uid, event.matched_order_id, false),
+                ),
+                MatcherEventType::TriggeredTrade(action) => (
+                    (event.bid_uid, event.bid_order_id, action == OrderAction::Bid),
+                    (event.matched_order_uid, event.matched_order_id, action == OrderAction::Ask),
+                ),
+                _ => continue,
+            };
+            if let Some(fee) = self.order_fee(first, spec) {
+                event.taker_fee = Self::saturating_i64(event.size as i128 * fee as i128);
+            }
+            if let Some(fee) = self.order_fee(second, spec) {
+                event.maker_fee = Self::saturating_i64(event.size as i128 * fee as i128);
+            }
+        }
+    }
+
+    /// Phí theo lot của một bên giao dịch, None nếu người dùng không thuộc phân đoạn này
+    fn order_fee(&self, (uid, order_id, taker): (UserId, OrderId, bool), spec: &CoreSymbolSpecification) -> Option<i64> {
+        if !self.uid_for_this_shard(uid) {
+            return None;
+        }
+        let (taker_fee, maker_fee) = self
+            .user_service
+            .get_user(uid)
+            .and_then(|profile| profile.holds.get(&(spec.symbol_id, order_id)))
+            .map_or_else(|| self.fee_schedule.fees(uid, spec), |hold| hold.fees);
+        Some(if taker { taker_fee } else { maker_fee })
+    }
+
+    /// Cộng tổng phí đã ghi vào các sự kiện khớp lệnh vào tài khoản thu phí của tiền quote (nếu thuộc phân đoạn này)
+    pub(super) fn collect_trade_fees(&mut self, cmd: &OrderCommand, spec: &CoreSymbolSpecification) {
+        let collected = cmd
+            .matcher_events
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event.event_type,
+                    MatcherEventType::Trade | MatcherEventType::AuctionTrade | MatcherEventType::TriggeredTrade(_)
+                )
+            })
+            .fold(0i64, |total, event| total.saturating_add(event.taker_fee).saturating_add(event.maker_fee));
+
+        let Some(fee_uid) = self.fee_account(spec.quote_currency) else {
+            return;
+        };
+        if collected == 0 || !self.uid_for_this_shard(fee_uid) {
+            return;
+        }
+        if let Some(account) = self.user_service.get_user_mut(fee_uid) {
+            let balance = account.accounts.entry(spec.quote_currency).or_insert(0);
+            *balance = balance.saturating_add(collected);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use super::RiskEngine;
+use crate::api::*;
+use serde::{Deserialize, Serialize};
+
+/// Trạng thái giá đánh dấu và funding của một công cụ ký quỹ
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarkPriceState {
+    pub mark_price: Price,
+    pub index_price: Price,
+    pub funding_rate: i64,      // Theo FUNDING_RATE_SCALE, dương = long trả cho short
+    pub next_funding_time: i64, // Mốc thanh toán funding kế tiếp (0 = chưa lên lịch)
+}
+
+impl RiskEngine {
+    /// Cập nhật giá đánh dấu / giá chỉ số / tỷ lệ funding.
+    /// Với hợp đồng vĩnh viễn, khi timestamp của lệnh vượt qua mốc funding thì thanh toán funding.
+    pub(super) fn update_mark_price(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(spec) = self.symbols.get(&cmd.symbol).cloned() else {
+            return CommandResultCode::InvalidSymbol;
+        };
+
+        if !spec.symbol_type.is_futures() {
+            return CommandResultCode::UnsupportedSymbolType;
+        }
+
+        let state = self.mark_prices.entry(spec.symbol_id).or_default();
+        state.mark_price = cmd.price;
+        if let Some(index_price) = cmd.index_price {
+            state.index_price = index_price;
+        }
+        if let Some(rate) = cmd.funding_rate {
+            state.funding_rate = rate;
+        }
+
+        if spec.symbol_type == SymbolType::PerpetualSwap && spec.funding_interval > 0 {
+            self.schedule_funding(&spec, cmd);
+        }
+
+        self.check_liquidations(&spec, cmd);
+        CommandResultCode::Success
+    }
+
+    /// Thanh toán funding nếu timestamp của lệnh đã vượt qua mốc funding kế tiếp.
+    /// Nếu lệnh cập nhật đến trễ qua nhiều mốc, mọi chu kỳ đã trôi qua đều được thanh toán
+    /// (theo giá đánh dấu và tỷ lệ hiện tại, do không có giá của từng mốc bị lỡ)
+    fn schedule_funding(&mut self, spec: &CoreSymbolSpecification, cmd: &mut OrderCommand) {
+        let state = self.mark_prices.entry(spec.symbol_id).or_default();
+        let next_boundary = (cmd.timestamp / spec.funding_interval + 1) * spec.funding_interval;
+        if state.next_funding_time == 0 {
+            // Lần cập nhật đầu tiên chỉ lên lịch, chưa thanh toán
+            state.next_funding_time = next_boundary;
+        } else if cmd.timestamp >= state.next_funding_time {
+            let intervals = (cmd.timestamp - state.next_funding_time) / spec.funding_interval + 1;
+            state.next_funding_time = next_boundary;
+            let (mark_price, rate) = (state.mark_price, state.funding_rate);
+            self.settle_funding(spec, mark_price, rate, intervals, cmd);
+        }
+    }
+
+    /// Thanh toán funding của `intervals` chu kỳ giữa long và short của phân đoạn này (một sự kiện mỗi người dùng).
+    /// Số tiền tính theo lot rồi nhân với khối lượng, nên tổng long trả luôn bằng tổng short nhận.
+    fn settle_funding(
+        &mut self,
+        spec: &CoreSymbolSpecification,
+        mark_price: Price,
+        rate: i64,
+        intervals: i64,
+        cmd: &mut OrderCommand,
+    ) {
+        let per_lot = (mark_price as i128 * spec.quote_scale_k as i128 * rate as i128 / FUNDING_RATE_SCALE as i128)
+            .saturating_mul(intervals as i128);
+        if per_lot == 0 {
+            return;
+        }
+
+        let mut events = Vec::new();
+        for profile in self.user_service.profiles_mut() {
+            let Some(position) = profile.positions.get(&spec.symbol_id) else {
+                continue;
+            };
+            let net_volume = position.net_volume();
+            if net_volume == 0 {
+                continue;
+            }
+
+            let amount = Self::saturating_i64((-(net_volume as i128)).saturating_mul(per_lot));
+            profile.credit(spec.quote_currency, amount as i128);
+            events.push(MatcherTradeEvent::new_funding(profile.uid, net_volume, mark_price, amount));
+        }
+
+        Self::merge_user_events(cmd, events);
+    }
+}
@@ -0,0 +1,93 @@
+use super::RiskEngine;
+use crate::api::*;
+use serde::{Deserialize, Serialize};
+
+/// Hạn heartbeat của một phiên kết nối (người dùng có thể mở nhiều phiên, mỗi phiên đăng ký riêng).
+/// Lệnh đầu tiên có timestamp vượt quá `deadline` khiến các lệnh đặt từ phiên đó bị hủy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Heartbeat {
+    pub timeout: i64,
+    pub deadline: i64,
+}
+
+impl RiskEngine {
+    pub fn get_heartbeat(&self, uid: UserId, session_id: SessionId) -> Option<&Heartbeat> {
+        self.heartbeats.get(&(uid, session_id))
+    }
+
+    /// R1 của heartbeat cho phiên cmd.session_id: đăng ký / đổi thời hạn (heartbeat_timeout > 0),
+    /// hủy đăng ký (<= 0) hoặc gia hạn theo thời hạn đã đăng ký (None)
+    pub(super) fn process_heartbeat(&mut self, cmd: &OrderCommand) -> CommandResultCode {
+        if self.user_service.get_user(cmd.uid).is_none() {
+            return CommandResultCode::AuthInvalidUser;
+        }
+        let key = (cmd.uid, cmd.session_id);
+        let timeout = match cmd.heartbeat_timeout {
+            Some(timeout) if timeout <= 0 => {
+                self.heartbeats.remove(&key);
+                return CommandResultCode::Success;
+            }
+            Some(timeout) => timeout,
+            None => match self.heartbeats.get(&key) {
+                Some(heartbeat) => heartbeat.timeout,
+                None => return CommandResultCode::UserMgmtHeartbeatNotRegistered,
+            },
+        };
+        let deadline = cmd.timestamp.saturating_add(timeout);
+        self.heartbeats.insert(key, Heartbeat { timeout, deadline });
+        CommandResultCode::Success
+    }
+
+    /// Phiên của người dùng thuộc phân đoạn này quá hạn heartbeat tại thời điểm `timestamp`
+    /// (theo thứ tự uid, mã phiên). Đăng ký của phiên bị xóa: phiên coi như đã ngắt kết nối
+    pub fn take_expired_heartbeats(&mut self, timestamp: i64) -> Vec<(UserId, SessionId)> {
+        let mut sessions: Vec<(UserId, SessionId)> = self
+            .heartbeats
+            .iter()
+            .filter(|(_, heartbeat)| timestamp > heartbeat.deadline)
+            .map(|(&key, _)| key)
+            .collect();
+        sessions.sort_unstable();
+        for key in &sessions {
+            self.heartbeats.remove(key);
+        }
+        sessions
+    }
+
+    /// Lệnh chờ của người dùng đặt từ phiên (theo thứ tự công cụ, ID lệnh)
+    pub fn session_orders(&self, uid: UserId, session_id: SessionId) -> Vec<(SymbolId, OrderId)> {
+        let Some(profile) = self.user_service.get_user(uid) else {
+            return Vec::new();
+        };
+        let mut orders: Vec<(SymbolId, OrderId)> = profile
+            .holds
+            .iter()
+            .filter(|(_, hold)| hold.session_id == session_id)
+            .map(|(&key, _)| key)
+            .collect();
+        orders.sort_unstable();
+        orders
+    }
+
+    /// Khóa / mở khóa người dùng: người dùng bị khóa không được đặt hoặc sửa lệnh
+    pub(super) fn set_user_suspended(&mut self, uid: UserId, suspended: bool) -> CommandResultCode {
+        let Some(profile) = self.user_service.get_user_mut(uid) else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+        profile.suspended = suspended;
+        CommandResultCode::Success
+    }
+
+    /// R1 của kill switch: khóa người dùng, bỏ đăng ký heartbeat của mọi phiên và hủy mọi lệnh trên mọi công cụ
+    /// (bộ lọc của hủy hàng loạt bị bỏ qua)
+    pub(super) fn kill_switch(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let result = self.set_user_suspended(cmd.uid, true);
+        if result != CommandResultCode::Success {
+            return result;
+        }
+        self.heartbeats.retain(|&(uid, _), _| uid != cmd.uid);
+        cmd.mass_cancel_symbol = None;
+        cmd.mass_cancel_action = None;
+        CommandResultCode::ValidForMatchingEngine
+    }
+}
@@ -0,0 +1,150 @@
+use super::RiskEngine;
+use crate::api::*;
+use crate::core::users::SymbolPositionRecord;
+
+impl RiskEngine {
+    /// Vị thế không còn đủ ký quỹ duy trì tại giá đánh dấu:
+    /// ký quỹ vị thế + lãi/lỗ chưa thực hiện < khối lượng × ký quỹ duy trì theo lot
+    fn is_under_margined(position: &SymbolPositionRecord, spec: &CoreSymbolSpecification, mark_price: Price) -> bool {
+        let net_volume = position.net_volume();
+        if net_volume == 0 {
+            return false;
+        }
+
+        let position_margin = position.open_volume_long as i128 * spec.margin_buy as i128
+            + position.open_volume_short as i128 * spec.margin_sell as i128;
+        let equity = position_margin + position.unrealized_pnl(mark_price) * spec.quote_scale_k as i128;
+        equity < net_volume.unsigned_abs() as i128 * spec.maintenance_margin as i128
+    }
+
+    /// Kiểm tra ký quỹ duy trì của người dùng thuộc phân đoạn này theo giá đánh dấu mới,
+    /// xếp lệnh thanh lý nội bộ cho các vị thế thiếu ký quỹ (theo thứ tự uid)
+    pub(super) fn check_liquidations(&mut self, spec: &CoreSymbolSpecification, cmd: &OrderCommand) {
+        if spec.maintenance_margin <= 0 {
+            return;
+        }
+
+        let mut uids: Vec<UserId> = self
+            .user_service
+            .profiles()
+            .filter(|profile| {
+                profile
+                    .positions
+                    .get(&spec.symbol_id)
+                    .is_some_and(|position| Self::is_under_margined(position, spec, cmd.price))
+            })
+            .map(|profile| profile.uid)
+            .collect();
+        uids.sort_unstable();
+
+        for uid in uids {
+            self.pending_commands.push(OrderCommand {
+                command: OrderCommandType::Liquidate,
+                uid,
+                symbol: spec.symbol_id,
+                timestamp: cmd.timestamp,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// R1 của lệnh thanh lý: kiểm tra lại điều kiện thanh lý rồi biến lệnh thành lệnh IOC
+    /// đóng toàn bộ vị thế ròng, không giới hạn giá và không giữ ký quỹ
+    pub(super) fn prepare_liquidation_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(spec) = self.symbols.get(&cmd.symbol) else {
+            return CommandResultCode::InvalidSymbol;
+        };
+        // Công cụ đã ngừng giao dịch chờ quyết toán: không thanh lý qua sổ lệnh
+        if Self::is_expired(spec, cmd.timestamp) {
+            return CommandResultCode::Success;
+        }
+        let Some(mark_price) = self.mark_prices.get(&cmd.symbol).map(|s| s.mark_price) else {
+            return CommandResultCode::Success;
+        };
+        let Some(profile) = self.user_service.get_user(cmd.uid) else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+        let Some(position) = profile.positions.get(&cmd.symbol) else {
+            return CommandResultCode::Success;
+        };
+
+        if !Self::is_under_margined(position, spec, mark_price) {
+            return CommandResultCode::Success;
+        }
+
+        let net_volume = position.net_volume();
+        cmd.size = net_volume.abs();
+        cmd.order_type = OrderType::Ioc;
+        if net_volume > 0 {
+            cmd.action = OrderAction::Ask;
+            cmd.price = 0;
+        } else {
+            cmd.action = OrderAction::Bid;
+            cmd.price = Price::MAX;
+        }
+        cmd.reserve_price = cmd.price;
+
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    /// R2 của lệnh thanh lý: nếu số dư quote bị âm thì xếp lệnh quỹ bảo hiểm bù thâm hụt
+    pub(super) fn check_liquidation_shortfall(&mut self, cmd: &OrderCommand, spec: &CoreSymbolSpecification) {
+        if !self.uid_for_this_shard(cmd.uid) || self.insurance_fund_uid.is_none() {
+            return;
+        }
+
+        let Some(profile) = self.user_service.get_user(cmd.uid) else {
+            return;
+        };
+
+        let balance = profile.accounts.get(&spec.quote_currency).copied().unwrap_or(0);
+        if balance < 0 {
+            self.pending_commands.push(OrderCommand {
+                command: OrderCommandType::InsuranceCover,
+                uid: cmd.uid,
+                currency: spec.quote_currency,
+                amount: -balance,
+                timestamp: cmd.timestamp,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// R1 của lệnh bù thâm hụt: phân đoạn sở hữu quỹ bảo hiểm trừ quỹ, tối đa bằng số dư hiện có.
+    /// amount của lệnh được giảm còn phần quỹ thực bù (R2 của phân đoạn người dùng ghi có đúng phần này),
+    /// phần quỹ không bù được được báo bằng sự kiện InsuranceShortfall và vẫn là số dư âm của người dùng
+    pub(super) fn apply_insurance_cover(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(fund_uid) = self.insurance_fund_uid else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+        if !self.uid_for_this_shard(fund_uid) {
+            return CommandResultCode::Success;
+        }
+
+        let covered = match self.user_service.get_user_mut(fund_uid) {
+            Some(fund) => {
+                let available = fund.accounts.entry(cmd.currency).or_insert(0);
+                let covered = cmd.amount.min((*available).max(0));
+                *available -= covered;
+                covered
+            }
+            None => 0,
+        };
+
+        if covered < cmd.amount {
+            cmd.matcher_events.push(MatcherTradeEvent::new_insurance_shortfall(cmd.uid, cmd.amount - covered));
+        }
+        cmd.amount = covered;
+        CommandResultCode::Success
+    }
+
+    /// R2 của lệnh bù thâm hụt: ghi có cho người dùng phần quỹ bảo hiểm đã trừ ở R1
+    pub(super) fn credit_insurance_cover(&mut self, cmd: &OrderCommand) {
+        if cmd.result_code != CommandResultCode::Success || !self.uid_for_this_shard(cmd.uid) {
+            return;
+        }
+        if let Some(profile) = self.user_service.get_user_mut(cmd.uid) {
+            profile.credit(cmd.currency, cmd.amount as i128);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use super::{FeeSchedule, RiskEngine};
+use crate::api::*;
+use crate::core::users::{SymbolPositionRecord, UserProfile};
+
+impl RiskEngine {
+    /// Ký quỹ ban đầu theo lot của một chiều giao dịch
+    #[inline]
+    pub(super) fn margin_per_lot(spec: &CoreSymbolSpecification, action: OrderAction) -> i64 {
+        match action {
+            OrderAction::Bid => spec.margin_buy,
+            OrderAction::Ask => spec.margin_sell,
+        }
+    }
+
+    /// Kiểm tra rủi ro lệnh ký quỹ: giữ (ký quỹ + phí) theo lot bằng tiền quote
+    pub(super) fn place_margin_order_risk_check(
+        profile: &mut UserProfile,
+        spec: &CoreSymbolSpecification,
+        cmd: &OrderCommand,
+        fees: (i64, i64),
+    ) -> CommandResultCode {
+        let Some(hold_per_lot) = Self::margin_per_lot(spec, cmd.action).checked_add(FeeSchedule::hold_fee(fees)) else {
+            return CommandResultCode::RiskNotionalOverflow;
+        };
+        let result = profile.place_hold(cmd, spec.quote_currency, hold_per_lot, fees);
+        if result != CommandResultCode::ValidForMatchingEngine {
+            return result;
+        }
+
+        let position = Self::position_entry(profile, spec);
+        match cmd.action {
+            OrderAction::Bid => position.pending_buy_size += cmd.size,
+            OrderAction::Ask => position.pending_sell_size += cmd.size,
+        }
+
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    /// Thanh toán giao dịch ký quỹ cho cả taker và maker
+    pub(super) fn handle_margin_trade_event(
+        &mut self,
+        cmd: &OrderCommand,
+        event: &MatcherTradeEvent,
+        spec: &CoreSymbolSpecification,
+    ) {
+        if self.uid_for_this_shard(cmd.uid) {
+            if let Some(taker) = self.user_service.get_user_mut(cmd.uid) {
+                Self::settle_margin_trade(taker, spec, cmd.action, cmd.order_id, event, event.taker_fee);
+            }
+        }
+
+        if self.uid_for_this_shard(event.matched_order_uid) {
+            if let Some(maker) = self.user_service.get_user_mut(event.matched_order_uid) {
+                let order_id = event.matched_order_id;
+                Self::settle_margin_trade(maker, spec, cmd.action.opposite(), order_id, event, event.maker_fee);
+            }
+        }
+    }
+
+    /// Cập nhật vị thế và số dư của một bên giao dịch.
+    /// Phần giữ của khối lượng khớp được lấy ra; phần mở vị thế khóa lại ký quỹ làm ký quỹ vị thế,
+    /// phần đóng vị thế mở khóa ký quỹ vị thế tương ứng và ghi nhận lãi/lỗ thực hiện.
+    /// Lệnh thanh lý không có khoản giữ (không giữ ký quỹ lệnh khi đặt).
+    fn settle_margin_trade(
+        profile: &mut UserProfile,
+        spec: &CoreSymbolSpecification,
+        action: OrderAction,
+        order_id: OrderId,
+        event: &MatcherTradeEvent,
+        fee: i64,
+    ) {
+        let size = event.size;
+        let held = profile.take_hold(spec.symbol_id, order_id, size);
+        let position = Self::position_entry(profile, spec);
+        if held.is_some() {
+            match action {
+                OrderAction::Bid => position.pending_buy_size -= size,
+                OrderAction::Ask => position.pending_sell_size -= size,
+            }
+        }
+        let (closed, pnl) = position.update_for_trade(action, size, event.price);
+        let is_empty = position.is_empty();
+
+        profile.credit(
+            spec.quote_currency,
+            held.unwrap_or(0) as i128 - fee as i128 + pnl as i128 * spec.quote_scale_k as i128,
+        );
+        let position_margin = Self::saturating_i64(
+            (size - closed) as i128 * Self::margin_per_lot(spec, action) as i128
+                - closed as i128 * Self::margin_per_lot(spec, action.opposite()) as i128,
+        );
+        profile.lock(spec.quote_currency, position_margin);
+
+        if is_empty {
+            profile.positions.remove(&spec.symbol_id);
+        }
+    }
+
+    /// Hoàn lại phần đã giữ của lệnh ký quỹ/quyền chọn bị hủy/từ chối
+    pub(super) fn release_position_order_hold(
+        profile: &mut UserProfile,
+        spec: &CoreSymbolSpecification,
+        action: OrderAction,
+        order_id: OrderId,
+        size: Size,
+    ) {
+        let Some(released) = profile.take_hold(spec.symbol_id, order_id, size) else {
+            return;
+        };
+        profile.credit(spec.quote_currency, released as i128);
+
+        if let Some(position) = profile.positions.get_mut(&spec.symbol_id) {
+            match action {
+                OrderAction::Bid => position.pending_buy_size -= size,
+                OrderAction::Ask => position.pending_sell_size -= size,
+            }
+            if position.is_empty() {
+                profile.positions.remove(&spec.symbol_id);
+            }
+        }
+    }
+
+    pub(super) fn position_entry<'a>(
+        profile: &'a mut UserProfile,
+        spec: &CoreSymbolSpecification,
+    ) -> &'a mut SymbolPositionRecord {
+        let uid = profile.uid;
+        profile
+            .positions
+            .entry(spec.symbol_id)
+            .or_insert_with(|| SymbolPositionRecord::new(uid, spec.symbol_id, spec.quote_currency))
+    }
+}
@@ -0,0 +1,158 @@
+use super::{FeeSchedule, RiskEngine};
+use crate::api::*;
+use crate::core::users::UserProfile;
+
+impl RiskEngine {
+    /// Kiểm tra rủi ro lệnh quyền chọn.
+    /// Bên mua giữ phí quyền chọn (giá × lot) + phí giao dịch; bên bán giữ tài sản đảm bảo margin_sell theo lot.
+    /// Giá dự trữ luôn bằng giá lệnh, khớp giá thấp hơn thì bên mua được hoàn phần chênh lệch.
+    pub(super) fn place_option_order_risk_check(
+        profile: &mut UserProfile,
+        spec: &CoreSymbolSpecification,
+        cmd: &mut OrderCommand,
+        fees: (i64, i64),
+    ) -> CommandResultCode {
+        cmd.reserve_price = cmd.price;
+        let Some(hold_per_lot) = Self::option_hold_per_lot(spec, cmd.action, cmd.price, FeeSchedule::hold_fee(fees)) else {
+            return CommandResultCode::RiskNotionalOverflow;
+        };
+        let result = profile.place_hold(cmd, spec.quote_currency, hold_per_lot, fees);
+        if result != CommandResultCode::ValidForMatchingEngine {
+            return result;
+        }
+
+        let position = Self::position_entry(profile, spec);
+        match cmd.action {
+            OrderAction::Bid => position.pending_buy_size += cmd.size,
+            OrderAction::Ask => position.pending_sell_size += cmd.size,
+        }
+
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    #[inline]
+    fn option_hold_per_lot(spec: &CoreSymbolSpecification, action: OrderAction, price: Price, hold_fee: i64) -> Option<i64> {
+        match action {
+            OrderAction::Bid => price.checked_mul(spec.quote_scale_k)?.checked_add(hold_fee),
+            OrderAction::Ask => spec.margin_sell.checked_add(hold_fee),
+        }
+    }
+
+    /// Thanh toán giao dịch quyền chọn cho cả taker và maker
+    pub(super) fn handle_option_trade_event(
+        &mut self,
+        cmd: &OrderCommand,
+        event: &MatcherTradeEvent,
+        spec: &CoreSymbolSpecification,
+    ) {
+        if self.uid_for_this_shard(cmd.uid) {
+            if let Some(taker) = self.user_service.get_user_mut(cmd.uid) {
+                Self::settle_option_trade(taker, spec, cmd.action, cmd.order_id, event, event.taker_fee);
+            }
+        }
+
+        if self.uid_for_this_shard(event.matched_order_uid) {
+            if let Some(maker) = self.user_service.get_user_mut(event.matched_order_uid) {
+                let order_id = event.matched_order_id;
+                Self::settle_option_trade(maker, spec, cmd.action.opposite(), order_id, event, event.maker_fee);
+            }
+        }
+    }
+
+    /// Bên mua trả phí quyền chọn, bên bán nhận phí quyền chọn.
+    /// Phần giữ của khối lượng khớp được lấy ra; phần đóng vị thế bán mở khóa tài sản đảm bảo tương ứng,
+    /// phần mở vị thế bán khóa lại margin_sell làm tài sản đảm bảo của vị thế.
+    fn settle_option_trade(
+        profile: &mut UserProfile,
+        spec: &CoreSymbolSpecification,
+        action: OrderAction,
+        order_id: OrderId,
+        event: &MatcherTradeEvent,
+        fee: i64,
+    ) {
+        let size = event.size;
+        let held = profile.take_hold(spec.symbol_id, order_id, size).unwrap_or(0);
+        let position = Self::position_entry(profile, spec);
+        match action {
+            OrderAction::Bid => position.pending_buy_size -= size,
+            OrderAction::Ask => position.pending_sell_size -= size,
+        }
+        let (closed, _) = position.update_for_trade(action, size, event.price);
+        let is_empty = position.is_empty();
+
+        let premium = (size as i128 * event.price as i128).saturating_mul(spec.quote_scale_k as i128);
+        let (received, collateral) = match action {
+            OrderAction::Bid => (held as i128 - premium, -closed as i128 * spec.margin_sell as i128),
+            OrderAction::Ask => (held as i128 + premium, (size - closed) as i128 * spec.margin_sell as i128),
+        };
+        profile.credit(spec.quote_currency, received - fee as i128);
+        profile.lock(spec.quote_currency, Self::saturating_i64(collateral));
+
+        if is_empty {
+            profile.positions.remove(&spec.symbol_id);
+        }
+    }
+
+    /// Quyết toán quyền chọn đáo hạn theo giá quyết toán `cmd.price`.
+    /// Lệnh chờ đã được pipeline hủy trước; sau khi quyết toán, công cụ bị gỡ khỏi engine rủi ro.
+    pub(super) fn expire_option(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let result = self.check_expiry(cmd);
+        if result != CommandResultCode::Success {
+            return result;
+        }
+        let Some(spec) = self.remove_symbol(cmd.symbol) else {
+            return CommandResultCode::InvalidSymbol;
+        };
+
+        let mut events = Vec::new();
+        for profile in self.user_service.profiles_mut() {
+            let Some(position) = profile.positions.remove(&spec.symbol_id) else {
+                continue;
+            };
+            let net_volume = position.net_volume();
+            if net_volume == 0 {
+                continue;
+            }
+
+            let amount = Self::exercise_option(profile, &spec, net_volume, cmd.price);
+            // Mở khóa tài sản đảm bảo của vị thế bán
+            let collateral = position.open_volume_short as i128 * spec.margin_sell as i128;
+            profile.lock(spec.quote_currency, Self::saturating_i64(-collateral));
+            events.push(MatcherTradeEvent::new_settlement(profile.uid, net_volume, cmd.price, amount));
+        }
+
+        Self::merge_user_events(cmd, events);
+        CommandResultCode::Success
+    }
+
+    /// Thực hiện quyền theo vị thế ròng, trả về số tiền quote (dương = nhận).
+    /// Tiền mặt: thanh toán giá trị nội tại; vật chất: chuyển giao tài sản cơ sở theo giá thực hiện
+    /// (chỉ khi có lãi). Tổng các vị thế ròng bằng 0 nên tổng tiền và tài sản luôn bảo toàn.
+    /// Giá quyết toán do lệnh quản trị cung cấp nên số tiền được tính bằng i128 và ghi có bão hòa.
+    fn exercise_option(
+        profile: &mut UserProfile,
+        spec: &CoreSymbolSpecification,
+        net_volume: Size,
+        settlement_price: Price,
+    ) -> i64 {
+        // Hướng nhận tài sản cơ sở của bên mua quyền: quyền chọn mua nhận, quyền chọn bán giao
+        let (intrinsic, direction) = match spec.symbol_type {
+            SymbolType::CallOption => (settlement_price as i128 - spec.strike_price as i128, 1),
+            _ => (spec.strike_price as i128 - settlement_price as i128, -1),
+        };
+        if intrinsic <= 0 {
+            return 0;
+        }
+
+        let net_volume = net_volume as i128;
+        let amount = match spec.option_settlement {
+            OptionSettlement::Cash => net_volume.saturating_mul(intrinsic).saturating_mul(spec.quote_scale_k as i128),
+            OptionSettlement::Physical => {
+                profile.credit(spec.base_currency, direction * net_volume * spec.base_scale_k as i128);
+                (-direction * net_volume * spec.strike_price as i128).saturating_mul(spec.quote_scale_k as i128)
+            }
+        };
+        profile.credit(spec.quote_currency, amount);
+        Self::saturating_i64(amount)
+    }
+}
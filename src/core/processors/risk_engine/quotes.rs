@@ -0,0 +1,111 @@
+use super::RiskEngine;
+use crate::api::*;
+use ahash::AHashSet;
+
+impl RiskEngine {
+    /// R1 của báo giá hai chiều, tất cả hoặc không. Báo giá cũ trên các công cụ được báo giá được đánh dấu để thay
+    /// (engine khớp lệnh hủy chúng khi treo báo giá mới). Báo giá mới được kiểm tra rủi ro như lệnh đặt trên số dư
+    /// sau khi hoàn khoản giữ của báo giá cũ, nhưng chỉ chạy thử: hồ sơ được khôi phục ngay, khoản giữ chỉ được đổi
+    /// ở R2 khi toàn bộ báo giá đã được treo. Báo giá bị từ chối (ở đây hoặc ở engine khớp lệnh) giữ nguyên báo giá cũ.
+    pub(super) fn mass_quote_risk_check(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        // Chỉ engine rủi ro được chọn báo giá cũ cần hủy
+        for quote in &mut cmd.quotes {
+            for action in [OrderAction::Bid, OrderAction::Ask] {
+                let side = quote.side_mut(action);
+                side.replaced = None;
+                side.replaced_size = 0;
+            }
+        }
+        let Some(profile) = self.user_service.get_user(cmd.uid) else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+        // Mỗi công cụ một báo giá
+        let mut symbols = AHashSet::new();
+        if !cmd.quotes.iter().all(|quote| self.symbols.contains_key(&quote.symbol) && symbols.insert(quote.symbol)) {
+            return CommandResultCode::InvalidSymbol;
+        }
+        // Hai phía của một báo giá là hai lệnh khác nhau
+        if cmd.quotes.iter().any(|quote| quote.bid.size != 0 && quote.ask.size != 0 && quote.bid.order_id == quote.ask.order_id) {
+            return CommandResultCode::RiskDuplicateOrderId;
+        }
+
+        for quote in &mut cmd.quotes {
+            let Some(&(bid, ask)) = self.quotes.get(&(cmd.uid, quote.symbol)) else {
+                continue;
+            };
+            for (action, order_id) in [(OrderAction::Bid, bid), (OrderAction::Ask, ask)] {
+                // Báo giá cũ đã khớp hết không còn khoản giữ
+                let Some(hold) = order_id.and_then(|order_id| profile.holds.get(&(quote.symbol, order_id))) else {
+                    continue;
+                };
+                let side = quote.side_mut(action);
+                side.replaced = order_id;
+                side.replaced_size = hold.size;
+            }
+        }
+
+        let snapshot = profile.clone();
+        let result = self.replace_quote_holds(cmd);
+        if let Some(profile) = self.user_service.get_user_mut(cmd.uid) {
+            *profile = snapshot;
+        }
+        result
+    }
+
+    /// R2 của báo giá hai chiều: báo giá được engine khớp lệnh treo toàn bộ thì đổi khoản giữ của báo giá cũ sang
+    /// báo giá mới (đã chạy thử ở R1 trên cùng số dư) và ghi nhận báo giá mới
+    pub(super) fn apply_mass_quote(&mut self, cmd: &mut OrderCommand) {
+        // Phân đoạn rủi ro chạy R2 trước có thể đã chuyển mã kết quả thành Success
+        let accepted = matches!(
+            cmd.result_code,
+            CommandResultCode::ValidForMatchingEngine | CommandResultCode::Success
+        );
+        if accepted && self.uid_for_this_shard(cmd.uid) {
+            let result = self.replace_quote_holds(cmd);
+            if result != CommandResultCode::ValidForMatchingEngine {
+                tracing::error!(uid = cmd.uid, ?result, "Không đổi được khoản giữ của báo giá đã được treo");
+            }
+            for quote in &cmd.quotes {
+                let order_id = |side: &QuoteSide| (side.size != 0).then_some(side.order_id);
+                self.quotes.insert((cmd.uid, quote.symbol), (order_id(&quote.bid), order_id(&quote.ask)));
+            }
+        }
+        if cmd.result_code == CommandResultCode::ValidForMatchingEngine {
+            cmd.result_code = CommandResultCode::Success;
+        }
+    }
+
+    /// Hoàn khoản giữ của các báo giá cũ bị thay rồi giữ cho các phía báo giá mới (dừng ở phía đầu tiên bị từ chối)
+    fn replace_quote_holds(&mut self, cmd: &OrderCommand) -> CommandResultCode {
+        for quote in &cmd.quotes {
+            let spec = self.symbols[&quote.symbol].clone();
+            for action in [OrderAction::Bid, OrderAction::Ask] {
+                let side = quote.side(action);
+                let Some(order_id) = side.replaced else {
+                    continue;
+                };
+                let old = QuoteSide { order_id, size: side.replaced_size, ..Default::default() };
+                let order = OrderCommand::quote_order(cmd.uid, cmd.timestamp, quote.symbol, action, &old);
+                self.handle_reject_event(&order, &MatcherTradeEvent::new_reject(old.size, 0), &spec);
+            }
+        }
+
+        for quote in &cmd.quotes {
+            for action in [OrderAction::Bid, OrderAction::Ask] {
+                let side = quote.side(action);
+                if side.size == 0 {
+                    continue;
+                }
+                let mut order = OrderCommand {
+                    session_id: cmd.session_id,
+                    ..OrderCommand::quote_order(cmd.uid, cmd.timestamp, quote.symbol, action, side)
+                };
+                let result = self.place_order_risk_check(&mut order);
+                if result != CommandResultCode::ValidForMatchingEngine {
+                    return result;
+                }
+            }
+        }
+        CommandResultCode::ValidForMatchingEngine
+    }
+}
@@ -0,0 +1,105 @@
+use super::RiskEngine;
+use crate::api::*;
+
+impl RiskEngine {
+    /// Công cụ có thời điểm đáo hạn đã tới: ngừng giao dịch
+    #[inline]
+    pub(super) fn is_expired(spec: &CoreSymbolSpecification, timestamp: i64) -> bool {
+        spec.expiry_time > 0 && timestamp >= spec.expiry_time
+    }
+
+    /// Gộp sự kiện theo người dùng (funding, quyết toán) của phân đoạn này vào lệnh.
+    /// Mỗi phân đoạn chỉ phát sự kiện cho người dùng của mình; sắp xếp lại theo uid sau mỗi lần gộp
+    /// để thứ tự không phụ thuộc số phân đoạn và xác định khi phát lại
+    pub(super) fn merge_user_events(cmd: &mut OrderCommand, events: Vec<MatcherTradeEvent>) {
+        cmd.matcher_events.extend(events);
+        cmd.matcher_events.sort_by_key(|e| e.matched_order_uid);
+    }
+
+    /// Kiểm tra lệnh quyết toán đáo hạn (ExpireOption / SettleFutures), không thay đổi trạng thái
+    pub fn check_expiry(&self, cmd: &OrderCommand) -> CommandResultCode {
+        let Some(spec) = self.symbols.get(&cmd.symbol) else {
+            return CommandResultCode::InvalidSymbol;
+        };
+        let supported = match cmd.command {
+            OrderCommandType::ExpireOption => spec.symbol_type.is_option(),
+            OrderCommandType::SettleFutures => spec.symbol_type == SymbolType::FuturesContract,
+            _ => false,
+        };
+        if !supported {
+            return CommandResultCode::UnsupportedSymbolType;
+        }
+        // expiry_time = 0: công cụ không đáo hạn
+        if !Self::is_expired(spec, cmd.timestamp) {
+            return CommandResultCode::SymbolNotExpired;
+        }
+        CommandResultCode::Success
+    }
+
+    /// Công cụ vừa tới thời điểm đáo hạn tại `timestamp` (theo thứ tự ID công cụ), mỗi công cụ chỉ trả về một lần.
+    /// Pipeline hủy lệnh chờ của các công cụ này ngay tại thời điểm đáo hạn, quyết toán chờ lệnh quyết toán.
+    pub fn take_expired_symbols(&mut self, timestamp: i64) -> Vec<SymbolId> {
+        let mut symbols: Vec<SymbolId> = self
+            .symbols
+            .values()
+            .filter(|spec| Self::is_expired(spec, timestamp) && !self.expired_symbols.contains(&spec.symbol_id))
+            .map(|spec| spec.symbol_id)
+            .collect();
+        symbols.sort_unstable();
+        self.expired_symbols.extend(symbols.iter().copied());
+        symbols
+    }
+
+    /// Người dùng thuộc phân đoạn này còn lệnh chờ trên công cụ (theo thứ tự uid)
+    pub fn users_with_pending_orders(&self, symbol: SymbolId) -> Vec<UserId> {
+        let mut uids: Vec<UserId> = self
+            .user_service
+            .profiles()
+            .filter(|profile| {
+                profile
+                    .positions
+                    .get(&symbol)
+                    .is_some_and(|p| p.pending_buy_size > 0 || p.pending_sell_size > 0)
+            })
+            .map(|profile| profile.uid)
+            .collect();
+        uids.sort_unstable();
+        uids
+    }
+
+    /// Quyết toán cuối cùng hợp đồng tương lai theo giá quyết toán `cmd.price`:
+    /// đóng mọi vị thế, ghi nhận lãi/lỗ và mở khóa ký quỹ vị thế.
+    /// Lệnh chờ đã được pipeline hủy tại thời điểm đáo hạn; sau khi quyết toán, công cụ bị gỡ khỏi engine rủi ro.
+    pub(super) fn settle_futures(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let result = self.check_expiry(cmd);
+        if result != CommandResultCode::Success {
+            return result;
+        }
+        let Some(spec) = self.remove_symbol(cmd.symbol) else {
+            return CommandResultCode::InvalidSymbol;
+        };
+
+        let mut events = Vec::new();
+        for profile in self.user_service.profiles_mut() {
+            let Some(position) = profile.positions.remove(&spec.symbol_id) else {
+                continue;
+            };
+            let net_volume = position.net_volume();
+            if net_volume == 0 {
+                continue;
+            }
+
+            let pnl = Self::saturating_i64(position.unrealized_pnl(cmd.price) * spec.quote_scale_k as i128);
+            let position_margin = Self::saturating_i64(
+                position.open_volume_long as i128 * spec.margin_buy as i128
+                    + position.open_volume_short as i128 * spec.margin_sell as i128,
+            );
+            profile.credit(spec.quote_currency, pnl as i128);
+            profile.lock(spec.quote_currency, -position_margin);
+            events.push(MatcherTradeEvent::new_settlement(profile.uid, net_volume, cmd.price, pnl));
+        }
+
+        Self::merge_user_events(cmd, events);
+        CommandResultCode::Success
+    }
+}
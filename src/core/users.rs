@@ -1,12 +1,48 @@
 use crate::api::*;
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserProfile {
     pub uid: UserId,
-    pub accounts: AHashMap<Currency, i64>, // Sử dụng AHashMap khi chạy (hiệu năng tốt hơn)
+    pub accounts: AHashMap<Currency, i64>, // Số dư khả dụng. Sử dụng AHashMap khi chạy (hiệu năng tốt hơn)
+    pub locked: AHashMap<Currency, i64>,   // Số dư bị khóa: giữ cho lệnh chờ + ký quỹ/tài sản đảm bảo của vị thế
+    pub holds: AHashMap<(SymbolId, OrderId), OrderHold>, // Khoản giữ của từng lệnh chờ
     pub positions: AHashMap<SymbolId, SymbolPositionRecord>,
+    pub applied_transactions: AHashSet<u64>, // Mã giao dịch nạp/rút đã áp dụng (chống ghi trùng)
+    pub net_deposits: AHashMap<Currency, i64>, // Tổng nạp - rút theo loại tiền, dùng cho kiểm toán bảo toàn số dư
+    pub suspended: bool, // Bị khóa (SuspendUser / kill switch): không được đặt hoặc sửa lệnh, vẫn được hủy lệnh
+}
+
+/// Khoản giữ của một lệnh: số tiền giữ theo lot, số lot còn đang giữ,
+/// phí (taker, maker) theo lot tại thời điểm đặt lệnh (lệnh được tính phí theo mức này đến khi hết khoản giữ)
+/// và phiên kết nối đã đặt lệnh (hủy khi phiên quá hạn heartbeat)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderHold {
+    pub currency: Currency,
+    pub amount_per_lot: i64,
+    pub size: Size,
+    pub fees: (i64, i64),
+    pub session_id: SessionId,
+}
+
+impl OrderHold {
+    pub fn amount(&self) -> i64 {
+        self.amount_per_lot * self.size
+    }
+}
+
+/// Số dư khả dụng và bị khóa của một loại tiền
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountBalance {
+    pub available: i64,
+    pub locked: i64,
+}
+
+impl AccountBalance {
+    pub fn total(&self) -> i64 {
+        self.available + self.locked
+    }
 }
 
 impl UserProfile {
@@ -14,8 +50,111 @@ impl UserProfile {
         Self {
             uid,
             accounts: AHashMap::new(),
+            locked: AHashMap::new(),
+            holds: AHashMap::new(),
             positions: AHashMap::new(),
+            applied_transactions: AHashSet::new(),
+            net_deposits: AHashMap::new(),
+            suspended: false,
+        }
+    }
+
+    /// Số dư khả dụng và bị khóa của một loại tiền
+    pub fn balance(&self, currency: Currency) -> AccountBalance {
+        AccountBalance {
+            available: self.accounts.get(&currency).copied().unwrap_or(0),
+            locked: self.locked.get(&currency).copied().unwrap_or(0),
+        }
+    }
+
+    /// Giữ `amount_per_lot × cmd.size` từ số dư khả dụng cho lệnh, ghi kèm phí (taker, maker) theo lot và phiên của lệnh.
+    /// Thất bại nếu không đủ số dư, số tiền giữ tràn số hoặc lệnh đã có khoản giữ (trùng mã lệnh).
+    /// Phần lấy ra sau này luôn không vượt quá tổng đã giữ nên không cần kiểm tra tràn số lại
+    pub fn place_hold(
+        &mut self,
+        cmd: &OrderCommand,
+        currency: Currency,
+        amount_per_lot: i64,
+        fees: (i64, i64),
+    ) -> CommandResultCode {
+        let (symbol, order_id, size) = (cmd.symbol, cmd.order_id, cmd.size);
+        if self.holds.contains_key(&(symbol, order_id)) {
+            return CommandResultCode::RiskDuplicateOrderId;
+        }
+
+        let Some(amount) = amount_per_lot.checked_mul(size) else {
+            return CommandResultCode::RiskNotionalOverflow;
+        };
+        let available = self.accounts.entry(currency).or_insert(0);
+        if *available < amount {
+            return CommandResultCode::RiskNsf;
+        }
+        *available -= amount;
+        *self.locked.entry(currency).or_insert(0) += amount;
+
+        let hold = OrderHold {
+            currency,
+            amount_per_lot,
+            size,
+            fees,
+            session_id: cmd.session_id,
+        };
+        self.holds.insert((symbol, order_id), hold);
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    /// Giữ thêm `size` lot cho lệnh đã có khoản giữ, theo cùng số tiền giữ mỗi lot (sửa lệnh tăng khối lượng).
+    /// Thất bại nếu lệnh không có khoản giữ, không đủ số dư hoặc số tiền giữ tràn số.
+    pub fn increase_hold(&mut self, symbol: SymbolId, order_id: OrderId, size: Size) -> CommandResultCode {
+        let Some(hold) = self.holds.get_mut(&(symbol, order_id)) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        let (Some(amount), Some(new_size)) = (hold.amount_per_lot.checked_mul(size), hold.size.checked_add(size)) else {
+            return CommandResultCode::RiskNotionalOverflow;
+        };
+        let available = self.accounts.entry(hold.currency).or_insert(0);
+        if *available < amount {
+            return CommandResultCode::RiskNsf;
+        }
+        *available -= amount;
+        *self.locked.entry(hold.currency).or_insert(0) += amount;
+        hold.size = new_size;
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    /// Lấy ra phần giữ của `size` lot (khớp, hủy hoặc giảm khối lượng) khỏi số dư bị khóa.
+    /// Trả về số tiền đã lấy ra (người gọi quyết định hoàn vào số dư khả dụng hay dùng để thanh toán),
+    /// None nếu lệnh không có khoản giữ (ví dụ lệnh thanh lý nội bộ)
+    pub fn take_hold(&mut self, symbol: SymbolId, order_id: OrderId, size: Size) -> Option<i64> {
+        let hold = self.holds.get_mut(&(symbol, order_id))?;
+        let lots = size.min(hold.size);
+        let amount = hold.amount_per_lot * lots;
+        let currency = hold.currency;
+
+        hold.size -= lots;
+        if hold.size == 0 {
+            self.holds.remove(&(symbol, order_id));
         }
+        *self.locked.entry(currency).or_insert(0) -= amount;
+        Some(amount)
+    }
+
+    /// Ghi có vào số dư khả dụng (số âm = ghi nợ). Số tiền thanh toán tính bằng i128 nên được cộng
+    /// bão hòa ở giới hạn i64: số dư cực lớn không làm tràn số khi thanh toán
+    pub fn credit(&mut self, currency: Currency, amount: i128) {
+        let balance = self.accounts.entry(currency).or_insert(0);
+        *balance = saturating_i64((*balance as i128).saturating_add(amount));
+    }
+
+    /// Chuyển tiền giữa số dư khả dụng và bị khóa (số âm = mở khóa), dùng cho ký quỹ vị thế
+    pub fn lock(&mut self, currency: Currency, amount: i64) {
+        if amount == 0 {
+            return;
+        }
+        let available = self.accounts.entry(currency).or_insert(0);
+        *available = available.saturating_sub(amount);
+        let locked = self.locked.entry(currency).or_insert(0);
+        *locked = locked.saturating_add(amount);
     }
 }
 
@@ -27,9 +166,9 @@ pub struct SymbolPositionRecord {
     pub direction: i32,
     pub open_volume_long: i64,
     pub open_volume_short: i64,
-    pub open_price_long: i64,
-    pub open_price_short: i64,
-    pub profit: i64,
+    pub open_price_long: i64,  // Tổng giá mở vị thế long (giá × khối lượng)
+    pub open_price_short: i64, // Tổng giá mở vị thế short (giá × khối lượng)
+    pub profit: i64,           // Lãi/lỗ đã thực hiện lũy kế
     pub pending_buy_size: i64,
     pub pending_sell_size: i64,
 }
@@ -57,9 +196,72 @@ impl SymbolPositionRecord {
             && self.pending_buy_size == 0
             && self.pending_sell_size == 0
     }
+
+    /// Vị thế ròng (dương = long, âm = short)
+    pub fn net_volume(&self) -> i64 {
+        self.open_volume_long - self.open_volume_short
+    }
+
+    /// Lãi/lỗ chưa thực hiện tại giá đánh dấu (đơn vị giá × khối lượng).
+    /// Tính bằng i128 vì giá đánh dấu do nguồn bên ngoài cung cấp, không được kiểm tra khi đặt lệnh
+    pub fn unrealized_pnl(&self, mark_price: Price) -> i128 {
+        let mark_price = mark_price as i128;
+        (self.open_volume_long as i128 * mark_price - self.open_price_long as i128)
+            + (self.open_price_short as i128 - self.open_volume_short as i128 * mark_price)
+    }
+
+    /// Cập nhật vị thế theo giao dịch: đóng vị thế ngược chiều trước, phần còn lại mở vị thế mới.
+    /// Trả về (khối lượng đã đóng, lãi/lỗ thực hiện theo đơn vị giá × khối lượng).
+    /// Tổng giá mở tăng dần qua các giao dịch nên được tính bằng i128 và bão hòa ở giới hạn i64
+    pub fn update_for_trade(&mut self, action: OrderAction, size: Size, price: Price) -> (Size, i64) {
+        let (closed, pnl) = match action {
+            OrderAction::Bid => {
+                let closed = size.min(self.open_volume_short);
+                let basis = Self::take_basis(&mut self.open_price_short, &mut self.open_volume_short, closed);
+                self.open_volume_long += size - closed;
+                Self::add_open_price(&mut self.open_price_long, size - closed, price);
+                (closed, saturating_i64(basis as i128 - closed as i128 * price as i128))
+            }
+            OrderAction::Ask => {
+                let closed = size.min(self.open_volume_long);
+                let basis = Self::take_basis(&mut self.open_price_long, &mut self.open_volume_long, closed);
+                self.open_volume_short += size - closed;
+                Self::add_open_price(&mut self.open_price_short, size - closed, price);
+                (closed, saturating_i64(closed as i128 * price as i128 - basis as i128))
+            }
+        };
+
+        self.profit = self.profit.saturating_add(pnl);
+        self.direction = self.net_volume().signum() as i32;
+        (closed, pnl)
+    }
+
+    fn add_open_price(price_sum: &mut i64, size: Size, price: Price) {
+        *price_sum = saturating_i64(*price_sum as i128 + size as i128 * price as i128);
+    }
+
+    /// Tách phần giá vốn tương ứng với khối lượng đóng (lần đóng cuối lấy toàn bộ phần dư, không thất thoát do làm tròn)
+    fn take_basis(price_sum: &mut i64, volume: &mut i64, closed: Size) -> i64 {
+        if closed == 0 {
+            return 0;
+        }
+        let basis = if closed == *volume {
+            *price_sum
+        } else {
+            (*price_sum as i128 * closed as i128 / *volume as i128) as i64
+        };
+        *price_sum -= basis;
+        *volume -= closed;
+        basis
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Giới hạn giá trị i128 về khoảng của i64
+fn saturating_i64(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UserProfileService {
     profiles: AHashMap<UserId, UserProfile>, // Sử dụng AHashMap khi chạy
 }
@@ -88,18 +290,48 @@ impl UserProfileService {
         self.profiles.get_mut(&uid)
     }
 
+    /// Duyệt tất cả hồ sơ người dùng (thứ tự không xác định)
+    pub fn profiles(&self) -> impl Iterator<Item = &UserProfile> {
+        self.profiles.values()
+    }
+
+    pub fn profiles_mut(&mut self) -> impl Iterator<Item = &mut UserProfile> {
+        self.profiles.values_mut()
+    }
+
+    /// Nạp/rút tiền. Mỗi mã giao dịch chỉ được áp dụng một lần;
+    /// lệnh rút làm số dư âm (hoặc lệnh nạp làm tràn số dư) bị từ chối và không ghi nhận mã giao dịch (có thể gửi lại sau)
     pub fn balance_adjustment(
         &mut self,
         uid: UserId,
         currency: Currency,
         amount: i64,
-        _transaction_id: i64,
+        transaction_id: u64,
     ) -> CommandResultCode {
-        if let Some(profile) = self.profiles.get_mut(&uid) {
-            *profile.accounts.entry(currency).or_insert(0) += amount;
-            CommandResultCode::Success
-        } else {
-            CommandResultCode::AuthInvalidUser
+        let Some(profile) = self.profiles.get_mut(&uid) else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+
+        if amount == 0 {
+            return CommandResultCode::UserMgmtBalanceAdjustmentZero;
+        }
+        if profile.applied_transactions.contains(&transaction_id) {
+            return CommandResultCode::UserMgmtBalanceAdjustmentAlreadyApplied;
         }
+
+        let balance = profile.accounts.entry(currency).or_insert(0);
+        let Some(new_balance) = balance.checked_add(amount) else {
+            return CommandResultCode::UserMgmtBalanceAdjustmentOverflow;
+        };
+        // Chỉ lệnh rút bị chặn khi số dư âm: lệnh nạp vào tài khoản đang âm (ví dụ sau thâm hụt thanh lý) vẫn được nhận
+        if amount < 0 && new_balance < 0 {
+            return CommandResultCode::UserMgmtBalanceAdjustmentNsf;
+        }
+
+        *balance = new_balance;
+        let net_deposits = profile.net_deposits.entry(currency).or_insert(0);
+        *net_deposits = net_deposits.saturating_add(amount);
+        profile.applied_transactions.insert(transaction_id);
+        CommandResultCode::Success
     }
 }
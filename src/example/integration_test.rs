@@ -22,13 +22,7 @@ pub fn test_full_flow() {
         ..Default::default()
     });
 
-    core.submit_command(OrderCommand {
-        command: OrderCommandType::BalanceAdjustment,
-        uid: 1,
-        symbol: 2,
-        price: 1000000,
-        ..Default::default()
-    });
+    core.submit_command(OrderCommand::balance_adjustment(1, 2, 1000000, 1));
 
     // 2. Test kết hợp WAL và snapshot
     let journal_path = "integration.wal";
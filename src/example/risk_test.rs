@@ -21,13 +21,7 @@ pub fn test_risk_engine_basic() {
     engine.pre_process(&mut cmd_user);
     assert_eq!(cmd_user.result_code, CommandResultCode::Success);
 
-    let mut cmd_balance = OrderCommand {
-        command: OrderCommandType::BalanceAdjustment,
-        uid: 1001,
-        symbol: 2, // Nạp tiền quote
-        price: 10000,
-        ..Default::default()
-    };
+    let mut cmd_balance = OrderCommand::balance_adjustment(1001, 2, 10000, 1); // Nạp tiền quote
     cmd_balance.result_code = CommandResultCode::ValidForMatchingEngine;
     engine.pre_process(&mut cmd_balance);
     assert_eq!(cmd_balance.result_code, CommandResultCode::Success);
@@ -13,6 +13,7 @@ fn create_symbol_spec() -> CoreSymbolSpecification {
         maker_fee: 0,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     }
 }
 
@@ -175,9 +176,11 @@ fn test_iceberg_order() {
     };
     book.new_order(&mut bid_cmd);
     
-    // Nên khớp 10
-    assert_eq!(bid_cmd.matcher_events.len(), 1);
+    // Nên khớp 10, phần hiển thị hết nên được làm mới
+    assert_eq!(bid_cmd.matcher_events.len(), 2);
     assert_eq!(bid_cmd.matcher_events[0].size, 10);
+    assert_eq!(bid_cmd.matcher_events[1].event_type, MatcherEventType::IcebergRefresh);
+    assert_eq!(bid_cmd.matcher_events[1].size, 10);
     
     // Sổ lệnh nên còn 90 (sau khi làm mới hiển thị 10)
     assert_eq!(book.get_total_ask_volume(), 90);
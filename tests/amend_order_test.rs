@@ -0,0 +1,142 @@
+mod common;
+
+use common::{add_user, balance, fills, order, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::ExchangeCore;
+use matching_core::core::orderbook::{AdvancedOrderBook, OrderBook};
+use matching_core::core::users::AccountBalance;
+
+const SPOT: SymbolId = 1;
+const FUTURES: SymbolId = 2;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+
+fn create_core() -> ExchangeCore {
+    let mut core = common::create_core(&[
+        spot_spec(SPOT, BASE, QUOTE),
+        CoreSymbolSpecification {
+            symbol_type: SymbolType::FuturesContract,
+            margin_buy: 500,
+            margin_sell: 500,
+            ..spot_spec(FUTURES, BASE, QUOTE)
+        },
+    ]);
+    for uid in [1, 2, 3] {
+        add_user(&mut core, uid, &[(QUOTE, 10_000), (BASE, 100)]);
+    }
+    core
+}
+
+fn amend(uid: UserId, order_id: OrderId, action: OrderAction, price: Price, size: Size) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::AmendOrder,
+        ..order(uid, order_id, SPOT, action, price, size)
+    }
+}
+
+fn sell(core: &mut ExchangeCore, order_id: OrderId, price: Price, size: Size) -> Vec<(OrderId, Size)> {
+    let cmd = core.submit_command(OrderCommand { order_type: OrderType::Ioc, ..order(3, order_id, SPOT, OrderAction::Ask, price, size) });
+    fills(&cmd)
+}
+
+#[test]
+fn test_amend_size_decrease_keeps_priority() {
+    let mut core = create_core();
+    core.submit_command(OrderCommand { reserve_price: 120, ..order(1, 11, SPOT, OrderAction::Bid, 100, 10) });
+    core.submit_command(order(2, 21, SPOT, OrderAction::Bid, 100, 5));
+
+    let cmd = core.submit_command(amend(1, 11, OrderAction::Bid, 100, 4));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_520, locked: 480 });
+
+    assert_eq!(sell(&mut core, 31, 100, 5), vec![(11, 4), (21, 1)]);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_600, locked: 0 });
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_amend_size_increase_loses_priority_and_holds_delta() {
+    let mut core = create_core();
+    core.submit_command(OrderCommand { reserve_price: 120, ..order(1, 11, SPOT, OrderAction::Bid, 100, 10) });
+    core.submit_command(order(2, 21, SPOT, OrderAction::Bid, 100, 5));
+
+    let cmd = core.submit_command(amend(1, 11, OrderAction::Bid, 100, 12));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert!(cmd.matcher_events.is_empty());
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 8_560, locked: 1_440 });
+
+    assert_eq!(sell(&mut core, 31, 100, 7), vec![(21, 5), (11, 2)]);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 8_600, locked: 1_200 });
+
+    // Không đủ số dư cho phần tăng: từ chối, khoản giữ không đổi
+    let cmd = core.submit_command(amend(1, 11, OrderAction::Bid, 100, 90));
+    assert_eq!(cmd.result_code, CommandResultCode::RiskNsf);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 8_600, locked: 1_200 });
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_amend_price_change_matches_and_failed_amend_releases_increase() {
+    let mut core = create_core();
+    core.submit_command(order(2, 21, SPOT, OrderAction::Ask, 105, 5));
+    core.submit_command(OrderCommand { reserve_price: 120, ..order(1, 11, SPOT, OrderAction::Bid, 100, 10) });
+
+    // Giá mới giao với lệnh bán: khớp 5 @ 105 như lệnh chủ động, phần còn lại treo ở 110
+    let cmd = core.submit_command(amend(1, 11, OrderAction::Bid, 110, 10));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(cmd.matcher_events[0].event_type, MatcherEventType::Trade);
+    assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 105, locked: 0 });
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 8_875, locked: 600 });
+
+    // Giá vượt giá dự trữ: phần tăng đã giữ ở R1 được hoàn lại
+    let cmd = core.submit_command(amend(1, 11, OrderAction::Bid, 130, 8));
+    assert_eq!(cmd.result_code, CommandResultCode::RiskInvalidReserveBidPrice);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 8_875, locked: 600 });
+
+    let cmd = core.submit_command(amend(1, 99, OrderAction::Bid, 110, 8));
+    assert_eq!(cmd.result_code, CommandResultCode::MatchingUnknownOrderId);
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_amend_futures_order_adjusts_margin_hold() {
+    let mut core = create_core();
+    core.submit_command(OrderCommand { symbol: FUTURES, ..order(1, 11, SPOT, OrderAction::Ask, 1_000, 4) });
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 8_000, locked: 2_000 });
+
+    core.submit_command(OrderCommand { symbol: FUTURES, ..amend(1, 11, OrderAction::Ask, 1_000, 6) });
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 7_000, locked: 3_000 });
+    core.submit_command(OrderCommand { symbol: FUTURES, ..amend(1, 11, OrderAction::Ask, 1_010, 1) });
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_500, locked: 500 });
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_advanced_amend_visible_size() {
+    let mut book = AdvancedOrderBook::new(CoreSymbolSpecification { symbol_id: SPOT, ..Default::default() });
+    let iceberg = |order_id, size, visible| OrderCommand {
+        order_type: OrderType::Iceberg,
+        visible_size: Some(visible),
+        ..order(order_id, order_id, SPOT, OrderAction::Ask, 100, size)
+    };
+    book.new_order(&mut iceberg(1, 20, 5));
+    book.new_order(&mut iceberg(2, 20, 5));
+
+    // Giảm phần hiển thị tại chỗ: giữ ưu tiên
+    let mut cmd = OrderCommand { visible_size: Some(2), ..amend(1, 1, OrderAction::Ask, 100, 20) };
+    assert_eq!(book.amend_order(&mut cmd), CommandResultCode::Success);
+    assert_eq!(book.get_l2_data(1).ask_volumes, vec![7]);
+
+    let mut bid = OrderCommand { order_type: OrderType::Ioc, ..order(9, 9, SPOT, OrderAction::Bid, 100, 1) };
+    book.new_order(&mut bid);
+    assert_eq!(bid.matcher_events[0].matched_order_id, 1);
+
+    // Tăng phần hiển thị: xuống cuối hàng đợi
+    let mut cmd = OrderCommand { visible_size: Some(8), ..amend(1, 1, OrderAction::Ask, 100, 19) };
+    assert_eq!(book.amend_order(&mut cmd), CommandResultCode::Success);
+    assert_eq!(book.get_l2_data(1).ask_volumes, vec![13]);
+
+    let mut bid = OrderCommand { order_type: OrderType::Ioc, ..order(10, 10, SPOT, OrderAction::Bid, 100, 1) };
+    book.new_order(&mut bid);
+    assert_eq!(bid.matcher_events[0].matched_order_id, 2);
+}
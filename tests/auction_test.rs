@@ -0,0 +1,182 @@
+mod common;
+
+use common::{add_user, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::auction;
+
+const SYMBOL: SymbolId = 1;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+const FEE_ACCOUNT: UserId = 100;
+
+fn create_core() -> ExchangeCore {
+    let config = ExchangeConfig {
+        fee_account_uid: Some(FEE_ACCOUNT),
+        ..Default::default()
+    };
+    let spec = CoreSymbolSpecification {
+        taker_fee: 3,
+        maker_fee: 1,
+        ..spot_spec(SYMBOL, BASE, QUOTE)
+    };
+    let mut core = common::create_core_with(config, &[spec]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, 100_000), (BASE, 100)]);
+    }
+    core
+}
+
+fn order(uid: UserId, order_id: OrderId, action: OrderAction, price: Price, size: Size) -> OrderCommand {
+    common::order(uid, order_id, SYMBOL, action, price, size)
+}
+
+fn set_session(core: &mut ExchangeCore, state: TradingSessionState) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::SetSessionState,
+        symbol: SYMBOL,
+        session_state: Some(state),
+        ..Default::default()
+    })
+}
+
+fn l2(asks: &[(Price, Size)], bids: &[(Price, Size)]) -> L2MarketData {
+    L2MarketData {
+        ask_prices: asks.iter().map(|&(price, _)| price).collect(),
+        ask_volumes: asks.iter().map(|&(_, size)| size).collect(),
+        bid_prices: bids.iter().map(|&(price, _)| price).collect(),
+        bid_volumes: bids.iter().map(|&(_, size)| size).collect(),
+    }
+}
+
+#[test]
+fn test_equilibrium_tie_breakers() {
+    // Không giao nhau
+    assert_eq!(auction::equilibrium(&l2(&[(101, 5)], &[(100, 5)]), None), None);
+
+    // Khối lượng lớn nhất, rồi chênh lệch nhỏ nhất, toàn dư bán → giá thấp nhất
+    let result = auction::equilibrium(&l2(&[(99, 8), (101, 6)], &[(102, 10), (100, 5)]), None);
+    assert_eq!(result, Some(AuctionIndicative { price: 101, volume: 10, imbalance: -4 }));
+
+    // Toàn dư mua → giá cao nhất
+    let result = auction::equilibrium(&l2(&[(100, 5), (101, 5)], &[(102, 20)]), None).unwrap();
+    assert_eq!((result.price, result.volume, result.imbalance), (102, 10, 10));
+
+    // Cân bằng: gần giá tham chiếu nhất, không có giá tham chiếu → giá thấp nhất
+    let book = l2(&[(99, 5), (100, 5)], &[(101, 10)]);
+    assert_eq!(auction::equilibrium(&book, Some(105)).unwrap().price, 101);
+    assert_eq!(auction::equilibrium(&book, Some(100)).unwrap().price, 100);
+    assert_eq!(auction::equilibrium(&book, None).unwrap().price, 100);
+}
+
+#[test]
+fn test_opening_auction_uncross() {
+    let mut core = create_core();
+    for state in [TradingSessionState::Closed, TradingSessionState::PreOpen, TradingSessionState::OpeningAuction] {
+        set_session(&mut core, state);
+    }
+
+    // Lệnh tích lũy, không khớp dù giao nhau
+    for cmd in [
+        order(1, 101, OrderAction::Bid, 102, 10),
+        order(1, 102, OrderAction::Bid, 100, 5),
+        order(2, 201, OrderAction::Ask, 99, 8),
+        order(2, 202, OrderAction::Ask, 101, 6),
+    ] {
+        let result = core.submit_command(cmd);
+        assert_eq!(result.result_code, CommandResultCode::Success);
+        assert!(result.matcher_events.is_empty());
+    }
+    let ioc = core.submit_command(OrderCommand { order_type: OrderType::Ioc, ..order(1, 103, OrderAction::Bid, 105, 1) });
+    assert_eq!(ioc.result_code, CommandResultCode::MatchingUnsupportedCommand);
+
+    assert_eq!(
+        core.get_auction_indicative(SYMBOL),
+        Some(AuctionIndicative { price: 101, volume: 10, imbalance: -4 })
+    );
+
+    let open = set_session(&mut core, TradingSessionState::Continuous);
+    assert_eq!(open.result_code, CommandResultCode::Success);
+    let trades: Vec<_> = open
+        .matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::AuctionTrade)
+        .map(|e| (e.bid_order_id, e.matched_order_id, e.size, e.price))
+        .collect();
+    assert_eq!(trades, vec![(101, 201, 8, 101), (101, 202, 2, 101)]);
+    assert_eq!(
+        open.matcher_events.last().unwrap().event_type,
+        MatcherEventType::SessionChange(TradingSessionState::Continuous)
+    );
+    assert_eq!(core.get_auction_indicative(SYMBOL), None);
+
+    // Cả hai bên chịu phí maker; lệnh mua 102 còn treo giữ 5 × (100 + 3)
+    let buyer = core.get_balance(1, QUOTE).unwrap();
+    assert_eq!((buyer.available, buyer.locked), (100_000 - 10 * 101 - 10 - 5 * 103, 5 * 103));
+    assert_eq!(core.get_balance(1, BASE).unwrap().available, 110);
+    assert_eq!(core.get_balance(2, QUOTE).unwrap().available, 100_000 + 10 * 101 - 10);
+    let seller = core.get_balance(2, BASE).unwrap();
+    assert_eq!((seller.available, seller.locked), (86, 4));
+    assert_eq!(core.get_balance(FEE_ACCOUNT, QUOTE).unwrap().available, 20);
+
+    // Khớp liên tục trở lại: sổ lệnh không còn giao nhau
+    let cmd = core.submit_command(order(1, 104, OrderAction::Bid, 101, 4));
+    assert_eq!(cmd.matcher_events[0].matched_order_id, 202);
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_closing_auction_uncross() {
+    let mut core = create_core();
+    core.submit_command(order(2, 201, OrderAction::Ask, 110, 5));
+    set_session(&mut core, TradingSessionState::ClosingAuction);
+
+    core.submit_command(order(1, 101, OrderAction::Bid, 112, 3));
+    core.submit_command(order(2, 202, OrderAction::Ask, 108, 2));
+    assert_eq!(core.get_auction_indicative(SYMBOL).unwrap().volume, 3);
+
+    let close = set_session(&mut core, TradingSessionState::Closed);
+    let sizes: Vec<_> = close
+        .matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::AuctionTrade)
+        .map(|e| (e.matched_order_id, e.size, e.price))
+        .collect();
+    assert_eq!(sizes, vec![(202, 2, 110), (201, 1, 110)]);
+    assert_eq!(core.get_session_state(SYMBOL), Some(TradingSessionState::Closed));
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_opening_auction_price_follows_reference_price() {
+    let mut core = create_core();
+    for state in [TradingSessionState::Closed, TradingSessionState::PreOpen, TradingSessionState::OpeningAuction] {
+        set_session(&mut core, state);
+    }
+    core.submit_command(order(2, 201, OrderAction::Ask, 99, 5));
+    core.submit_command(order(2, 202, OrderAction::Ask, 100, 5));
+    core.submit_command(order(1, 101, OrderAction::Bid, 101, 10));
+
+    // Hai giá cân bằng 100 và 101 ngang nhau về khối lượng và chênh lệch: chọn theo giá tham chiếu đặt trong phiên đấu giá
+    let set_reference = |core: &mut ExchangeCore, price: Price| {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::SetReferencePrice,
+            symbol: SYMBOL,
+            price,
+            ..Default::default()
+        })
+    };
+    assert_eq!(core.get_auction_indicative(SYMBOL).unwrap().price, 100);
+    assert_eq!(set_reference(&mut core, 105).result_code, CommandResultCode::Success);
+    assert_eq!(core.get_auction_indicative(SYMBOL).unwrap().price, 101);
+
+    let open = set_session(&mut core, TradingSessionState::Continuous);
+    let trades: Vec<_> = open
+        .matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::AuctionTrade)
+        .map(|e| (e.matched_order_id, e.size, e.price))
+        .collect();
+    assert_eq!(trades, vec![(201, 5, 101), (202, 5, 101)]);
+    assert!(core.audit().unwrap().is_balanced());
+}
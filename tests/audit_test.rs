@@ -0,0 +1,137 @@
+mod common;
+
+use common::{add_user, order, spot_spec};
+use matching_core::api::*;
+use matching_core::core::audit::{self, AuditDiscrepancy};
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::matching_engine::MatchingEngineState;
+use std::collections::HashMap;
+
+const SPOT: SymbolId = 1;
+const FUTURES: SymbolId = 2;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+const FEE_ACCOUNT: UserId = 100;
+
+fn create_core() -> ExchangeCore {
+    let config = ExchangeConfig {
+        fee_account_uid: Some(FEE_ACCOUNT),
+        audit_interval: 1,
+        ..Default::default()
+    };
+    let spot = CoreSymbolSpecification {
+        taker_fee: 2,
+        maker_fee: 1,
+        ..spot_spec(SPOT, BASE, QUOTE)
+    };
+    let futures = CoreSymbolSpecification {
+        symbol_type: SymbolType::FuturesContract,
+        margin_buy: 500,
+        margin_sell: 500,
+        taker_fee: 3,
+        ..spot_spec(FUTURES, BASE, QUOTE)
+    };
+    let mut core = common::create_core_with(config, &[spot, futures]);
+    for uid in [1, 2, 3] {
+        add_user(&mut core, uid, &[(QUOTE, 100_000), (BASE, 100)]);
+    }
+    core
+}
+
+/// Lệnh GTC với giá dự trữ cao hơn giá lệnh 10
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, symbol: SymbolId, action: OrderAction, price: Price, size: Size) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        reserve_price: price + 10,
+        ..order(uid, order_id, symbol, action, price, size)
+    })
+}
+
+/// Giao ngay có phí, lệnh chờ, vị thế tương lai mở rồi chuyển sang người khác
+fn trade(core: &mut ExchangeCore) {
+    place(core, 2, 201, SPOT, OrderAction::Ask, 100, 5);
+    place(core, 1, 101, SPOT, OrderAction::Bid, 100, 3);
+    place(core, 1, 102, SPOT, OrderAction::Bid, 90, 2);
+
+    place(core, 1, 103, FUTURES, OrderAction::Ask, 1_000, 2);
+    place(core, 2, 202, FUTURES, OrderAction::Bid, 1_000, 2);
+    place(core, 2, 203, FUTURES, OrderAction::Ask, 1_100, 2);
+    place(core, 3, 301, FUTURES, OrderAction::Bid, 1_100, 2);
+    place(core, 3, 302, FUTURES, OrderAction::Bid, 900, 1);
+}
+
+#[test]
+fn test_audit_balanced_after_trading() {
+    let mut core = create_core();
+    trade(&mut core);
+
+    let report = core.audit().unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+
+    // User 2 đã nhận lãi 200, khoản lỗ tương ứng còn nằm trong giá vốn vị thế của user 1 và user 3
+    let quote = report.currencies[&QUOTE];
+    assert_eq!(quote.position_basis, -200);
+    assert_eq!(quote.net_deposits, 300_000);
+    assert_eq!(quote.difference(), 0);
+    assert!(core.get_balance(FEE_ACCOUNT, QUOTE).unwrap().available > 0);
+    assert_eq!((report.currencies[&BASE].available, report.currencies[&BASE].locked), (298, 2));
+}
+
+#[test]
+fn test_audit_totals_beyond_i64() {
+    let mut core = create_core();
+    for uid in [1, 2, 3] {
+        core.submit_command(OrderCommand::balance_adjustment(uid, QUOTE, i64::MAX - 100_000, 50));
+    }
+    place(&mut core, 1, 101, FUTURES, OrderAction::Ask, 1_000, 2);
+    place(&mut core, 2, 201, FUTURES, OrderAction::Bid, 1_000, 2);
+
+    // Mỗi số dư hợp lệ nhưng tổng theo loại tiền vượt i64
+    let report = core.audit().unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    let quote = report.currencies[&QUOTE];
+    assert_eq!(quote.net_deposits, 3 * i64::MAX as i128);
+    assert_eq!(quote.available + quote.locked + quote.position_basis, 3 * i64::MAX as i128);
+}
+
+#[test]
+fn test_audit_offline_snapshot() {
+    let dir = std::env::temp_dir().join(format!("audit_snapshot_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut core = create_core();
+    trade(&mut core);
+    core.enable_snapshotting(&dir).unwrap();
+    core.take_snapshot(1).unwrap();
+
+    let store = matching_core::core::snapshot::SnapshotStore::new(&dir).unwrap();
+    let report = audit::audit_snapshot(store.load_snapshot(1).unwrap());
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_audit_reports_holds_without_resting_orders() {
+    let mut core = create_core();
+    trade(&mut core);
+
+    // Mất sổ lệnh: khoản giữ của lệnh chờ không còn lệnh tương ứng
+    let mut state = core.serialize_state();
+    state.pipeline_state.matching_engines = vec![MatchingEngineState {
+        shard_id: 0,
+        shard_mask: 0,
+        order_books: HashMap::new(),
+        price_bands: HashMap::new(),
+        sessions: HashMap::new(),
+    }];
+
+    let report = audit::audit_snapshot(state);
+    assert_eq!(
+        report.discrepancies,
+        vec![
+            AuditDiscrepancy::OrderHold { uid: 1, symbol: SPOT, order_id: 102, held_size: 2, resting_size: 0 },
+            AuditDiscrepancy::OrderHold { uid: 2, symbol: SPOT, order_id: 201, held_size: 2, resting_size: 0 },
+            AuditDiscrepancy::OrderHold { uid: 3, symbol: FUTURES, order_id: 302, held_size: 1, resting_size: 0 },
+        ]
+    );
+}
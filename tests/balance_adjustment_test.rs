@@ -0,0 +1,81 @@
+mod common;
+
+use common::{add_user, balance};
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+const QUOTE: Currency = 2;
+
+/// User 1 chưa có số dư
+fn create_core() -> ExchangeCore {
+    let mut core = common::create_core(&[]);
+    add_user(&mut core, 1, &[]);
+    core
+}
+
+#[test]
+fn test_duplicate_transaction_rejected() {
+    let mut core = create_core();
+
+    let first = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 1_000, 7));
+    assert_eq!(first.result_code, CommandResultCode::Success);
+
+    // Dịch vụ nạp tiền gửi lại do timeout
+    let retry = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 1_000, 7));
+    assert_eq!(retry.result_code, CommandResultCode::UserMgmtBalanceAdjustmentAlreadyApplied);
+    assert_eq!(balance(&core, 1, QUOTE).available, 1_000);
+
+    let withdrawal = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, -400, 8));
+    assert_eq!(withdrawal.result_code, CommandResultCode::Success);
+    let withdrawal_retry = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, -400, 8));
+    assert_eq!(withdrawal_retry.result_code, CommandResultCode::UserMgmtBalanceAdjustmentAlreadyApplied);
+    assert_eq!(balance(&core, 1, QUOTE).available, 600);
+}
+
+#[test]
+fn test_withdrawal_below_zero_rejected() {
+    let mut core = create_core();
+    core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 500, 1));
+
+    let withdrawal = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, -501, 2));
+    assert_eq!(withdrawal.result_code, CommandResultCode::UserMgmtBalanceAdjustmentNsf);
+    assert_eq!(balance(&core, 1, QUOTE).available, 500);
+
+    // Lệnh bị từ chối không ghi nhận mã giao dịch, có thể gửi lại khi đủ số dư
+    core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 1, 3));
+    let retry = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, -501, 2));
+    assert_eq!(retry.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1, QUOTE).available, 0);
+}
+
+#[test]
+fn test_invalid_adjustments_rejected() {
+    let mut core = create_core();
+
+    let zero = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 0, 1));
+    assert_eq!(zero.result_code, CommandResultCode::UserMgmtBalanceAdjustmentZero);
+
+    let unknown = core.submit_command(OrderCommand::balance_adjustment(2, QUOTE, 100, 1));
+    assert_eq!(unknown.result_code, CommandResultCode::AuthInvalidUser);
+}
+
+#[test]
+fn test_applied_transactions_persisted_in_snapshot() {
+    let dir = std::env::temp_dir().join(format!("balance_adjustment_snapshot_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut core = create_core();
+    core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 1_000, 42));
+    core.enable_snapshotting(&dir).unwrap();
+    core.take_snapshot(1).unwrap();
+
+    let mut restored = ExchangeCore::new(ExchangeConfig::default());
+    restored.enable_snapshotting(&dir).unwrap();
+    assert!(restored.load_latest_snapshot().unwrap());
+
+    let retry = restored.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 1_000, 42));
+    assert_eq!(retry.result_code, CommandResultCode::UserMgmtBalanceAdjustmentAlreadyApplied);
+    assert_eq!(balance(&restored, 1, QUOTE).available, 1_000);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
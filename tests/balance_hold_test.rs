@@ -0,0 +1,109 @@
+mod common;
+
+use common::{add_user, balance, order, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::ExchangeCore;
+use matching_core::core::users::AccountBalance;
+
+const SPOT: SymbolId = 1;
+const FUTURES: SymbolId = 2;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+
+fn create_core() -> ExchangeCore {
+    let futures = CoreSymbolSpecification {
+        symbol_type: SymbolType::FuturesContract,
+        margin_buy: 500,
+        margin_sell: 500,
+        ..spot_spec(FUTURES, BASE, QUOTE)
+    };
+    let mut core = common::create_core(&[spot_spec(SPOT, BASE, QUOTE), futures]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, 10_000), (BASE, 100)]);
+    }
+    core
+}
+
+fn modify(core: &mut ExchangeCore, command: OrderCommandType, uid: UserId, order_id: OrderId, size: Size) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command,
+        uid,
+        order_id,
+        symbol: SPOT,
+        size,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_cancel_releases_full_hold() {
+    let mut core = create_core();
+
+    // Giữ theo giá dự trữ 120, không phải giá lệnh
+    core.submit_command(OrderCommand { reserve_price: 120, ..order(1, 101, SPOT, OrderAction::Bid, 100, 5) });
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_400, locked: 600 });
+    assert_eq!(core.get_user_profile(1).unwrap().holds[&(SPOT, 101)].size, 5);
+
+    core.submit_command(order(1, 102, SPOT, OrderAction::Ask, 200, 4));
+    assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 96, locked: 4 });
+
+    modify(&mut core, OrderCommandType::CancelOrder, 1, 101, 0);
+    modify(&mut core, OrderCommandType::CancelOrder, 1, 102, 0);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 10_000, locked: 0 });
+    assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 100, locked: 0 });
+    assert!(core.get_user_profile(1).unwrap().holds.is_empty());
+}
+
+#[test]
+fn test_partial_fill_and_reduce_release_exact_hold() {
+    let mut core = create_core();
+
+    core.submit_command(order(2, 201, SPOT, OrderAction::Ask, 100, 3));
+    core.submit_command(OrderCommand { reserve_price: 110, ..order(1, 101, SPOT, OrderAction::Bid, 100, 5) });
+
+    // Khớp 3 @ 100, 2 lot còn lại vẫn giữ theo giá dự trữ 110
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_480, locked: 220 });
+    assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 103, locked: 0 });
+    assert_eq!(balance(&core, 2, QUOTE), AccountBalance { available: 10_300, locked: 0 });
+    assert_eq!(balance(&core, 2, BASE), AccountBalance { available: 97, locked: 0 });
+
+    let reduce = modify(&mut core, OrderCommandType::ReduceOrder, 1, 101, 1);
+    assert_eq!(reduce.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_590, locked: 110 });
+
+    modify(&mut core, OrderCommandType::CancelOrder, 1, 101, 0);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_700, locked: 0 });
+}
+
+#[test]
+fn test_position_margin_reported_as_locked() {
+    let mut core = create_core();
+
+    core.submit_command(order(2, 201, FUTURES, OrderAction::Ask, 1_000, 4));
+    core.submit_command(order(1, 101, FUTURES, OrderAction::Bid, 1_000, 6));
+
+    // Ký quỹ vị thế 4 lot + giữ cho 2 lot còn chờ
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 7_000, locked: 3_000 });
+    assert_eq!(balance(&core, 2, QUOTE), AccountBalance { available: 8_000, locked: 2_000 });
+
+    // Đóng vị thế: ký quỹ vị thế được mở khóa cùng lãi/lỗ
+    core.submit_command(order(2, 202, FUTURES, OrderAction::Bid, 1_100, 4));
+    core.submit_command(order(1, 102, FUTURES, OrderAction::Ask, 1_100, 4));
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_400, locked: 1_000 });
+    assert_eq!(balance(&core, 2, QUOTE), AccountBalance { available: 9_600, locked: 0 });
+    assert_eq!(balance(&core, 1, QUOTE).total() + balance(&core, 2, QUOTE).total(), 20_000);
+}
+
+#[test]
+fn test_duplicate_order_id_rejected() {
+    let mut core = create_core();
+
+    core.submit_command(order(1, 101, SPOT, OrderAction::Bid, 100, 5));
+    let duplicate = core.submit_command(order(1, 101, SPOT, OrderAction::Bid, 90, 2));
+    assert_eq!(duplicate.result_code, CommandResultCode::RiskDuplicateOrderId);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_500, locked: 500 });
+
+    // Cùng mã lệnh trên công cụ khác là lệnh khác
+    let other_symbol = core.submit_command(order(1, 101, FUTURES, OrderAction::Bid, 1_000, 1));
+    assert_eq!(other_symbol.result_code, CommandResultCode::Success);
+}
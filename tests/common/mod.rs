@@ -0,0 +1,103 @@
+//! Dựng sẵn lõi sàn, người dùng và lệnh dùng chung cho các bài kiểm thử tích hợp.
+//! Mỗi tệp kiểm thử chỉ dùng một phần các hàm nên tắt cảnh báo hàm không dùng.
+#![allow(dead_code)]
+
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::users::AccountBalance;
+
+/// Lõi sàn cấu hình mặc định với các công cụ cho trước
+pub fn create_core(specs: &[CoreSymbolSpecification]) -> ExchangeCore {
+    create_core_with(ExchangeConfig::default(), specs)
+}
+
+pub fn create_core_with(config: ExchangeConfig, specs: &[CoreSymbolSpecification]) -> ExchangeCore {
+    let mut core = ExchangeCore::new(config);
+    for spec in specs {
+        core.add_symbol(spec.clone());
+    }
+    core
+}
+
+/// Thêm người dùng và nạp số dư ban đầu (mã giao dịch nạp là mã loại tiền)
+pub fn add_user(core: &mut ExchangeCore, uid: UserId, deposits: &[(Currency, i64)]) {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::AddUser,
+        uid,
+        ..Default::default()
+    });
+    for &(currency, amount) in deposits {
+        core.submit_command(OrderCommand::balance_adjustment(uid, currency, amount, currency as u64));
+    }
+}
+
+/// Số dư khả dụng và đang giữ của người dùng
+pub fn balance(core: &ExchangeCore, uid: UserId, currency: Currency) -> AccountBalance {
+    core.get_balance(uid, currency).unwrap()
+}
+
+/// Số dư khả dụng của người dùng
+pub fn available(core: &ExchangeCore, uid: UserId, currency: Currency) -> i64 {
+    balance(core, uid, currency).available
+}
+
+/// Công cụ giao ngay base/quote, các quy tắc giao dịch để mặc định
+pub fn spot_spec(symbol_id: SymbolId, base_currency: Currency, quote_currency: Currency) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency,
+        quote_currency,
+        ..Default::default()
+    }
+}
+
+/// Lệnh đặt GTC, giá dự trữ bằng giá lệnh
+pub fn order(uid: UserId, order_id: OrderId, symbol: SymbolId, action: OrderAction, price: Price, size: Size) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        ..Default::default()
+    }
+}
+
+/// Gửi lệnh đặt GTC vào lõi sàn
+pub fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, symbol: SymbolId, action: OrderAction, price: Price, size: Size) -> OrderCommand {
+    core.submit_command(order(uid, order_id, symbol, action, price, size))
+}
+
+/// (lệnh đối ứng, khối lượng) của các sự kiện khớp lệnh
+pub fn fills(cmd: &OrderCommand) -> Vec<(OrderId, Size)> {
+    cmd.matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::Trade)
+        .map(|e| (e.matched_order_id, e.size))
+        .collect()
+}
+
+/// Công cụ của các bài kiểm thử trực tiếp trên sổ lệnh
+pub const BOOK_SYMBOL: SymbolId = 1;
+
+pub fn book_spec() -> CoreSymbolSpecification {
+    spot_spec(BOOK_SYMBOL, 0, 1)
+}
+
+/// Lệnh đặt trực tiếp vào sổ lệnh: mỗi lệnh một người dùng (uid = mã lệnh)
+pub fn book_order(order_id: OrderId, action: OrderAction, order_type: OrderType, price: Price, size: Size) -> OrderCommand {
+    OrderCommand {
+        order_type,
+        ..order(order_id, order_id, BOOK_SYMBOL, action, price, size)
+    }
+}
+
+/// (loại sự kiện, lệnh đối ứng, khối lượng) của các sự kiện khớp lệnh
+pub fn events(cmd: &OrderCommand) -> Vec<(MatcherEventType, OrderId, Size)> {
+    cmd.matcher_events.iter().map(|e| (e.event_type, e.matched_order_id, e.size)).collect()
+}
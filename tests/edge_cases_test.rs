@@ -13,6 +13,7 @@ fn create_symbol_spec() -> CoreSymbolSpecification {
         maker_fee: 0,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     }
 }
 
@@ -84,7 +85,14 @@ fn test_iceberg_multiple_refresh() {
         ..Default::default()
     };
     book.new_order(&mut bid3);
-    assert_eq!(bid3.matcher_events[0].size, 10);
+    // Mỗi lượt chỉ khớp phần hiển thị 5, làm mới giữa hai lượt
+    let trades: Vec<_> = bid3.matcher_events.iter().map(|e| (e.event_type, e.size)).collect();
+    assert_eq!(trades, vec![
+        (MatcherEventType::Trade, 5),
+        (MatcherEventType::IcebergRefresh, 5),
+        (MatcherEventType::Trade, 5),
+        (MatcherEventType::IcebergRefresh, 5),
+    ]);
     assert_eq!(book.get_total_ask_volume(), 30);
 }
 
@@ -444,7 +452,7 @@ fn test_cancel_stop_order() {
 #[test]
 fn test_all_symbol_types() {
     // Test tất cả loại công cụ giao dịch
-    let types = vec![
+    let types = [
         SymbolType::CurrencyExchangePair,
         SymbolType::FuturesContract,
         SymbolType::PerpetualSwap,
@@ -464,6 +472,7 @@ fn test_all_symbol_types() {
             maker_fee: 0,
             margin_buy: 0,
             margin_sell: 0,
+            ..Default::default()
         };
         
         let mut book = AdvancedOrderBook::new(spec);
@@ -0,0 +1,221 @@
+mod common;
+
+use common::{add_user, available, place, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use ahash::AHashMap;
+
+const SPOT: SymbolId = 1;
+const PERP: SymbolId = 2;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+const FEE_ACCOUNT: UserId = 900;
+const QUOTE_FEE_ACCOUNT: UserId = 901;
+
+fn create_core() -> ExchangeCore {
+    create_core_with(ExchangeConfig {
+        fee_account_uid: Some(FEE_ACCOUNT),
+        ..Default::default()
+    })
+}
+
+fn create_core_with(config: ExchangeConfig) -> ExchangeCore {
+    let spot = CoreSymbolSpecification {
+        taker_fee: 10,
+        maker_fee: 4,
+        ..spot_spec(SPOT, BASE, QUOTE)
+    };
+    let perp = CoreSymbolSpecification {
+        symbol_type: SymbolType::PerpetualSwap,
+        taker_fee: 10,
+        maker_fee: 5,
+        margin_buy: 1000,
+        margin_sell: 1000,
+        ..spot_spec(PERP, BASE, QUOTE)
+    };
+    let mut core = common::create_core_with(config, &[spot, perp]);
+    add_user(&mut core, 1, &[(QUOTE, 1_000_000)]);
+    add_user(&mut core, 2, &[(BASE, 1_000), (QUOTE, 100_000)]);
+    core
+}
+
+fn total_quote(core: &ExchangeCore) -> i64 {
+    [1, 2, FEE_ACCOUNT].iter().map(|&uid| available(core, uid, QUOTE)).sum()
+}
+
+#[test]
+fn test_fees_credited_to_fee_account() {
+    let mut core = create_core();
+    place(&mut core, 2, 201, SPOT, OrderAction::Ask, 100, 10);
+    let taker = place(&mut core, 1, 101, SPOT, OrderAction::Bid, 100, 10);
+
+    assert_eq!(taker.matcher_events.len(), 1);
+    assert_eq!(taker.matcher_events[0].taker_fee, 100);
+    assert_eq!(taker.matcher_events[0].maker_fee, 40);
+
+    assert_eq!(available(&core, 1, QUOTE), 998_900);
+    assert_eq!(available(&core, 1, BASE), 10);
+    assert_eq!(available(&core, 2, QUOTE), 100_960);
+    assert_eq!(available(&core, FEE_ACCOUNT, QUOTE), 140);
+    assert_eq!(total_quote(&core), 1_100_000);
+}
+
+#[test]
+fn test_resting_bid_pays_maker_fee() {
+    let mut core = create_core();
+    place(&mut core, 1, 101, SPOT, OrderAction::Bid, 100, 10);
+    assert_eq!(available(&core, 1, QUOTE), 998_900);
+
+    place(&mut core, 2, 201, SPOT, OrderAction::Ask, 100, 10);
+
+    // Phí giữ theo phí taker, phần dư so với phí maker được hoàn
+    assert_eq!(available(&core, 1, QUOTE), 998_960);
+    assert_eq!(available(&core, 2, QUOTE), 100_900);
+    assert_eq!(available(&core, FEE_ACCOUNT, QUOTE), 140);
+    assert_eq!(total_quote(&core), 1_100_000);
+}
+
+#[test]
+fn test_fee_tier_and_maker_rebate_override() {
+    let mut core = create_core();
+    core.set_fee_tier(1, FeeTier { taker_fee_rate: 5_000, maker_fee_rate: 5_000 });
+    core.set_user_fee_tier(1, Some(1));
+    core.set_fee_override(2, SPOT, Some(FeeOverride { taker_fee: 10, maker_fee: -3 }));
+
+    place(&mut core, 2, 201, SPOT, OrderAction::Ask, 100, 10);
+    let taker = place(&mut core, 1, 101, SPOT, OrderAction::Bid, 100, 10);
+
+    assert_eq!(taker.matcher_events[0].taker_fee, 50);
+    assert_eq!(taker.matcher_events[0].maker_fee, -30);
+    assert_eq!(available(&core, 1, QUOTE), 998_950);
+    assert_eq!(available(&core, 2, QUOTE), 101_030);
+    assert_eq!(available(&core, FEE_ACCOUNT, QUOTE), 20);
+    assert_eq!(total_quote(&core), 1_100_000);
+
+    // Bỏ cấp phí: quay về phí của công cụ
+    core.set_user_fee_tier(1, None);
+    core.set_fee_override(2, SPOT, None);
+    place(&mut core, 2, 202, SPOT, OrderAction::Ask, 100, 1);
+    let taker = place(&mut core, 1, 102, SPOT, OrderAction::Bid, 100, 1);
+    assert_eq!(taker.matcher_events[0].taker_fee, 10);
+    assert_eq!(taker.matcher_events[0].maker_fee, 4);
+}
+
+#[test]
+fn test_large_fee_scaled_by_tier_without_overflow() {
+    let mut core = create_core();
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 3,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: BASE,
+        quote_currency: QUOTE,
+        taker_fee: 1_000_000_000_000_000,
+        ..Default::default()
+    });
+    core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 10_000_000_000_000_000, 99));
+
+    // Phí × tỷ lệ vượt i64 trước khi chia cho FEE_RATE_SCALE
+    core.set_fee_tier(1, FeeTier { taker_fee_rate: 2 * FEE_RATE_SCALE, maker_fee_rate: FEE_RATE_SCALE });
+    core.set_user_fee_tier(1, Some(1));
+
+    place(&mut core, 2, 201, 3, OrderAction::Ask, 100, 1);
+    let taker = place(&mut core, 1, 101, 3, OrderAction::Bid, 100, 1);
+    assert_eq!(taker.result_code, CommandResultCode::Success);
+    assert_eq!(taker.matcher_events[0].taker_fee, 2_000_000_000_000_000);
+    assert_eq!(available(&core, FEE_ACCOUNT, QUOTE), 2_000_000_000_000_000);
+}
+
+#[test]
+fn test_margin_trade_fees_conserved() {
+    let mut core = create_core();
+    core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, -900_000, 999));
+
+    place(&mut core, 2, 201, PERP, OrderAction::Ask, 50_000, 10);
+    place(&mut core, 1, 101, PERP, OrderAction::Bid, 50_000, 10);
+
+    assert_eq!(available(&core, 1, QUOTE), 89_900);
+    assert_eq!(available(&core, 2, QUOTE), 89_950);
+    assert_eq!(available(&core, FEE_ACCOUNT, QUOTE), 150);
+    // Ký quỹ vị thế 2 * 10 * 1000 đang bị giữ
+    assert_eq!(total_quote(&core) + 20_000, 200_000);
+}
+
+#[test]
+fn test_fee_tier_change_while_order_rests_keeps_placement_fee() {
+    let mut core = create_core();
+    place(&mut core, 1, 101, SPOT, OrderAction::Bid, 100, 10);
+    assert_eq!(available(&core, 1, QUOTE), 998_900);
+
+    // Phí maker mới (12) vượt phí đã giữ (10): lệnh đang treo vẫn chịu phí lúc đặt lệnh
+    core.set_fee_tier(1, FeeTier { taker_fee_rate: 30_000, maker_fee_rate: 30_000 });
+    core.set_user_fee_tier(1, Some(1));
+    let taker = place(&mut core, 2, 201, SPOT, OrderAction::Ask, 100, 10);
+    assert_eq!(taker.matcher_events[0].taker_fee, 100);
+    assert_eq!(taker.matcher_events[0].maker_fee, 40);
+    assert_eq!(available(&core, 1, QUOTE), 998_960);
+    assert_eq!(core.get_balance(1, QUOTE).unwrap().locked, 0);
+
+    // Lệnh mới theo cấp phí mới
+    place(&mut core, 2, 202, SPOT, OrderAction::Ask, 100, 1);
+    let taker = place(&mut core, 1, 102, SPOT, OrderAction::Bid, 100, 1);
+    assert_eq!(taker.matcher_events[0].taker_fee, 30);
+    assert_eq!(total_quote(&core), 1_100_000);
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_fee_configuration_commands_are_validated_and_journaled() {
+    let path = std::env::temp_dir().join(format!("fees_journal_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut core = create_core();
+    core.enable_journaling(&path).unwrap();
+    assert_eq!(core.set_user_fee_tier(1, Some(1)).result_code, CommandResultCode::InvalidFeeTier);
+    assert_eq!(core.set_fee_override(2, 99, Some(FeeOverride::default())).result_code, CommandResultCode::InvalidSymbol);
+    assert_eq!(
+        core.set_fee_tier(1, FeeTier { taker_fee_rate: 5_000, maker_fee_rate: 5_000 }).result_code,
+        CommandResultCode::Success
+    );
+    assert_eq!(core.set_user_fee_tier(1, Some(1)).result_code, CommandResultCode::Success);
+    drop(core);
+
+    // Biểu phí được khôi phục khi phát lại nhật ký
+    let mut core = create_core();
+    core.replay_journal(&path).unwrap();
+    place(&mut core, 2, 201, SPOT, OrderAction::Ask, 100, 10);
+    let taker = place(&mut core, 1, 101, SPOT, OrderAction::Bid, 100, 10);
+    assert_eq!(taker.matcher_events[0].taker_fee, 50);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_fees_credited_to_currency_fee_account() {
+    let mut core = create_core_with(ExchangeConfig {
+        fee_account_uid: Some(FEE_ACCOUNT),
+        fee_accounts: AHashMap::from_iter([(QUOTE, QUOTE_FEE_ACCOUNT)]),
+        ..Default::default()
+    });
+    // Công cụ yết giá bằng tiền BASE: phí vào tài khoản chung
+    const INVERSE: SymbolId = 3;
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: INVERSE,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: QUOTE,
+        quote_currency: BASE,
+        taker_fee: 1,
+        maker_fee: 1,
+        ..Default::default()
+    });
+
+    place(&mut core, 2, 201, SPOT, OrderAction::Ask, 100, 10);
+    place(&mut core, 1, 101, SPOT, OrderAction::Bid, 100, 10);
+    assert_eq!(available(&core, QUOTE_FEE_ACCOUNT, QUOTE), 140);
+    assert_eq!(available(&core, FEE_ACCOUNT, QUOTE), 0);
+
+    place(&mut core, 2, 202, INVERSE, OrderAction::Bid, 10, 10);
+    place(&mut core, 1, 102, INVERSE, OrderAction::Ask, 10, 10);
+    assert_eq!(available(&core, FEE_ACCOUNT, BASE), 20);
+    assert_eq!(available(&core, QUOTE_FEE_ACCOUNT, BASE), 0);
+    assert!(core.audit().unwrap().is_balanced());
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use common::{add_user, available, order};
+use matching_core::api::*;
+use matching_core::core::exchange::ExchangeCore;
+
+const SYMBOL: SymbolId = 10;
+const QUOTE: Currency = 2;
+
+fn create_perpetual_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: SYMBOL,
+        symbol_type: SymbolType::PerpetualSwap,
+        base_currency: 1,
+        quote_currency: QUOTE,
+        margin_buy: 1000,
+        margin_sell: 1000,
+        funding_interval: 3600,
+        ..Default::default()
+    }
+}
+
+fn create_core() -> ExchangeCore {
+    let mut core = common::create_core(&[create_perpetual_spec()]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, 100_000)]);
+    }
+    core
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, action: OrderAction, price: Price, size: Size, order_type: OrderType) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        order_type,
+        ..order(uid, order_id, SYMBOL, action, price, size)
+    })
+}
+
+fn mark_price(core: &mut ExchangeCore, price: Price, rate: i64, timestamp: i64) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::UpdateMarkPrice,
+        symbol: SYMBOL,
+        price,
+        index_price: Some(price),
+        funding_rate: Some(rate),
+        timestamp,
+        ..Default::default()
+    })
+}
+
+/// User 1 short 10, user 2 long 10 @ 50000
+fn open_positions(core: &mut ExchangeCore) {
+    place(core, 1, 101, OrderAction::Ask, 50_000, 10, OrderType::Gtc);
+    let taker = place(core, 2, 201, OrderAction::Bid, 50_000, 10, OrderType::Ioc);
+    assert_eq!(taker.matcher_events.len(), 1);
+    assert_eq!(taker.matcher_events[0].event_type, MatcherEventType::Trade);
+}
+
+#[test]
+fn test_margin_positions_opened_on_trade() {
+    let mut core = create_core();
+    open_positions(&mut core);
+
+    let short = &core.get_user_profile(1).unwrap().positions[&SYMBOL];
+    assert_eq!(short.net_volume(), -10);
+    assert_eq!(short.pending_sell_size, 0);
+
+    let long = &core.get_user_profile(2).unwrap().positions[&SYMBOL];
+    assert_eq!(long.net_volume(), 10);
+
+    // Ký quỹ vị thế 10 * 1000 vẫn bị giữ
+    assert_eq!(available(&core, 1, QUOTE), 90_000);
+    assert_eq!(available(&core, 2, QUOTE), 90_000);
+}
+
+#[test]
+fn test_funding_settled_at_interval() {
+    let mut core = create_core();
+    open_positions(&mut core);
+
+    // Lần đầu chỉ lên lịch
+    let first = mark_price(&mut core, 50_000, 1000, 100);
+    assert_eq!(first.result_code, CommandResultCode::Success);
+    assert!(first.matcher_events.is_empty());
+
+    // Chưa tới mốc funding
+    let before = mark_price(&mut core, 50_000, 1000, 3599);
+    assert!(before.matcher_events.is_empty());
+
+    // Tới mốc: 50000 * 0.1% = 50 mỗi lot, long trả cho short
+    let settle = mark_price(&mut core, 50_000, 1000, 3600);
+    assert_eq!(settle.matcher_events.len(), 2);
+    assert!(settle.matcher_events.iter().all(|e| e.event_type == MatcherEventType::Funding));
+    assert_eq!(settle.matcher_events[0].matched_order_uid, 1);
+    assert_eq!(settle.matcher_events[0].size, -10);
+    assert_eq!(settle.matcher_events[0].amount, 500);
+    assert_eq!(settle.matcher_events[1].matched_order_uid, 2);
+    assert_eq!(settle.matcher_events[1].amount, -500);
+    assert_eq!(settle.matcher_events.iter().map(|e| e.amount).sum::<i64>(), 0);
+
+    assert_eq!(available(&core, 1, QUOTE), 90_500);
+    assert_eq!(available(&core, 2, QUOTE), 89_500);
+
+    // Tỷ lệ âm: short trả cho long ở mốc kế tiếp
+    let negative = mark_price(&mut core, 50_000, -1000, 7200);
+    assert_eq!(negative.matcher_events.len(), 2);
+    assert_eq!(available(&core, 1, QUOTE), 90_000);
+    assert_eq!(available(&core, 2, QUOTE), 90_000);
+}
+
+#[test]
+fn test_late_update_settles_every_elapsed_interval() {
+    let mut core = create_core();
+    open_positions(&mut core);
+    mark_price(&mut core, 50_000, 1000, 100);
+
+    // Cập nhật trễ qua ba mốc 3600, 7200, 10800: thanh toán cả ba chu kỳ trong một lần
+    let late = mark_price(&mut core, 50_000, 1000, 11_000);
+    assert_eq!(late.matcher_events.len(), 2);
+    assert_eq!(late.matcher_events[0].matched_order_uid, 1);
+    assert_eq!(late.matcher_events[0].amount, 1_500);
+    assert_eq!(late.matcher_events[1].matched_order_uid, 2);
+    assert_eq!(late.matcher_events[1].amount, -1_500);
+    assert_eq!(available(&core, 1, QUOTE), 91_500);
+    assert_eq!(available(&core, 2, QUOTE), 88_500);
+
+    // Mốc kế tiếp là 14400: không thanh toán lại các chu kỳ đã trả
+    assert!(mark_price(&mut core, 50_000, 1000, 14_399).matcher_events.is_empty());
+    let next = mark_price(&mut core, 50_000, 1000, 14_400);
+    assert_eq!(next.matcher_events[0].amount, 500);
+    assert_eq!(available(&core, 1, QUOTE), 92_000);
+}
+
+#[test]
+fn test_close_position_realizes_pnl() {
+    let mut core = create_core();
+    open_positions(&mut core);
+
+    // User 1 mua đóng short, user 2 bán đóng long @ 49000
+    place(&mut core, 1, 102, OrderAction::Bid, 49_000, 10, OrderType::Gtc);
+    place(&mut core, 2, 202, OrderAction::Ask, 49_000, 10, OrderType::Ioc);
+
+    assert!(core.get_user_profile(1).unwrap().positions.is_empty());
+    assert!(core.get_user_profile(2).unwrap().positions.is_empty());
+
+    assert_eq!(available(&core, 1, QUOTE), 110_000);
+    assert_eq!(available(&core, 2, QUOTE), 90_000);
+    assert_eq!(available(&core, 1, QUOTE) + available(&core, 2, QUOTE), 200_000);
+}
+
+#[test]
+fn test_cancel_releases_margin() {
+    let mut core = create_core();
+    place(&mut core, 1, 101, OrderAction::Bid, 50_000, 5, OrderType::Gtc);
+    assert_eq!(available(&core, 1, QUOTE), 95_000);
+
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1,
+        order_id: 101,
+        symbol: SYMBOL,
+        ..Default::default()
+    });
+    assert_eq!(cancel.result_code, CommandResultCode::Success);
+    assert_eq!(available(&core, 1, QUOTE), 100_000);
+    assert!(core.get_user_profile(1).unwrap().positions.is_empty());
+}
+
+#[test]
+fn test_mark_price_rejected_for_spot() {
+    let mut core = create_core();
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 20,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 1,
+        quote_currency: QUOTE,
+        ..Default::default()
+    });
+
+    let cmd = core.submit_command(OrderCommand {
+        command: OrderCommandType::UpdateMarkPrice,
+        symbol: 20,
+        price: 100,
+        ..Default::default()
+    });
+    assert_eq!(cmd.result_code, CommandResultCode::UnsupportedSymbolType);
+}
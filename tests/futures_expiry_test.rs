@@ -0,0 +1,189 @@
+mod common;
+
+use common::{add_user, available, order};
+use matching_core::api::*;
+use matching_core::core::exchange::ExchangeCore;
+
+const SYMBOL: SymbolId = 50;
+const QUOTE: Currency = 2;
+const EXPIRY: i64 = 5000;
+
+fn create_futures_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: SYMBOL,
+        symbol_type: SymbolType::FuturesContract,
+        base_currency: 1,
+        quote_currency: QUOTE,
+        margin_buy: 1000,
+        margin_sell: 1000,
+        expiry_time: EXPIRY,
+        ..Default::default()
+    }
+}
+
+fn create_core() -> ExchangeCore {
+    let mut core = common::create_core(&[create_futures_spec()]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, 100_000)]);
+    }
+    core
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, action: OrderAction, price: Price, size: Size, timestamp: i64) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        timestamp,
+        ..order(uid, order_id, SYMBOL, action, price, size)
+    })
+}
+
+fn settle(core: &mut ExchangeCore, symbol: SymbolId, settlement_price: Price, timestamp: i64) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::SettleFutures,
+        symbol,
+        price: settlement_price,
+        timestamp,
+        ..Default::default()
+    })
+}
+
+/// User 1 short 10, user 2 long 10 @ 50000; user 1 còn một lệnh mua chờ
+fn open_positions(core: &mut ExchangeCore) {
+    place(core, 1, 101, OrderAction::Ask, 50_000, 10, 0);
+    place(core, 2, 201, OrderAction::Bid, 50_000, 10, 0);
+    place(core, 1, 102, OrderAction::Bid, 45_000, 2, 0);
+    assert_eq!(available(core, 1, QUOTE), 88_000);
+    assert_eq!(available(core, 2, QUOTE), 90_000);
+}
+
+#[test]
+fn test_trading_halted_at_expiry() {
+    let mut core = create_core();
+    open_positions(&mut core);
+
+    let before = place(&mut core, 2, 202, OrderAction::Bid, 45_000, 1, EXPIRY - 1);
+    assert_eq!(before.result_code, CommandResultCode::Success);
+
+    let halted = place(&mut core, 2, 203, OrderAction::Bid, 45_000, 1, EXPIRY);
+    assert_eq!(halted.result_code, CommandResultCode::TradingHalted);
+    // Lệnh chờ được hủy ngay tại thời điểm đáo hạn, không chờ lệnh quyết toán
+    assert_eq!(available(&core, 1, QUOTE), 90_000);
+    assert_eq!(available(&core, 2, QUOTE), 90_000);
+    assert_eq!(core.get_user_profile(2).unwrap().positions[&SYMBOL].pending_buy_size, 0);
+}
+
+#[test]
+fn test_amend_and_move_rejected_after_expiry() {
+    let mut core = create_core();
+    open_positions(&mut core);
+    let amend = core.submit_command(OrderCommand {
+        command: OrderCommandType::AmendOrder,
+        uid: 1,
+        order_id: 102,
+        symbol: SYMBOL,
+        price: 45_000,
+        reserve_price: 45_000,
+        size: 5,
+        action: OrderAction::Bid,
+        timestamp: EXPIRY,
+        ..Default::default()
+    });
+    assert_eq!(amend.result_code, CommandResultCode::TradingHalted);
+    let moved = core.submit_command(OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1,
+        order_id: 102,
+        symbol: SYMBOL,
+        price: 46_000,
+        timestamp: EXPIRY + 1,
+        ..Default::default()
+    });
+    assert_eq!(moved.result_code, CommandResultCode::TradingHalted);
+    assert_eq!(available(&core, 1, QUOTE), 90_000);
+}
+
+#[test]
+fn test_symbol_without_expiry_time_never_settles() {
+    let mut core = create_core();
+    core.add_symbol(CoreSymbolSpecification { symbol_id: 51, expiry_time: 0, ..create_futures_spec() });
+    let cmd = core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 1,
+        order_id: 111,
+        symbol: 51,
+        price: 45_000,
+        reserve_price: 45_000,
+        size: 2,
+        action: OrderAction::Bid,
+        order_type: OrderType::Gtc,
+        timestamp: i64::MAX - 1,
+        ..Default::default()
+    });
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    let cmd = settle(&mut core, 51, 52_000, i64::MAX);
+    assert_eq!(cmd.result_code, CommandResultCode::SymbolNotExpired);
+    assert_eq!(core.get_user_profile(1).unwrap().positions[&51].pending_buy_size, 2);
+}
+
+#[test]
+fn test_final_settlement_closes_positions() {
+    let mut core = create_core();
+    open_positions(&mut core);
+
+    let cmd = settle(&mut core, SYMBOL, 52_000, EXPIRY);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(cmd.matcher_events.len(), 2);
+    assert!(cmd.matcher_events.iter().all(|e| e.event_type == MatcherEventType::Settlement));
+    assert_eq!(cmd.matcher_events[0].matched_order_uid, 1);
+    assert_eq!(cmd.matcher_events[0].size, -10);
+    assert_eq!(cmd.matcher_events[0].amount, -20_000);
+    assert_eq!(cmd.matcher_events[1].matched_order_uid, 2);
+    assert_eq!(cmd.matcher_events[1].amount, 20_000);
+
+    // Lệnh chờ được hủy và hoàn ký quỹ, ký quỹ vị thế được hoàn cùng lãi/lỗ
+    assert_eq!(available(&core, 1, QUOTE), 80_000);
+    assert_eq!(available(&core, 2, QUOTE), 120_000);
+    assert!(core.get_user_profile(1).unwrap().positions.is_empty());
+    assert!(core.get_user_profile(2).unwrap().positions.is_empty());
+
+    // Công cụ đã gỡ khỏi engine rủi ro và sổ lệnh
+    let late = place(&mut core, 1, 103, OrderAction::Bid, 50_000, 1, 0);
+    assert_eq!(late.result_code, CommandResultCode::InvalidSymbol);
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1,
+        order_id: 102,
+        symbol: SYMBOL,
+        ..Default::default()
+    });
+    assert_eq!(cancel.result_code, CommandResultCode::MatchingInvalidOrderBookId);
+}
+
+#[test]
+fn test_settlement_rejected_before_expiry_or_for_perpetual() {
+    let mut core = create_core();
+    open_positions(&mut core);
+
+    let early = settle(&mut core, SYMBOL, 52_000, EXPIRY - 1);
+    assert_eq!(early.result_code, CommandResultCode::SymbolNotExpired);
+    assert_eq!(core.get_user_profile(1).unwrap().positions[&SYMBOL].pending_buy_size, 2);
+    assert_eq!(available(&core, 1, QUOTE), 88_000);
+
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 60,
+        symbol_type: SymbolType::PerpetualSwap,
+        quote_currency: QUOTE,
+        ..Default::default()
+    });
+    let perpetual = settle(&mut core, 60, 52_000, EXPIRY);
+    assert_eq!(perpetual.result_code, CommandResultCode::UnsupportedSymbolType);
+}
+
+#[test]
+fn test_remove_symbol() {
+    let mut core = create_core();
+    core.remove_symbol(SYMBOL);
+
+    let cmd = place(&mut core, 1, 101, OrderAction::Bid, 50_000, 1, 0);
+    assert_eq!(cmd.result_code, CommandResultCode::InvalidSymbol);
+    assert_eq!(available(&core, 1, QUOTE), 100_000);
+}
@@ -0,0 +1,188 @@
+mod common;
+
+use common::{add_user, balance, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::users::AccountBalance;
+use std::sync::{Arc, Mutex};
+
+const SPOT: SymbolId = 1;
+const SPOT_2: SymbolId = 2;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+
+fn create_core(risk_engines_num: usize) -> ExchangeCore {
+    let config = ExchangeConfig {
+        risk_engines_num,
+        matching_engines_num: 2,
+        ..Default::default()
+    };
+    let mut core = common::create_core_with(config, &[spot_spec(SPOT, BASE, QUOTE), spot_spec(SPOT_2, BASE, QUOTE)]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, 10_000), (BASE, 100)]);
+    }
+    core
+}
+
+/// Lệnh GTC 10 @ 100 gửi tại thời điểm cho trước
+fn order(uid: UserId, order_id: OrderId, symbol: SymbolId, action: OrderAction, timestamp: i64) -> OrderCommand {
+    OrderCommand {
+        timestamp,
+        ..common::order(uid, order_id, symbol, action, 100, 10)
+    }
+}
+
+fn heartbeat(uid: UserId, timeout: Option<i64>, timestamp: i64) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::Heartbeat,
+        uid,
+        heartbeat_timeout: timeout,
+        timestamp,
+        ..Default::default()
+    }
+}
+
+/// Lệnh / heartbeat gửi từ phiên kết nối cho trước
+fn from_session(session_id: SessionId, cmd: OrderCommand) -> OrderCommand {
+    OrderCommand { session_id, ..cmd }
+}
+
+fn command(command: OrderCommandType, uid: UserId) -> OrderCommand {
+    OrderCommand {
+        command,
+        uid,
+        ..Default::default()
+    }
+}
+
+
+#[test]
+fn test_missed_heartbeat_cancels_session_orders() {
+    for risk_engines_num in [1, 2] {
+        let mut core = create_core(risk_engines_num);
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let sink = results.clone();
+        core.set_result_consumer(Arc::new(move |cmd: &OrderCommand| {
+            sink.lock().unwrap().push((cmd.command, cmd.uid, cmd.matcher_events.len()));
+        }));
+
+        assert_eq!(core.submit_command(heartbeat(1, Some(100), 1_000)).result_code, CommandResultCode::Success);
+        core.submit_command(order(1, 11, SPOT, OrderAction::Bid, 1_010));
+        core.submit_command(order(1, 12, SPOT_2, OrderAction::Ask, 1_020));
+        core.submit_command(order(2, 21, SPOT_2, OrderAction::Ask, 1_030));
+        assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_000, locked: 1_000 });
+
+        // Gia hạn theo thời hạn đã đăng ký: hạn mới là 1_150
+        assert_eq!(core.submit_command(heartbeat(1, None, 1_050)).result_code, CommandResultCode::Success);
+        core.submit_command(OrderCommand { timestamp: 1_150, ..command(OrderCommandType::Nop, 2) });
+        assert_eq!(balance(&core, 1, QUOTE).locked, 1_000);
+
+        // Lệnh đầu tiên sau hạn (của người dùng khác) kích hoạt hủy lệnh trước khi được xử lý
+        results.lock().unwrap().clear();
+        core.submit_command(OrderCommand { timestamp: 1_151, ..command(OrderCommandType::Nop, 2) });
+        assert_eq!(
+            results.lock().unwrap()[..2],
+            [(OrderCommandType::CancelOrder, 1, 1), (OrderCommandType::CancelOrder, 1, 1)]
+        );
+        assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 10_000, locked: 0 });
+        assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 100, locked: 0 });
+        assert_eq!(balance(&core, 2, BASE), AccountBalance { available: 90, locked: 10 });
+
+        // Phiên đã ngắt: đăng ký bị xóa, lệnh mới không bị hủy nữa
+        assert_eq!(
+            core.submit_command(heartbeat(1, None, 1_200)).result_code,
+            CommandResultCode::UserMgmtHeartbeatNotRegistered
+        );
+        core.submit_command(order(1, 13, SPOT, OrderAction::Bid, 1_300));
+        core.submit_command(OrderCommand { timestamp: 5_000, ..command(OrderCommandType::Nop, 2) });
+        assert_eq!(balance(&core, 1, QUOTE).locked, 1_000);
+        assert!(core.audit().unwrap().is_balanced());
+    }
+}
+
+#[test]
+fn test_expired_session_cancels_only_its_own_orders() {
+    for risk_engines_num in [1, 2] {
+        let mut core = create_core(risk_engines_num);
+        core.submit_command(from_session(1, heartbeat(1, Some(100), 0)));
+        core.submit_command(from_session(2, heartbeat(1, Some(500), 0)));
+        assert_eq!(core.submit_command(from_session(1, order(1, 11, SPOT, OrderAction::Bid, 10))).result_code, CommandResultCode::Success);
+        core.submit_command(from_session(1, order(1, 12, SPOT_2, OrderAction::Ask, 10)));
+        core.submit_command(from_session(2, order(1, 13, SPOT, OrderAction::Bid, 10)));
+        core.submit_command(from_session(2, order(1, 14, SPOT_2, OrderAction::Ask, 10)));
+        assert_eq!(balance(&core, 1, QUOTE).locked, 2_000);
+        assert_eq!(balance(&core, 1, BASE).locked, 20);
+
+        // Phiên 1 quá hạn: chỉ lệnh 11 và 12 bị hủy, đăng ký của phiên 2 vẫn còn
+        core.submit_command(OrderCommand { timestamp: 101, ..command(OrderCommandType::Nop, 2) });
+        assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_000, locked: 1_000 });
+        assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 90, locked: 10 });
+        assert_eq!(
+            core.submit_command(from_session(1, heartbeat(1, None, 200))).result_code,
+            CommandResultCode::UserMgmtHeartbeatNotRegistered
+        );
+        assert_eq!(core.submit_command(from_session(2, heartbeat(1, None, 200))).result_code, CommandResultCode::Success);
+
+        // Phiên 2 quá hạn sau đó (hạn mới 700): lệnh còn lại của phiên bị hủy
+        core.submit_command(OrderCommand { timestamp: 700, ..command(OrderCommandType::Nop, 2) });
+        assert_eq!(balance(&core, 1, QUOTE).locked, 1_000);
+        core.submit_command(OrderCommand { timestamp: 701, ..command(OrderCommandType::Nop, 2) });
+        assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 10_000, locked: 0 });
+        assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 100, locked: 0 });
+        assert!(core.audit().unwrap().is_balanced());
+    }
+}
+
+#[test]
+fn test_heartbeat_unregister_and_unknown_user() {
+    let mut core = create_core(1);
+    core.submit_command(heartbeat(1, Some(10), 0));
+    core.submit_command(order(1, 11, SPOT, OrderAction::Bid, 5));
+    assert_eq!(core.submit_command(heartbeat(1, Some(0), 8)).result_code, CommandResultCode::Success);
+
+    core.submit_command(OrderCommand { timestamp: 100, ..command(OrderCommandType::Nop, 2) });
+    assert_eq!(balance(&core, 1, QUOTE).locked, 1_000);
+
+    assert_eq!(core.submit_command(heartbeat(42, Some(10), 100)).result_code, CommandResultCode::AuthInvalidUser);
+}
+
+#[test]
+fn test_kill_switch_freezes_user_and_cancels_everything() {
+    let mut core = create_core(2);
+    core.submit_command(order(1, 11, SPOT, OrderAction::Bid, 0));
+    core.submit_command(order(1, 12, SPOT_2, OrderAction::Ask, 0));
+
+    // Bộ lọc của hủy hàng loạt bị bỏ qua: kill switch hủy mọi lệnh
+    let cmd = core.submit_command(OrderCommand {
+        mass_cancel_symbol: Some(SPOT),
+        ..command(OrderCommandType::KillSwitch, 1)
+    });
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(cmd.matcher_events.len(), 2);
+    assert!(cmd.matcher_events.iter().all(|e| matches!(e.event_type, MatcherEventType::MassCancel(_))));
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 10_000, locked: 0 });
+    assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 100, locked: 0 });
+
+    let cmd = core.submit_command(order(1, 13, SPOT, OrderAction::Bid, 0));
+    assert_eq!(cmd.result_code, CommandResultCode::UserMgmtUserSuspended);
+    assert_eq!(balance(&core, 1, QUOTE).locked, 0);
+
+    assert_eq!(core.submit_command(command(OrderCommandType::ResumeUser, 1)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(order(1, 13, SPOT, OrderAction::Bid, 0)).result_code, CommandResultCode::Success);
+
+    // Khóa không hủy lệnh: lệnh đang treo vẫn được hủy bởi người dùng
+    assert_eq!(core.submit_command(command(OrderCommandType::SuspendUser, 1)).result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1, QUOTE).locked, 1_000);
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1,
+        order_id: 13,
+        symbol: SPOT,
+        ..Default::default()
+    });
+    assert_eq!(cancel.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1, QUOTE).locked, 0);
+
+    assert_eq!(core.submit_command(command(OrderCommandType::KillSwitch, 42)).result_code, CommandResultCode::AuthInvalidUser);
+    assert!(core.audit().unwrap().is_balanced());
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{book_order, book_spec, events};
+use matching_core::api::*;
+use matching_core::core::orderbook::{AdvancedOrderBook, OrderBook};
+
+fn hidden_ask(order_id: OrderId, price: Price, size: Size) -> OrderCommand {
+    OrderCommand { hidden: true, ..book_order(order_id, OrderAction::Ask, OrderType::Gtc, price, size) }
+}
+
+#[test]
+fn test_hidden_orders_excluded_from_l2_and_matched_last() {
+    let mut book = AdvancedOrderBook::new(book_spec());
+    book.new_order(&mut hidden_ask(1, 100, 10));
+    book.new_order(&mut book_order(2, OrderAction::Ask, OrderType::Gtc, 100, 5));
+    book.new_order(&mut hidden_ask(3, 101, 5));
+
+    let l2 = book.get_l2_data(10);
+    assert_eq!((l2.ask_prices, l2.ask_volumes), (vec![100], vec![5]));
+    assert_eq!(book.get_total_ask_volume(), 20);
+
+    // Lệnh ẩn đặt trước vẫn khớp sau lệnh hiển thị cùng giá
+    let mut bid = book_order(10, OrderAction::Bid, OrderType::Ioc, 100, 8);
+    book.new_order(&mut bid);
+    assert_eq!(events(&bid), vec![(MatcherEventType::Trade, 2, 5), (MatcherEventType::Trade, 1, 3)]);
+    assert!(book.get_l2_data(10).ask_prices.is_empty());
+}
+
+#[test]
+fn test_hidden_orders_behind_iceberg_refresh() {
+    let mut book = AdvancedOrderBook::new(book_spec());
+    book.new_order(&mut hidden_ask(1, 100, 10));
+    book.new_order(&mut OrderCommand {
+        visible_size: Some(4),
+        ..book_order(2, OrderAction::Ask, OrderType::Iceberg, 100, 12)
+    });
+    assert_eq!(book.get_l2_data(1).ask_volumes, vec![4]);
+
+    // Phần làm mới của iceberg là lệnh hiển thị: khớp hết trước lệnh ẩn
+    let mut bid = book_order(10, OrderAction::Bid, OrderType::Ioc, 100, 15);
+    book.new_order(&mut bid);
+    assert_eq!(
+        events(&bid),
+        vec![
+            (MatcherEventType::Trade, 2, 4),
+            (MatcherEventType::IcebergRefresh, 2, 4),
+            (MatcherEventType::Trade, 2, 4),
+            (MatcherEventType::IcebergRefresh, 2, 4),
+            (MatcherEventType::Trade, 2, 4),
+            (MatcherEventType::Trade, 1, 3),
+        ]
+    );
+    assert_eq!(book.get_total_ask_volume(), 7);
+    assert!(book.get_l2_data(1).ask_volumes.is_empty());
+}
+
+#[test]
+fn test_hidden_post_only() {
+    let mut book = AdvancedOrderBook::new(book_spec());
+
+    // Lệnh Post-Only ẩn được treo nhưng không hiển thị
+    let mut post_only = OrderCommand { hidden: true, ..book_order(1, OrderAction::Ask, OrderType::PostOnly, 100, 5) };
+    book.new_order(&mut post_only);
+    assert!(post_only.matcher_events.is_empty());
+    assert!(book.get_l2_data(1).ask_prices.is_empty());
+
+    // Lệnh Post-Only giao với lệnh ẩn bị từ chối (sẽ lấy thanh khoản)
+    let mut crossing = book_order(2, OrderAction::Bid, OrderType::PostOnly, 100, 5);
+    book.new_order(&mut crossing);
+    assert_eq!(crossing.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(book.get_total_ask_volume(), 5);
+}
+
+#[test]
+fn test_maker_min_quantity_and_all_or_none() {
+    let mut book = AdvancedOrderBook::new(book_spec());
+    book.new_order(&mut OrderCommand { min_quantity: Some(10), ..book_order(1, OrderAction::Ask, OrderType::Gtc, 100, 10) });
+    book.new_order(&mut book_order(2, OrderAction::Ask, OrderType::Gtc, 100, 5));
+    book.new_order(&mut OrderCommand { min_quantity: Some(4), ..book_order(3, OrderAction::Ask, OrderType::Gtc, 101, 10) });
+
+    // Lệnh all-or-none bị bỏ qua khi không khớp đủ, lệnh sau cùng giá vẫn khớp
+    let mut bid = book_order(10, OrderAction::Bid, OrderType::Ioc, 100, 6);
+    book.new_order(&mut bid);
+    assert_eq!(events(&bid), vec![(MatcherEventType::Trade, 2, 5), (MatcherEventType::Reject, 0, 1)]);
+
+    // Dưới mức tối thiểu của lệnh 3: chỉ khớp lệnh all-or-none
+    let mut bid = book_order(11, OrderAction::Bid, OrderType::Ioc, 101, 13);
+    book.new_order(&mut bid);
+    assert_eq!(events(&bid), vec![(MatcherEventType::Trade, 1, 10), (MatcherEventType::Reject, 0, 3)]);
+
+    let mut bid = book_order(12, OrderAction::Bid, OrderType::Ioc, 101, 4);
+    book.new_order(&mut bid);
+    assert_eq!(events(&bid), vec![(MatcherEventType::Trade, 3, 4)]);
+
+    // FOK không tính lệnh có mức tối thiểu lớn hơn khối lượng của mình
+    let mut fok = book_order(13, OrderAction::Bid, OrderType::Fok, 101, 3);
+    book.new_order(&mut fok);
+    assert_eq!(events(&fok), vec![(MatcherEventType::Reject, 0, 3)]);
+}
+
+#[test]
+fn test_taker_min_quantity() {
+    let mut book = AdvancedOrderBook::new(book_spec());
+    book.new_order(&mut book_order(1, OrderAction::Ask, OrderType::Gtc, 100, 3));
+
+    // Không khớp được ngay 5: không khớp, phần còn lại được treo
+    let mut bid = OrderCommand { min_quantity: Some(5), ..book_order(10, OrderAction::Bid, OrderType::Gtc, 100, 10) };
+    book.new_order(&mut bid);
+    assert!(bid.matcher_events.is_empty());
+    assert_eq!(book.get_total_ask_volume(), 3);
+    assert_eq!(book.get_total_bid_volume(), 10);
+
+    // Khi đang treo, mức tối thiểu áp dụng cho lệnh chủ động đối ứng
+    let mut ask = book_order(2, OrderAction::Ask, OrderType::Ioc, 100, 4);
+    book.new_order(&mut ask);
+    assert_eq!(events(&ask), vec![(MatcherEventType::Reject, 0, 4)]);
+
+    let mut ask = book_order(3, OrderAction::Ask, OrderType::Ioc, 100, 6);
+    book.new_order(&mut ask);
+    assert_eq!(events(&ask), vec![(MatcherEventType::Trade, 10, 6)]);
+
+    // Lệnh còn 4 < mức tối thiểu: chỉ cần khớp hết phần còn lại
+    let mut ask = book_order(4, OrderAction::Ask, OrderType::Ioc, 100, 4);
+    book.new_order(&mut ask);
+    assert_eq!(events(&ask), vec![(MatcherEventType::Trade, 10, 4)]);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{book_order, book_spec, events};
+use matching_core::api::*;
+use matching_core::core::orderbook::{AdvancedOrderBook, OrderBook};
+
+fn ask(order_id: OrderId, size: Size, visible_size: Option<Size>, visible_variance: Option<Size>) -> OrderCommand {
+    OrderCommand {
+        order_type: if visible_size.is_some() { OrderType::Iceberg } else { OrderType::Gtc },
+        visible_size,
+        visible_variance,
+        ..book_order(order_id, OrderAction::Ask, OrderType::Gtc, 100, size)
+    }
+}
+
+/// Lệnh mua IOC của người dùng 99
+fn bid(order_id: OrderId, size: Size) -> OrderCommand {
+    OrderCommand {
+        uid: 99,
+        ..book_order(order_id, OrderAction::Bid, OrderType::Ioc, 100, size)
+    }
+}
+
+#[test]
+fn test_iceberg_loses_time_priority_on_refresh() {
+    let mut book = AdvancedOrderBook::new(book_spec());
+    book.new_order(&mut ask(1, 100, Some(5), None));
+    book.new_order(&mut ask(2, 10, None, None));
+    assert_eq!(book.get_l2_data(1).ask_volumes, vec![15]);
+
+    // Phần hiển thị hết → làm mới và xuống sau lệnh 2
+    let mut cmd = bid(10, 12);
+    book.new_order(&mut cmd);
+    assert_eq!(
+        events(&cmd),
+        vec![
+            (MatcherEventType::Trade, 1, 5),
+            (MatcherEventType::IcebergRefresh, 1, 5),
+            (MatcherEventType::Trade, 2, 7),
+        ]
+    );
+
+    // Lệnh 2 đứng trước; lượt sau chỉ còn iceberg
+    let mut cmd = bid(11, 10);
+    book.new_order(&mut cmd);
+    assert_eq!(
+        events(&cmd),
+        vec![
+            (MatcherEventType::Trade, 2, 3),
+            (MatcherEventType::Trade, 1, 5),
+            (MatcherEventType::IcebergRefresh, 1, 5),
+            (MatcherEventType::Trade, 1, 2),
+        ]
+    );
+    assert_eq!(book.get_total_ask_volume(), 88);
+    assert_eq!(book.get_l2_data(1).ask_volumes, vec![3]);
+}
+
+#[test]
+fn test_iceberg_randomized_peaks_are_deterministic() {
+    let run = || {
+        let mut book = AdvancedOrderBook::new(book_spec());
+        book.new_order(&mut ask(1, 1000, Some(10), Some(3)));
+        let mut peaks = vec![book.get_l2_data(1).ask_volumes[0]];
+        for order_id in 10..30 {
+            let mut cmd = bid(order_id, peaks[peaks.len() - 1]);
+            book.new_order(&mut cmd);
+            let refresh = cmd.matcher_events.iter().find(|e| e.event_type == MatcherEventType::IcebergRefresh);
+            peaks.push(refresh.unwrap().size);
+        }
+        peaks
+    };
+
+    let peaks = run();
+    assert!(peaks.iter().all(|peak| (7..=13).contains(peak)), "{peaks:?}");
+    assert!(peaks.iter().any(|&peak| peak != peaks[0]), "{peaks:?}");
+    assert_eq!(peaks, run());
+}
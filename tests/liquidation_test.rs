@@ -0,0 +1,251 @@
+mod common;
+
+use common::{add_user, available, order};
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use std::sync::{Arc, Mutex};
+
+const SYMBOL: SymbolId = 10;
+const QUOTE: Currency = 2;
+const INSURANCE_FUND: UserId = 99;
+
+fn create_perpetual_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: SYMBOL,
+        symbol_type: SymbolType::PerpetualSwap,
+        base_currency: 1,
+        quote_currency: QUOTE,
+        margin_buy: 1000,
+        margin_sell: 1000,
+        maintenance_margin: 500,
+        ..Default::default()
+    }
+}
+
+/// User 1 ký quỹ ít, user 2 và 3 là đối tác, user 99 là quỹ bảo hiểm
+fn create_core() -> ExchangeCore {
+    create_core_with(ExchangeConfig::default())
+}
+
+fn create_core_with(config: ExchangeConfig) -> ExchangeCore {
+    let config = ExchangeConfig {
+        insurance_fund_uid: Some(INSURANCE_FUND),
+        ..config
+    };
+    let mut core = common::create_core_with(config, &[create_perpetual_spec()]);
+    for (uid, amount) in [(1, 12_000), (2, 100_000), (3, 100_000), (INSURANCE_FUND, 50_000)] {
+        add_user(&mut core, uid, &[(QUOTE, amount)]);
+    }
+    core
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, action: OrderAction, price: Price, size: Size, order_type: OrderType) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        order_type,
+        ..order(uid, order_id, SYMBOL, action, price, size)
+    })
+}
+
+fn mark_price(core: &mut ExchangeCore, price: Price) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::UpdateMarkPrice,
+        symbol: SYMBOL,
+        price,
+        ..Default::default()
+    })
+}
+
+/// User 1 long 10 @ 50000 (đối tác user 2), thêm một lệnh bán chờ @ 60000
+fn open_long(core: &mut ExchangeCore) {
+    place(core, 2, 201, OrderAction::Ask, 50_000, 10, OrderType::Gtc);
+    place(core, 1, 101, OrderAction::Bid, 50_000, 10, OrderType::Ioc);
+    place(core, 1, 102, OrderAction::Ask, 60_000, 1, OrderType::Gtc);
+    assert_eq!(core.get_user_profile(1).unwrap().positions[&SYMBOL].net_volume(), 10);
+    assert_eq!(available(core, 1, QUOTE), 1_000);
+}
+
+#[test]
+fn test_no_liquidation_above_maintenance() {
+    let mut core = create_core();
+    open_long(&mut core);
+
+    // Lỗ 600 < 10000 - 5000: vẫn đủ ký quỹ duy trì
+    let cmd = mark_price(&mut core, 49_940);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(core.get_user_profile(1).unwrap().positions[&SYMBOL].net_volume(), 10);
+    assert_eq!(core.get_user_profile(1).unwrap().positions[&SYMBOL].pending_sell_size, 1);
+}
+
+#[test]
+fn test_liquidation_closes_position_and_cancels_orders() {
+    let mut core = create_core();
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let sink = results.clone();
+    core.set_result_consumer(Arc::new(move |cmd: &OrderCommand| {
+        sink.lock().unwrap().push(cmd.clone());
+    }));
+
+    open_long(&mut core);
+    place(&mut core, 3, 301, OrderAction::Bid, 48_500, 10, OrderType::Gtc);
+    results.lock().unwrap().clear();
+
+    // Lỗ 6000 > 10000 - 5000: bị thanh lý
+    mark_price(&mut core, 49_400);
+
+    let commands: Vec<OrderCommandType> = results.lock().unwrap().iter().map(|c| c.command).collect();
+    assert_eq!(
+        commands,
+        vec![
+            OrderCommandType::UpdateMarkPrice,
+            OrderCommandType::CancelOrder,
+            OrderCommandType::Liquidate,
+            OrderCommandType::InsuranceCover,
+        ]
+    );
+
+    let liquidation = results.lock().unwrap()[2].clone();
+    assert_eq!(liquidation.uid, 1);
+    assert_eq!(liquidation.action, OrderAction::Ask);
+    assert_eq!(liquidation.matcher_events.len(), 1);
+    assert_eq!(liquidation.matcher_events[0].matched_order_uid, 3);
+    assert_eq!(liquidation.matcher_events[0].price, 48_500);
+
+    // Vị thế đã đóng, user 3 nhận vị thế long
+    assert!(core.get_user_profile(1).unwrap().positions.is_empty());
+    assert_eq!(core.get_user_profile(3).unwrap().positions[&SYMBOL].net_volume(), 10);
+
+    // Lỗ thực hiện 15000 vượt ký quỹ: quỹ bảo hiểm bù 3000
+    assert_eq!(available(&core, 1, QUOTE), 0);
+    assert_eq!(available(&core, INSURANCE_FUND, QUOTE), 47_000);
+}
+
+#[test]
+fn test_insurance_cover_capped_at_fund_balance() {
+    // User 1 và quỹ bảo hiểm cùng phân đoạn hoặc khác phân đoạn rủi ro
+    for risk_engines_num in [1, 4] {
+        let mut core = create_core_with(ExchangeConfig {
+            risk_engines_num,
+            ..Default::default()
+        });
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let sink = results.clone();
+        core.set_result_consumer(Arc::new(move |cmd: &OrderCommand| {
+            sink.lock().unwrap().push(cmd.clone());
+        }));
+
+        // Quỹ chỉ còn 1000, không đủ bù thâm hụt 3000
+        let withdraw = core.submit_command(OrderCommand::balance_adjustment(INSURANCE_FUND, QUOTE, -49_000, 1_000));
+        assert_eq!(withdraw.result_code, CommandResultCode::Success);
+        open_long(&mut core);
+        place(&mut core, 3, 301, OrderAction::Bid, 48_500, 10, OrderType::Gtc);
+        results.lock().unwrap().clear();
+
+        mark_price(&mut core, 49_400);
+
+        let cover = results
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|c| c.command == OrderCommandType::InsuranceCover)
+            .cloned()
+            .unwrap();
+        assert_eq!(cover.result_code, CommandResultCode::Success);
+        assert_eq!(cover.amount, 1_000, "shards {risk_engines_num}");
+        assert_eq!(cover.matcher_events.len(), 1);
+        assert_eq!(cover.matcher_events[0].event_type, MatcherEventType::InsuranceShortfall);
+        assert_eq!(cover.matcher_events[0].matched_order_uid, 1);
+        assert_eq!(cover.matcher_events[0].amount, 2_000);
+
+        // Quỹ cạn về 0, phần không bù được vẫn là số dư âm của user 1
+        assert_eq!(available(&core, INSURANCE_FUND, QUOTE), 0);
+        assert_eq!(available(&core, 1, QUOTE), -2_000);
+    }
+}
+
+#[test]
+fn test_deposit_accepted_while_balance_negative() {
+    let mut core = create_core();
+    core.submit_command(OrderCommand::balance_adjustment(INSURANCE_FUND, QUOTE, -50_000, 1_000));
+    open_long(&mut core);
+    place(&mut core, 3, 301, OrderAction::Bid, 48_500, 10, OrderType::Gtc);
+
+    // Quỹ bảo hiểm rỗng: user 1 còn thâm hụt 3000
+    mark_price(&mut core, 49_400);
+    assert_eq!(available(&core, 1, QUOTE), -3_000);
+
+    // Nạp tiền vào tài khoản đang âm được nhận dù số dư vẫn âm
+    let deposit = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 1_000, 1_001));
+    assert_eq!(deposit.result_code, CommandResultCode::Success);
+    assert_eq!(available(&core, 1, QUOTE), -2_000);
+
+    // Rút tiền khi số dư âm vẫn bị từ chối
+    let withdrawal = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, -1, 1_002));
+    assert_eq!(withdrawal.result_code, CommandResultCode::UserMgmtBalanceAdjustmentNsf);
+    assert_eq!(available(&core, 1, QUOTE), -2_000);
+}
+
+#[test]
+fn test_external_internal_commands_rejected() {
+    let mut core = create_core();
+    open_long(&mut core);
+
+    let liquidate = core.submit_command(OrderCommand {
+        command: OrderCommandType::Liquidate,
+        uid: 1,
+        symbol: SYMBOL,
+        ..Default::default()
+    });
+    assert_eq!(liquidate.result_code, CommandResultCode::InternalCommandRejected);
+    assert_eq!(core.get_user_profile(1).unwrap().positions[&SYMBOL].net_volume(), 10);
+
+    let cover = core.submit_command(OrderCommand {
+        command: OrderCommandType::InsuranceCover,
+        uid: 1,
+        currency: QUOTE,
+        amount: 10_000,
+        ..Default::default()
+    });
+    assert_eq!(cover.result_code, CommandResultCode::InternalCommandRejected);
+    assert_eq!(available(&core, 1, QUOTE), 1_000);
+    assert_eq!(available(&core, INSURANCE_FUND, QUOTE), 50_000);
+}
+
+#[test]
+fn test_liquidation_rejected_outside_continuous_session() {
+    use TradingSessionState::*;
+    for path in [vec![Halted], vec![ClosingAuction], vec![Closed], vec![Closed, PreOpen], vec![Halted, OpeningAuction]] {
+        let mut core = create_core();
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let sink = results.clone();
+        core.set_result_consumer(Arc::new(move |cmd: &OrderCommand| {
+            sink.lock().unwrap().push(cmd.clone());
+        }));
+        open_long(&mut core);
+        place(&mut core, 3, 301, OrderAction::Bid, 48_500, 10, OrderType::Gtc);
+        for &state in &path {
+            let cmd = core.submit_command(OrderCommand {
+                command: OrderCommandType::SetSessionState,
+                symbol: SYMBOL,
+                session_state: Some(state),
+                ..Default::default()
+            });
+            assert_eq!(cmd.result_code, CommandResultCode::Success);
+        }
+        results.lock().unwrap().clear();
+
+        // Thiếu ký quỹ nhưng phiên không liên tục: lệnh thanh lý bị từ chối, không khớp với lệnh đang treo
+        mark_price(&mut core, 49_400);
+        let liquidation = results
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|c| c.command == OrderCommandType::Liquidate)
+            .cloned()
+            .unwrap();
+        assert_eq!(liquidation.result_code, CommandResultCode::SessionCommandNotAllowed, "{path:?}");
+        assert!(liquidation.matcher_events.is_empty());
+        assert_eq!(core.get_user_profile(1).unwrap().positions[&SYMBOL].net_volume(), 10);
+        assert_eq!(core.get_user_profile(3).unwrap().positions[&SYMBOL].net_volume(), 0);
+        assert_eq!(available(&core, INSURANCE_FUND, QUOTE), 50_000);
+    }
+}
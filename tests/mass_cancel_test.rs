@@ -0,0 +1,205 @@
+mod common;
+
+use common::{add_user, balance, order, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::{AdvancedOrderBook, DirectOrderBookOptimized, NaiveOrderBook, OrderBook};
+use matching_core::core::users::AccountBalance;
+
+const SPOT: SymbolId = 1;
+const PERPETUAL: SymbolId = 2;
+const SPOT_2: SymbolId = 3;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+
+fn create_core(risk_engines_num: usize, matching_engines_num: usize) -> ExchangeCore {
+    let config = ExchangeConfig {
+        risk_engines_num,
+        matching_engines_num,
+        ..Default::default()
+    };
+    let perpetual = CoreSymbolSpecification {
+        symbol_type: SymbolType::PerpetualSwap,
+        margin_buy: 100,
+        margin_sell: 100,
+        ..spot_spec(PERPETUAL, BASE, QUOTE)
+    };
+    let mut core = common::create_core_with(config, &[spot_spec(SPOT, BASE, QUOTE), spot_spec(SPOT_2, BASE, QUOTE), perpetual]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, 10_000), (BASE, 100)]);
+    }
+    core
+}
+
+/// Đặt lệnh trên ba công cụ cho người dùng 1 và một lệnh cho người dùng 2
+fn place_orders(core: &mut ExchangeCore) {
+    core.submit_command(order(1, 11, SPOT, OrderAction::Bid, 100, 10));
+    core.submit_command(order(1, 12, SPOT, OrderAction::Ask, 110, 5));
+    core.submit_command(order(1, 13, SPOT_2, OrderAction::Bid, 50, 4));
+    core.submit_command(order(1, 14, PERPETUAL, OrderAction::Ask, 200, 3));
+    core.submit_command(order(2, 21, SPOT, OrderAction::Bid, 90, 10));
+}
+
+fn cancelled(cmd: &OrderCommand) -> Vec<(SymbolId, OrderId, Size)> {
+    let mut orders: Vec<(SymbolId, OrderId, Size)> = cmd
+        .matcher_events
+        .iter()
+        .filter(|e| matches!(e.event_type, MatcherEventType::MassCancel(_)))
+        .map(|e| (e.symbol, e.matched_order_id, e.size))
+        .collect();
+    orders.sort_unstable();
+    orders
+}
+
+#[test]
+fn test_mass_cancel_all_orders_across_shards() {
+    for (risk_engines_num, matching_engines_num) in [(1, 1), (2, 2), (4, 2)] {
+        let mut core = create_core(risk_engines_num, matching_engines_num);
+        place_orders(&mut core);
+
+        let cmd = core.submit_command(OrderCommand::mass_cancel(1));
+        assert_eq!(cmd.result_code, CommandResultCode::Success);
+        assert_eq!(cancelled(&cmd), vec![(SPOT, 11, 10), (SPOT, 12, 5), (PERPETUAL, 14, 3), (SPOT_2, 13, 4)]);
+
+        // Mọi khoản giữ của người dùng 1 được hoàn, lệnh của người dùng 2 không bị ảnh hưởng
+        assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 10_000, locked: 0 });
+        assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 100, locked: 0 });
+        assert!(core.get_user_profile(1).unwrap().holds.is_empty());
+        assert_eq!(balance(&core, 2, QUOTE), AccountBalance { available: 9_100, locked: 900 });
+        assert!(core.audit().unwrap().is_balanced());
+
+        // Không còn lệnh để hủy
+        let cmd = core.submit_command(OrderCommand::mass_cancel(1));
+        assert_eq!(cmd.result_code, CommandResultCode::Success);
+        assert!(cmd.matcher_events.is_empty());
+    }
+}
+
+#[test]
+fn test_mass_cancel_filtered_by_symbol_and_side() {
+    let mut core = create_core(2, 2);
+    place_orders(&mut core);
+
+    let cmd = core.submit_command(OrderCommand {
+        mass_cancel_symbol: Some(SPOT),
+        mass_cancel_action: Some(OrderAction::Bid),
+        ..OrderCommand::mass_cancel(1)
+    });
+    assert_eq!(cancelled(&cmd), vec![(SPOT, 11, 10)]);
+    assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 95, locked: 5 });
+
+    let cmd = core.submit_command(OrderCommand {
+        mass_cancel_action: Some(OrderAction::Ask),
+        ..OrderCommand::mass_cancel(1)
+    });
+    assert_eq!(cancelled(&cmd), vec![(SPOT, 12, 5), (PERPETUAL, 14, 3)]);
+    assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 100, locked: 0 });
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 9_800, locked: 200 });
+
+    let cmd = core.submit_command(OrderCommand { mass_cancel_symbol: Some(SPOT_2), ..OrderCommand::mass_cancel(1) });
+    assert_eq!(cancelled(&cmd), vec![(SPOT_2, 13, 4)]);
+    assert_eq!(balance(&core, 1, QUOTE), AccountBalance { available: 10_000, locked: 0 });
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_mass_cancel_after_partial_fill_and_unknown_user() {
+    let mut core = create_core(1, 1);
+    place_orders(&mut core);
+    core.submit_command(OrderCommand { order_type: OrderType::Ioc, ..order(2, 22, SPOT, OrderAction::Ask, 100, 4) });
+
+    let cmd = core.submit_command(OrderCommand { mass_cancel_symbol: Some(SPOT), ..OrderCommand::mass_cancel(1) });
+    assert_eq!(cancelled(&cmd), vec![(SPOT, 11, 6), (SPOT, 12, 5)]);
+    assert_eq!(balance(&core, 1, BASE), AccountBalance { available: 104, locked: 0 });
+    assert!(core.audit().unwrap().is_balanced());
+
+    let cmd = core.submit_command(OrderCommand::mass_cancel(42));
+    assert_eq!(cmd.result_code, CommandResultCode::AuthInvalidUser);
+    assert!(cmd.matcher_events.is_empty());
+}
+
+#[test]
+fn test_mass_cancel_allowed_while_halted() {
+    let mut core = create_core(1, 1);
+    place_orders(&mut core);
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::SetSessionState,
+        symbol: SPOT,
+        session_state: Some(TradingSessionState::Halted),
+        ..Default::default()
+    });
+
+    let cmd = core.submit_command(OrderCommand { mass_cancel_symbol: Some(SPOT), ..OrderCommand::mass_cancel(1) });
+    assert_eq!(cancelled(&cmd), vec![(SPOT, 11, 10), (SPOT, 12, 5)]);
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_advanced_book_mass_cancel_includes_group_legs() {
+    let mut book = AdvancedOrderBook::new(CoreSymbolSpecification { symbol_id: SPOT, ..Default::default() });
+    book.new_order(&mut OrderCommand { group_id: Some(7), ..order(1, 1, SPOT, OrderAction::Bid, 90, 5) });
+    book.new_order(&mut OrderCommand { group_id: Some(7), ..order(1, 2, SPOT, OrderAction::Ask, 120, 5) });
+    book.new_order(&mut order(1, 3, SPOT, OrderAction::Ask, 130, 2));
+    book.new_order(&mut order(2, 4, SPOT, OrderAction::Ask, 125, 2));
+
+    let mut cmd = OrderCommand { mass_cancel_action: Some(OrderAction::Bid), ..OrderCommand::mass_cancel(1) };
+    book.cancel_user_orders(&mut cmd);
+    // Hủy lệnh mua của nhóm OCO hủy theo lệnh bán cùng nhóm
+    let events: Vec<(MatcherEventType, OrderId, Size)> =
+        cmd.matcher_events.iter().map(|e| (e.event_type, e.matched_order_id, e.size)).collect();
+    assert_eq!(
+        events,
+        vec![
+            (MatcherEventType::MassCancel(OrderAction::Bid), 1, 5),
+            (MatcherEventType::MassCancel(OrderAction::Ask), 2, 5),
+        ]
+    );
+    assert_eq!(book.get_user_orders(1), vec![3]);
+    assert_eq!(book.get_user_orders(2), vec![4]);
+}
+
+/// Chỉ mục lệnh theo người dùng bỏ lệnh khớp hết và lệnh đã hủy
+fn assert_user_orders_follow_fills_and_cancels(book: &mut impl OrderBook) {
+    book.new_order(&mut order(1, 1, SPOT, OrderAction::Bid, 100, 5));
+    book.new_order(&mut order(1, 2, SPOT, OrderAction::Bid, 99, 5));
+    book.new_order(&mut order(2, 3, SPOT, OrderAction::Bid, 98, 5));
+    assert_eq!(book.get_user_orders(1), vec![1, 2]);
+
+    book.new_order(&mut OrderCommand { order_type: OrderType::Ioc, ..order(3, 4, SPOT, OrderAction::Ask, 100, 5) });
+    assert_eq!(book.get_user_orders(1), vec![2]);
+
+    let mut cmd = OrderCommand::mass_cancel(1);
+    book.cancel_user_orders(&mut cmd);
+    assert_eq!(cmd.matcher_events.len(), 1);
+    assert!(book.get_user_orders(1).is_empty());
+    assert_eq!(book.get_user_orders(2), vec![3]);
+    assert!(book.get_user_orders(3).is_empty());
+}
+
+#[test]
+fn test_user_order_index_in_all_order_books() {
+    let spec = CoreSymbolSpecification { symbol_id: SPOT, ..Default::default() };
+    assert_user_orders_follow_fills_and_cancels(&mut NaiveOrderBook::new(spec.clone()));
+    assert_user_orders_follow_fills_and_cancels(&mut DirectOrderBookOptimized::new(spec.clone()));
+    assert_user_orders_follow_fills_and_cancels(&mut AdvancedOrderBook::new(spec.clone()));
+
+    // Sổ lệnh nâng cao: lệnh cắt lỗ chờ kích hoạt có trong chỉ mục, lệnh sửa giá khớp hết thì rời chỉ mục
+    let mut book = AdvancedOrderBook::new(spec);
+    book.new_order(&mut OrderCommand {
+        order_type: OrderType::StopLimit,
+        stop_price: Some(120),
+        ..order(1, 5, SPOT, OrderAction::Bid, 121, 2)
+    });
+    book.new_order(&mut OrderCommand { reserve_price: 100, ..order(1, 6, SPOT, OrderAction::Bid, 90, 3) });
+    book.new_order(&mut order(2, 7, SPOT, OrderAction::Ask, 95, 3));
+    assert_eq!(book.get_user_orders(1), vec![5, 6]);
+
+    let mut amend = OrderCommand { command: OrderCommandType::AmendOrder, ..order(1, 6, SPOT, OrderAction::Bid, 95, 3) };
+    assert_eq!(book.amend_order(&mut amend), CommandResultCode::Success);
+    assert_eq!(book.get_user_orders(1), vec![5]);
+    assert!(book.get_user_orders(2).is_empty());
+
+    let mut cancel = OrderCommand { command: OrderCommandType::CancelOrder, ..order(1, 5, SPOT, OrderAction::Bid, 0, 0) };
+    assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::Success);
+    assert!(book.get_user_orders(1).is_empty());
+}
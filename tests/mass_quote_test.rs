@@ -0,0 +1,190 @@
+mod common;
+
+use common::{add_user, balance, fills, order, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::users::AccountBalance;
+
+const SPOT: SymbolId = 1;
+const PERPETUAL: SymbolId = 2;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+const MAKER: UserId = 1;
+const TAKER: UserId = 2;
+
+fn create_core(risk_engines_num: usize, matching_engines_num: usize) -> ExchangeCore {
+    let config = ExchangeConfig {
+        risk_engines_num,
+        matching_engines_num,
+        ..Default::default()
+    };
+    let perpetual = CoreSymbolSpecification {
+        symbol_type: SymbolType::PerpetualSwap,
+        margin_buy: 100,
+        margin_sell: 100,
+        price_band_min: 150,
+        price_band_max: 250,
+        ..spot_spec(PERPETUAL, BASE, QUOTE)
+    };
+    let mut core = common::create_core_with(config, &[spot_spec(SPOT, BASE, QUOTE), perpetual]);
+    for (uid, quote, base) in [(MAKER, 2_000, 10), (TAKER, 100_000, 100)] {
+        add_user(&mut core, uid, &[(QUOTE, quote), (BASE, base)]);
+    }
+    core
+}
+
+fn side(order_id: OrderId, price: Price, size: Size) -> QuoteSide {
+    QuoteSide { order_id, price, size, ..Default::default() }
+}
+
+fn quote(symbol: SymbolId, bid: QuoteSide, ask: QuoteSide) -> Quote {
+    Quote { symbol, bid, ask }
+}
+
+fn mass_quote(quotes: Vec<Quote>) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::MassQuote,
+        uid: MAKER,
+        quotes,
+        ..Default::default()
+    }
+}
+
+/// Lệnh IOC của taker, trả về các lệnh bị khớp (ID lệnh, khối lượng)
+fn take(core: &mut ExchangeCore, symbol: SymbolId, action: OrderAction, price: Price, size: Size) -> Vec<(OrderId, Size)> {
+    let cmd = core.submit_command(OrderCommand {
+        order_type: OrderType::Ioc,
+        ..order(TAKER, 1_000 + price as OrderId, symbol, action, price, size)
+    });
+    fills(&cmd)
+}
+
+#[test]
+fn test_mass_quote_replaces_previous_quotes_reusing_held_funds() {
+    let mut core = create_core(1, 1);
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(1, 100, 10), side(2, 110, 10))]));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert!(cmd.matcher_events.is_empty());
+    assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 1_000, locked: 1_000 });
+    assert_eq!(balance(&core, MAKER, BASE), AccountBalance { available: 0, locked: 10 });
+
+    // Toàn bộ số dư đã bị giữ: báo giá mới chỉ hợp lệ nhờ khoản giữ của báo giá cũ được hoàn trước
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(3, 99, 20), side(4, 111, 10))]));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 20, locked: 1_980 });
+    assert_eq!(balance(&core, MAKER, BASE), AccountBalance { available: 0, locked: 10 });
+
+    assert_eq!(take(&mut core, SPOT, OrderAction::Ask, 100, 5), vec![]);
+    assert_eq!(take(&mut core, SPOT, OrderAction::Ask, 99, 5), vec![(3, 5)]);
+    assert_eq!(take(&mut core, SPOT, OrderAction::Bid, 110, 5), vec![]);
+    assert_eq!(take(&mut core, SPOT, OrderAction::Bid, 111, 5), vec![(4, 5)]);
+
+    // Báo giá đã khớp một phần được thay bằng báo giá dùng lại cùng ID lệnh; khối lượng 0 = rút báo giá phía đó
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(3, 98, 5), side(4, 0, 0))]));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 1_570, locked: 490 });
+    assert_eq!(balance(&core, MAKER, BASE), AccountBalance { available: 10, locked: 0 });
+    assert_eq!(take(&mut core, SPOT, OrderAction::Ask, 98, 10), vec![(3, 5)]);
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_mass_quote_is_all_or_nothing_across_symbols_and_shards() {
+    for (risk_engines_num, matching_engines_num) in [(1, 1), (2, 2)] {
+        let mut core = create_core(risk_engines_num, matching_engines_num);
+        core.submit_command(mass_quote(vec![
+            quote(SPOT, side(1, 100, 5), side(2, 110, 5)),
+            quote(PERPETUAL, side(3, 200, 2), side(4, 210, 2)),
+        ]));
+        assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 1_100, locked: 900 });
+
+        // Phía cuối cùng không đủ ký quỹ: không phía nào được treo, báo giá cũ giữ nguyên cùng khoản giữ
+        let cmd = core.submit_command(mass_quote(vec![
+            quote(SPOT, side(5, 100, 10), side(6, 110, 5)),
+            quote(PERPETUAL, side(7, 200, 5), side(8, 210, 50)),
+        ]));
+        assert_eq!(cmd.result_code, CommandResultCode::RiskNsf);
+        assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 1_100, locked: 900 });
+        assert_eq!(balance(&core, MAKER, BASE), AccountBalance { available: 5, locked: 5 });
+
+        // Báo giá mới đủ ký quỹ nhưng một phía bị engine khớp lệnh từ chối (ngoài dải giá): báo giá cũ giữ nguyên
+        let cmd = core.submit_command(mass_quote(vec![
+            quote(SPOT, side(5, 101, 1), side(6, 109, 1)),
+            quote(PERPETUAL, side(7, 100, 1), side(8, 210, 1)),
+        ]));
+        assert_eq!(cmd.result_code, CommandResultCode::PriceOutsideBand);
+        assert!(cmd.matcher_events.is_empty());
+        assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 1_100, locked: 900 });
+        assert_eq!(take(&mut core, SPOT, OrderAction::Ask, 100, 5), vec![(1, 5)]);
+        assert_eq!(take(&mut core, PERPETUAL, OrderAction::Bid, 210, 2), vec![(4, 2)]);
+
+        let before = balance(&core, MAKER, QUOTE);
+        let cmd = core.submit_command(mass_quote(vec![
+            quote(SPOT, side(9, 100, 1), side(10, 110, 1)),
+            quote(SPOT, side(11, 100, 1), side(12, 110, 1)),
+        ]));
+        assert_eq!(cmd.result_code, CommandResultCode::InvalidSymbol);
+        assert_eq!(balance(&core, MAKER, QUOTE), before);
+        assert!(core.audit().unwrap().is_balanced());
+    }
+}
+
+#[test]
+fn test_mass_quote_that_would_take_is_rejected_and_keeps_previous_quotes() {
+    let mut core = create_core(1, 1);
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: TAKER,
+        order_id: 50,
+        symbol: SPOT,
+        price: 105,
+        reserve_price: 105,
+        size: 5,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        ..Default::default()
+    });
+
+    // Phía mua giao với lệnh bán đang treo: cả báo giá bị từ chối, phía bán hợp lệ cũng không được treo
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(1, 105, 10), side(2, 110, 10))]));
+    assert_eq!(cmd.result_code, CommandResultCode::PostOnlyWouldTake);
+    assert!(cmd.matcher_events.is_empty());
+    assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 2_000, locked: 0 });
+    assert_eq!(balance(&core, MAKER, BASE), AccountBalance { available: 10, locked: 0 });
+
+    // Hai phía của báo giá giao nhau, hoặc trùng ID lệnh
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(1, 104, 1), side(2, 104, 1))]));
+    assert_eq!(cmd.result_code, CommandResultCode::PostOnlyWouldTake);
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(1, 100, 1), side(1, 104, 1))]));
+    assert_eq!(cmd.result_code, CommandResultCode::RiskDuplicateOrderId);
+
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(1, 100, 10), side(2, 104, 5))]));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 1_000, locked: 1_000 });
+    assert_eq!(balance(&core, MAKER, BASE), AccountBalance { available: 5, locked: 5 });
+
+    // Giá mua mới bằng giá bán cũ của chính báo giá bị thay: không tính là giao nhau
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(3, 104, 10), side(4, 106, 5))]));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 960, locked: 1_040 });
+
+    // Báo giá thay thế bị từ chối: báo giá cũ vẫn treo với khoản giữ cũ
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(5, 105, 10), side(6, 108, 5))]));
+    assert_eq!(cmd.result_code, CommandResultCode::PostOnlyWouldTake);
+    assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 960, locked: 1_040 });
+    assert_eq!(balance(&core, MAKER, BASE), AccountBalance { available: 5, locked: 5 });
+    assert_eq!(take(&mut core, SPOT, OrderAction::Ask, 104, 5), vec![(3, 5)]);
+
+    // Phiên tạm dừng: báo giá không được treo, báo giá cũ giữ nguyên
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::SetSessionState,
+        symbol: SPOT,
+        session_state: Some(TradingSessionState::Halted),
+        ..Default::default()
+    });
+    let cmd = core.submit_command(mass_quote(vec![quote(SPOT, side(7, 100, 10), side(8, 110, 10))]));
+    assert_eq!(cmd.result_code, CommandResultCode::SessionCommandNotAllowed);
+    assert_eq!(balance(&core, MAKER, QUOTE), AccountBalance { available: 960, locked: 520 });
+    assert_eq!(balance(&core, MAKER, BASE), AccountBalance { available: 10, locked: 5 });
+    assert!(core.audit().unwrap().is_balanced());
+}
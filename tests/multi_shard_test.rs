@@ -0,0 +1,160 @@
+mod common;
+
+use common::{add_user, order, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+const SPOT: SymbolId = 1;
+const PERPETUAL: SymbolId = 2;
+const OPTION: SymbolId = 3;
+const FUTURES: SymbolId = 4;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+const INSURANCE_FUND: UserId = 99;
+const FEE_ACCOUNT: UserId = 100;
+const EXPIRY: i64 = 1000;
+const UIDS: [UserId; 8] = [1, 2, 3, 4, 5, 6, INSURANCE_FUND, FEE_ACCOUNT];
+
+fn create_core(risk_engines_num: usize, matching_engines_num: usize) -> ExchangeCore {
+    let config = ExchangeConfig {
+        risk_engines_num,
+        matching_engines_num,
+        insurance_fund_uid: Some(INSURANCE_FUND),
+        fee_account_uid: Some(FEE_ACCOUNT),
+        ..Default::default()
+    };
+    let spot = CoreSymbolSpecification {
+        taker_fee: 2,
+        maker_fee: 1,
+        ..spot_spec(SPOT, BASE, QUOTE)
+    };
+    let perpetual = CoreSymbolSpecification {
+        symbol_type: SymbolType::PerpetualSwap,
+        margin_buy: 100,
+        margin_sell: 100,
+        maintenance_margin: 50,
+        ..spot_spec(PERPETUAL, BASE, QUOTE)
+    };
+    let option = CoreSymbolSpecification {
+        symbol_type: SymbolType::CallOption,
+        margin_sell: 200,
+        underlying_symbol: SPOT,
+        strike_price: 100,
+        expiry_time: EXPIRY,
+        ..spot_spec(OPTION, BASE, QUOTE)
+    };
+    let futures = CoreSymbolSpecification {
+        symbol_type: SymbolType::FuturesContract,
+        margin_buy: 100,
+        margin_sell: 100,
+        expiry_time: EXPIRY,
+        ..spot_spec(FUTURES, BASE, QUOTE)
+    };
+    let mut core = common::create_core_with(config, &[spot, perpetual, option, futures]);
+    for uid in [1, 2, 3, 4, 5, 6, INSURANCE_FUND] {
+        let quote = if uid == 5 { 1_000 } else { 100_000 };
+        add_user(&mut core, uid, &[(QUOTE, quote), (BASE, 100)]);
+    }
+    core
+}
+
+fn symbol_command(command: OrderCommandType, symbol: SymbolId, price: Price, timestamp: i64) -> OrderCommand {
+    OrderCommand {
+        command,
+        symbol,
+        price,
+        timestamp,
+        ..Default::default()
+    }
+}
+
+/// Kịch bản có giao dịch giữa người dùng thuộc các phân đoạn khác nhau:
+/// giao ngay có phí, thanh lý có quỹ bảo hiểm bù, quyết toán quyền chọn và hợp đồng tương lai
+fn run_script(core: &mut ExchangeCore) -> Vec<OrderCommand> {
+    let commands = vec![
+        order(1, 101, SPOT, OrderAction::Ask, 100, 5),
+        OrderCommand { reserve_price: 110, ..order(2, 201, SPOT, OrderAction::Bid, 100, 3) },
+        OrderCommand { order_type: OrderType::Ioc, ..order(3, 301, SPOT, OrderAction::Bid, 100, 4) },
+        order(4, 401, SPOT, OrderAction::Bid, 95, 1),
+        OrderCommand { command: OrderCommandType::CancelOrder, uid: 4, order_id: 401, symbol: SPOT, ..Default::default() },
+        order(6, 601, PERPETUAL, OrderAction::Ask, 1_000, 10),
+        order(5, 501, PERPETUAL, OrderAction::Bid, 1_000, 10),
+        order(2, 202, PERPETUAL, OrderAction::Bid, 880, 10),
+        symbol_command(OrderCommandType::UpdateMarkPrice, PERPETUAL, 940, 10),
+        order(3, 302, OPTION, OrderAction::Ask, 10, 4),
+        OrderCommand { order_type: OrderType::Ioc, ..order(4, 402, OPTION, OrderAction::Bid, 12, 4) },
+        order(1, 102, FUTURES, OrderAction::Ask, 500, 2),
+        order(6, 602, FUTURES, OrderAction::Bid, 500, 2),
+        order(6, 603, FUTURES, OrderAction::Bid, 450, 1),
+        symbol_command(OrderCommandType::ExpireOption, OPTION, 130, EXPIRY),
+        symbol_command(OrderCommandType::SettleFutures, FUTURES, 520, EXPIRY),
+    ];
+    commands.into_iter().map(|cmd| core.submit_command(cmd)).collect()
+}
+
+/// (uid, khả dụng/bị khóa theo loại tiền, vị thế ròng theo công cụ)
+type UserState = (UserId, [(i64, i64); 2], Vec<(SymbolId, i64)>);
+
+fn state(core: &ExchangeCore) -> Vec<UserState> {
+    UIDS.iter()
+        .map(|&uid| {
+            let profile = core.get_user_profile(uid).unwrap();
+            let balances = [BASE, QUOTE].map(|currency| {
+                let balance = profile.balance(currency);
+                (balance.available, balance.locked)
+            });
+            let mut positions: Vec<(SymbolId, i64)> =
+                profile.positions.iter().map(|(&symbol, p)| (symbol, p.net_volume())).collect();
+            positions.sort_unstable();
+            (uid, balances, positions)
+        })
+        .collect()
+}
+
+#[test]
+fn test_results_independent_of_shard_count() {
+    let mut reference = create_core(1, 1);
+    let reference_results = run_script(&mut reference);
+    assert!(reference.audit().unwrap().is_balanced());
+
+    // Kịch bản thực sự đi qua thanh lý có quỹ bảo hiểm bù và quyết toán
+    assert!(reference.get_user_profile(5).unwrap().positions.is_empty());
+    assert!(reference.get_balance(INSURANCE_FUND, QUOTE).unwrap().available < 100_000);
+    assert!(reference.get_balance(FEE_ACCOUNT, QUOTE).unwrap().available > 0);
+
+    for (risk_engines_num, matching_engines_num) in [(2, 1), (4, 1), (4, 2), (8, 4)] {
+        let mut core = create_core(risk_engines_num, matching_engines_num);
+        let results = run_script(&mut core);
+
+        for (expected, actual) in reference_results.iter().zip(&results) {
+            assert_eq!(expected.result_code, actual.result_code, "{risk_engines_num} phân đoạn: {actual:?}");
+            assert_eq!(format!("{:?}", expected.matcher_events), format!("{:?}", actual.matcher_events));
+        }
+        assert_eq!(state(&reference), state(&core), "{risk_engines_num} phân đoạn rủi ro");
+
+        let report = core.audit().unwrap();
+        assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    }
+}
+
+#[test]
+fn test_maker_and_taker_on_different_shards_settled_once() {
+    let mut core = create_core(2, 1);
+
+    // User 2 (phân đoạn 0) treo lệnh bán, user 1 (phân đoạn 1) mua
+    core.submit_command(order(2, 201, SPOT, OrderAction::Ask, 100, 5));
+    let taker = core.submit_command(OrderCommand { reserve_price: 110, ..order(1, 101, SPOT, OrderAction::Bid, 100, 3) });
+    assert_eq!(taker.result_code, CommandResultCode::Success);
+    assert_eq!(taker.matcher_events.len(), 1);
+    assert_eq!((taker.matcher_events[0].taker_fee, taker.matcher_events[0].maker_fee), (6, 3));
+
+    let taker = core.get_balance(1, QUOTE).unwrap();
+    assert_eq!((taker.available, taker.locked), (100_000 - 300 - 6, 0));
+    assert_eq!(core.get_balance(1, BASE).unwrap().available, 103);
+
+    let maker = core.get_balance(2, QUOTE).unwrap();
+    assert_eq!((maker.available, maker.locked), (100_000 + 300 - 3, 0));
+    assert_eq!(core.get_balance(2, BASE).unwrap(), matching_core::core::users::AccountBalance { available: 95, locked: 2 });
+
+    assert_eq!(core.get_balance(FEE_ACCOUNT, QUOTE).unwrap().available, 9);
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use common::{add_user, available, order};
+use matching_core::api::*;
+use matching_core::core::exchange::ExchangeCore;
+
+const SYMBOL: SymbolId = 30;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+const EXPIRY: i64 = 1000;
+
+fn create_option_spec(symbol_type: SymbolType, option_settlement: OptionSettlement) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: SYMBOL,
+        symbol_type,
+        base_currency: BASE,
+        quote_currency: QUOTE,
+        margin_sell: 200,
+        underlying_symbol: 1,
+        strike_price: 100,
+        expiry_time: EXPIRY,
+        option_settlement,
+        ..Default::default()
+    }
+}
+
+fn create_core(spec: CoreSymbolSpecification) -> ExchangeCore {
+    let mut core = common::create_core(&[spec]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, 100_000), (BASE, 100)]);
+    }
+    core
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, action: OrderAction, price: Price, size: Size, order_type: OrderType) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        order_type,
+        ..order(uid, order_id, SYMBOL, action, price, size)
+    })
+}
+
+fn expire(core: &mut ExchangeCore, settlement_price: Price, timestamp: i64) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::ExpireOption,
+        symbol: SYMBOL,
+        price: settlement_price,
+        timestamp,
+        ..Default::default()
+    })
+}
+
+/// User 2 bán (viết) 5 quyền chọn @ 10, user 1 mua
+fn open_positions(core: &mut ExchangeCore) {
+    place(core, 2, 201, OrderAction::Ask, 10, 5, OrderType::Gtc);
+    let taker = place(core, 1, 101, OrderAction::Bid, 12, 5, OrderType::Ioc);
+    assert_eq!(taker.matcher_events.len(), 1);
+    assert_eq!(taker.matcher_events[0].price, 10);
+}
+
+#[test]
+fn test_premium_and_writer_collateral_holds() {
+    let mut core = create_core(create_option_spec(SymbolType::CallOption, OptionSettlement::Cash));
+
+    place(&mut core, 2, 201, OrderAction::Ask, 10, 5, OrderType::Gtc);
+    assert_eq!(available(&core, 2, QUOTE), 99_000);
+
+    place(&mut core, 1, 101, OrderAction::Bid, 12, 5, OrderType::Ioc);
+
+    // Bên mua trả phí quyền chọn 50, phần giữ dư được hoàn
+    assert_eq!(available(&core, 1, QUOTE), 99_950);
+    assert_eq!(available(&core, 2, QUOTE), 99_050);
+    assert_eq!(core.get_user_profile(1).unwrap().positions[&SYMBOL].net_volume(), 5);
+    assert_eq!(core.get_user_profile(2).unwrap().positions[&SYMBOL].net_volume(), -5);
+
+    // Lệnh mua chờ bị hủy được hoàn toàn bộ phí quyền chọn đã giữ
+    place(&mut core, 1, 102, OrderAction::Bid, 8, 3, OrderType::Gtc);
+    assert_eq!(available(&core, 1, QUOTE), 99_926);
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1,
+        order_id: 102,
+        symbol: SYMBOL,
+        ..Default::default()
+    });
+    assert_eq!(available(&core, 1, QUOTE), 99_950);
+}
+
+#[test]
+fn test_cash_settlement_in_the_money() {
+    let mut core = create_core(create_option_spec(SymbolType::CallOption, OptionSettlement::Cash));
+    open_positions(&mut core);
+    place(&mut core, 1, 102, OrderAction::Bid, 8, 3, OrderType::Gtc);
+
+    let cmd = expire(&mut core, 130, EXPIRY);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(cmd.matcher_events.len(), 2);
+    assert!(cmd.matcher_events.iter().all(|e| e.event_type == MatcherEventType::Settlement));
+    assert_eq!(cmd.matcher_events[0].matched_order_uid, 1);
+    assert_eq!(cmd.matcher_events[0].amount, 150);
+    assert_eq!(cmd.matcher_events[1].matched_order_uid, 2);
+    assert_eq!(cmd.matcher_events[1].amount, -150);
+
+    // Lệnh chờ đã hủy, tài sản đảm bảo của bên bán được hoàn
+    assert_eq!(available(&core, 1, QUOTE), 100_100);
+    assert_eq!(available(&core, 2, QUOTE), 99_900);
+    assert!(core.get_user_profile(1).unwrap().positions.is_empty());
+    assert!(core.get_user_profile(2).unwrap().positions.is_empty());
+
+    // Công cụ đã đáo hạn không nhận lệnh mới
+    let late = place(&mut core, 1, 103, OrderAction::Bid, 10, 1, OrderType::Gtc);
+    assert_eq!(late.result_code, CommandResultCode::InvalidSymbol);
+}
+
+#[test]
+fn test_out_of_the_money_expires_worthless() {
+    let mut core = create_core(create_option_spec(SymbolType::CallOption, OptionSettlement::Cash));
+    open_positions(&mut core);
+
+    let cmd = expire(&mut core, 90, EXPIRY);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert!(cmd.matcher_events.iter().all(|e| e.amount == 0));
+
+    assert_eq!(available(&core, 1, QUOTE), 99_950);
+    assert_eq!(available(&core, 2, QUOTE), 100_050);
+}
+
+#[test]
+fn test_extreme_settlement_price() {
+    // Quyền chọn mua thanh toán tiền mặt tại giá cực đại: giá trị nội tại vượt i64, số tiền được ghi bão hòa
+    let mut core = create_core(create_option_spec(SymbolType::CallOption, OptionSettlement::Cash));
+    open_positions(&mut core);
+    let cmd = expire(&mut core, i64::MAX, EXPIRY);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(cmd.matcher_events[0].amount, i64::MAX);
+    assert_eq!(cmd.matcher_events[1].amount, i64::MIN);
+    assert_eq!(available(&core, 1, QUOTE), i64::MAX);
+    // Bên bán: bị ghi nợ đến giới hạn rồi được mở khóa tài sản đảm bảo 5 * 200
+    assert_eq!(available(&core, 2, QUOTE), i64::MIN + 1_000);
+
+    // Quyền chọn bán giao vật chất tại giá cực tiểu: chuyển giao theo giá thực hiện như bình thường
+    let mut core = create_core(create_option_spec(SymbolType::PutOption, OptionSettlement::Physical));
+    open_positions(&mut core);
+    let cmd = expire(&mut core, i64::MIN, EXPIRY);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(available(&core, 1, BASE), 95);
+    assert_eq!(available(&core, 1, QUOTE), 100_450);
+    assert_eq!(available(&core, 2, QUOTE), 99_550);
+}
+
+#[test]
+fn test_physical_put_exercise() {
+    let mut core = create_core(create_option_spec(SymbolType::PutOption, OptionSettlement::Physical));
+    open_positions(&mut core);
+
+    let cmd = expire(&mut core, 80, EXPIRY + 5);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+
+    // Bên mua quyền bán giao 5 tài sản cơ sở, nhận 5 * 100 theo giá thực hiện
+    assert_eq!(available(&core, 1, BASE), 95);
+    assert_eq!(available(&core, 1, QUOTE), 100_450);
+    assert_eq!(available(&core, 2, BASE), 105);
+    assert_eq!(available(&core, 2, QUOTE), 99_550);
+    assert_eq!(available(&core, 1, QUOTE) + available(&core, 2, QUOTE), 200_000);
+}
+
+#[test]
+fn test_expiry_rejected_before_expiry_time_or_for_non_options() {
+    let mut core = create_core(create_option_spec(SymbolType::CallOption, OptionSettlement::Cash));
+    open_positions(&mut core);
+    place(&mut core, 1, 102, OrderAction::Bid, 8, 3, OrderType::Gtc);
+
+    let early = expire(&mut core, 130, EXPIRY - 1);
+    assert_eq!(early.result_code, CommandResultCode::SymbolNotExpired);
+    assert_eq!(core.get_user_profile(1).unwrap().positions[&SYMBOL].pending_buy_size, 3);
+    assert_eq!(core.get_user_profile(1).unwrap().positions[&SYMBOL].net_volume(), 5);
+
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 40,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: BASE,
+        quote_currency: QUOTE,
+        ..Default::default()
+    });
+    let spot = core.submit_command(OrderCommand {
+        command: OrderCommandType::ExpireOption,
+        symbol: 40,
+        price: 100,
+        timestamp: EXPIRY,
+        ..Default::default()
+    });
+    assert_eq!(spot.result_code, CommandResultCode::UnsupportedSymbolType);
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use common::spot_spec;
+use matching_core::api::*;
+use matching_core::core::orderbook::{AdvancedOrderBook, OrderBook};
+use matching_core::core::processors::risk_engine::RiskEngine;
+
+const SYMBOL: SymbolId = 1;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+
+struct Exchange {
+    risk: RiskEngine,
+    book: AdvancedOrderBook,
+}
+
+impl Exchange {
+    fn new() -> Self {
+        let spec = spot_spec(SYMBOL, BASE, QUOTE);
+        let mut risk = RiskEngine::new(0, 1);
+        risk.add_symbol(spec.clone());
+        let mut exchange = Self { risk, book: AdvancedOrderBook::new(spec) };
+        for uid in [1, 2] {
+            exchange.submit(OrderCommand {
+                command: OrderCommandType::AddUser,
+                uid,
+                ..Default::default()
+            });
+            exchange.submit(OrderCommand::balance_adjustment(uid, QUOTE, 10_000, 1));
+            exchange.submit(OrderCommand::balance_adjustment(uid, BASE, 20, 2));
+        }
+        exchange
+    }
+
+    /// R1 → sổ lệnh nâng cao → R2
+    fn submit(&mut self, mut cmd: OrderCommand) -> OrderCommand {
+        self.risk.pre_process(&mut cmd);
+        match cmd.command {
+            OrderCommandType::PlaceOrder if cmd.result_code == CommandResultCode::ValidForMatchingEngine => {
+                self.book.new_order(&mut cmd);
+            }
+            OrderCommandType::CancelOrder => {
+                cmd.result_code = self.book.cancel_order(&mut cmd);
+            }
+            _ => {}
+        }
+        self.risk.assign_trade_fees(&mut cmd);
+        self.risk.post_process(&mut cmd);
+        cmd
+    }
+
+    /// (khả dụng, bị khóa)
+    fn balance(&self, uid: UserId, currency: Currency) -> (i64, i64) {
+        let profile = self.risk.get_user_profile(uid).unwrap();
+        (
+            profile.accounts.get(&currency).copied().unwrap_or(0),
+            profile.locked.get(&currency).copied().unwrap_or(0),
+        )
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, action: OrderAction, price: Price, size: Size) -> OrderCommand {
+    common::order(uid, order_id, SYMBOL, action, price, size)
+}
+
+fn stop_loss(uid: UserId, order_id: OrderId, stop_price: Price, size: Size) -> OrderCommand {
+    OrderCommand {
+        order_type: OrderType::StopMarket,
+        stop_price: Some(stop_price),
+        ..order(uid, order_id, OrderAction::Ask, 1, size)
+    }
+}
+
+fn cancel(uid: UserId, order_id: OrderId) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid,
+        order_id,
+        symbol: SYMBOL,
+        ..Default::default()
+    }
+}
+
+fn group_cancels(cmd: &OrderCommand) -> Vec<(OrderId, Size, u64)> {
+    cmd.matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::GroupCancel(OrderAction::Ask))
+        .map(|e| (e.matched_order_id, e.size, e.group_id))
+        .collect()
+}
+
+#[test]
+fn test_oco_fill_cancels_other_leg() {
+    let mut exchange = Exchange::new();
+    exchange.submit(OrderCommand { group_id: Some(7), ..order(1, 11, OrderAction::Ask, 120, 10) });
+    exchange.submit(OrderCommand { group_id: Some(7), ..stop_loss(1, 12, 90, 10) });
+    assert_eq!(exchange.balance(1, BASE), (0, 20));
+
+    // Chốt lời khớp một phần: cắt lỗ bị hủy và hoàn khoản giữ
+    let cmd = exchange.submit(order(2, 21, OrderAction::Bid, 120, 4));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(cmd.matcher_events[0].group_id, 7);
+    assert_eq!(group_cancels(&cmd), vec![(12, 10, 7)]);
+    assert_eq!(exchange.book.get_user_orders(1), vec![11]);
+    assert_eq!(exchange.balance(1, BASE), (10, 6));
+    assert_eq!(exchange.balance(1, QUOTE), (10_480, 0));
+}
+
+#[test]
+fn test_oco_cancel_cancels_other_leg() {
+    let mut exchange = Exchange::new();
+    exchange.submit(OrderCommand { group_id: Some(7), ..order(1, 11, OrderAction::Ask, 120, 10) });
+    exchange.submit(OrderCommand { group_id: Some(7), ..stop_loss(1, 12, 90, 10) });
+
+    let cmd = exchange.submit(cancel(1, 11));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(group_cancels(&cmd), vec![(12, 10, 7)]);
+    assert!(exchange.book.get_user_orders(1).is_empty());
+    assert_eq!(exchange.balance(1, BASE), (20, 0));
+
+    // Lệnh thứ hai của nhóm đã bị hủy
+    assert_eq!(exchange.submit(cancel(1, 12)).result_code, CommandResultCode::MatchingUnknownOrderId);
+}
+
+#[test]
+fn test_bracket_spawns_oco_on_entry_fill() {
+    let mut exchange = Exchange::new();
+    exchange.submit(order(1, 11, OrderAction::Bid, 100, 5));
+    for child in [
+        order(1, 12, OrderAction::Ask, 120, 5),
+        stop_loss(1, 13, 90, 5),
+    ] {
+        let cmd = exchange.submit(OrderCommand { group_id: Some(9), parent_order_id: Some(11), ..child });
+        assert!(cmd.matcher_events.is_empty());
+    }
+    assert_eq!(exchange.book.get_user_orders(1), vec![11, 12, 13]);
+    assert_eq!(exchange.book.get_total_ask_volume(), 0);
+
+    // Lệnh vào khớp đủ: lệnh con vào sổ lệnh thành cặp OCO
+    exchange.submit(order(2, 21, OrderAction::Ask, 100, 5));
+    assert_eq!(exchange.book.get_user_orders(1), vec![12, 13]);
+    assert_eq!(exchange.book.get_total_ask_volume(), 5);
+
+    let cmd = exchange.submit(order(2, 22, OrderAction::Bid, 120, 5));
+    assert_eq!(group_cancels(&cmd), vec![(13, 5, 9)]);
+    assert!(exchange.book.get_user_orders(1).is_empty());
+    assert_eq!(exchange.balance(1, BASE), (20, 0));
+    assert_eq!(exchange.balance(1, QUOTE), (10_000 - 500 + 600, 0));
+}
+
+#[test]
+fn test_bracket_entry_cancel_and_invalid_links() {
+    let mut exchange = Exchange::new();
+    exchange.submit(order(1, 11, OrderAction::Bid, 100, 5));
+    exchange.submit(OrderCommand { group_id: Some(9), parent_order_id: Some(11), ..order(1, 12, OrderAction::Ask, 120, 5) });
+    exchange.submit(OrderCommand { group_id: Some(9), parent_order_id: Some(11), ..stop_loss(1, 13, 90, 5) });
+
+    // Lệnh vào không tồn tại, hoặc nhóm của người dùng khác: từ chối và hoàn khoản giữ
+    let orphan = exchange.submit(OrderCommand { parent_order_id: Some(99), ..order(1, 14, OrderAction::Ask, 120, 1) });
+    assert_eq!(orphan.matcher_events[0].event_type, MatcherEventType::Reject);
+    let foreign = exchange.submit(OrderCommand { group_id: Some(9), ..order(2, 21, OrderAction::Ask, 130, 1) });
+    assert_eq!(foreign.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(exchange.balance(2, BASE), (20, 0));
+
+    // Hủy lệnh vào: các lệnh con đang chờ bị hủy theo
+    let cmd = exchange.submit(cancel(1, 11));
+    assert_eq!(group_cancels(&cmd), vec![(12, 5, 9), (13, 5, 9)]);
+    assert!(exchange.book.get_user_orders(1).is_empty());
+    assert_eq!(exchange.balance(1, BASE), (20, 0));
+    assert_eq!(exchange.balance(1, QUOTE), (10_000, 0));
+}
+
+#[test]
+fn test_bracket_child_crossing_on_activation_settles_both_sides() {
+    let mut exchange = Exchange::new();
+    exchange.submit(order(2, 22, OrderAction::Ask, 104, 5));
+    exchange.submit(order(1, 11, OrderAction::Ask, 100, 5));
+    exchange.submit(OrderCommand { group_id: Some(9), parent_order_id: Some(11), ..order(1, 12, OrderAction::Bid, 105, 5) });
+    exchange.submit(OrderCommand { group_id: Some(9), parent_order_id: Some(11), ..stop_loss(1, 13, 90, 5) });
+
+    // Lệnh vào khớp đủ: lệnh chốt lời giao với lệnh bán đang treo ngay khi vào sổ lệnh, lệnh cắt lỗ bị hủy theo nhóm
+    let cmd = exchange.submit(order(2, 21, OrderAction::Bid, 100, 5));
+    let trades: Vec<(MatcherEventType, OrderId, OrderId, Size, Price)> = cmd
+        .matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::TriggeredTrade(OrderAction::Bid))
+        .map(|e| (e.event_type, e.bid_order_id, e.matched_order_id, e.size, e.price))
+        .collect();
+    assert_eq!(trades, vec![(MatcherEventType::TriggeredTrade(OrderAction::Bid), 12, 22, 5, 104)]);
+    assert_eq!(group_cancels(&cmd), vec![(13, 5, 9)]);
+    assert!(exchange.book.get_user_orders(1).is_empty());
+    assert!(exchange.book.get_user_orders(2).is_empty());
+
+    // Cả hai bên được thanh toán, không còn khoản giữ nào bị khóa
+    assert_eq!(exchange.balance(1, BASE), (20, 0));
+    assert_eq!(exchange.balance(1, QUOTE), (10_000 + 500 - 520, 0));
+    assert_eq!(exchange.balance(2, BASE), (20, 0));
+    assert_eq!(exchange.balance(2, QUOTE), (10_000 - 500 + 520, 0));
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use common::{add_user, available, place, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::ExchangeCore;
+
+const SPOT: SymbolId = 1;
+const FUTURES: SymbolId = 2;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+
+fn create_core(taker_fee: i64) -> ExchangeCore {
+    let spot = CoreSymbolSpecification {
+        taker_fee,
+        ..spot_spec(SPOT, BASE, QUOTE)
+    };
+    let futures = CoreSymbolSpecification {
+        symbol_type: SymbolType::PerpetualSwap,
+        margin_buy: 100,
+        margin_sell: 100,
+        maintenance_margin: 50,
+        ..spot_spec(FUTURES, BASE, QUOTE)
+    };
+    let mut core = common::create_core(&[spot, futures]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, i64::MAX), (BASE, 10)]);
+    }
+    core
+}
+
+#[test]
+fn test_notional_overflow_rejected() {
+    let mut core = create_core(0);
+
+    let cmd = place(&mut core, 1, 101, SPOT, OrderAction::Bid, i64::MAX / 2 + 1, 2);
+    assert_eq!(cmd.result_code, CommandResultCode::RiskNotionalOverflow);
+
+    let ask = place(&mut core, 1, 102, SPOT, OrderAction::Ask, i64::MAX / 2 + 1, 2);
+    assert_eq!(ask.result_code, CommandResultCode::RiskNotionalOverflow);
+    assert_eq!(available(&core, 1, QUOTE), i64::MAX);
+    assert_eq!(available(&core, 1, BASE), 10);
+
+    // Lệnh ký quỹ chỉ giữ ký quỹ nhỏ nhưng giá trị danh nghĩa vẫn phải biểu diễn được
+    let futures = place(&mut core, 1, 103, FUTURES, OrderAction::Bid, i64::MAX / 2, 3);
+    assert_eq!(futures.result_code, CommandResultCode::RiskNotionalOverflow);
+    assert_eq!(available(&core, 1, QUOTE), i64::MAX);
+}
+
+#[test]
+fn test_hold_at_numeric_limit() {
+    let mut core = create_core(0);
+
+    let cmd = place(&mut core, 1, 101, SPOT, OrderAction::Bid, i64::MAX, 1);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(core.get_balance(1, QUOTE).unwrap().locked, i64::MAX);
+
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1,
+        order_id: 101,
+        symbol: SPOT,
+        ..Default::default()
+    });
+    assert_eq!(available(&core, 1, QUOTE), i64::MAX);
+
+    // Phí giữ cộng thêm làm tràn khoản giữ theo lot
+    let mut core = create_core(1);
+    let with_fee = place(&mut core, 1, 101, SPOT, OrderAction::Bid, i64::MAX, 1);
+    assert_eq!(with_fee.result_code, CommandResultCode::RiskNotionalOverflow);
+}
+
+#[test]
+fn test_deposit_overflow_rejected() {
+    let mut core = create_core(0);
+
+    let cmd = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 1, 99));
+    assert_eq!(cmd.result_code, CommandResultCode::UserMgmtBalanceAdjustmentOverflow);
+    assert_eq!(available(&core, 1, QUOTE), i64::MAX);
+
+    // Mã giao dịch bị từ chối chưa được ghi nhận
+    core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, -1, 98));
+    let retry = core.submit_command(OrderCommand::balance_adjustment(1, QUOTE, 1, 99));
+    assert_eq!(retry.result_code, CommandResultCode::Success);
+}
+
+#[test]
+fn test_budget_overflow_rejected() {
+    let mut core = create_core(0);
+
+    let price = i64::MAX / 2 + 10;
+    place(&mut core, 2, 201, SPOT, OrderAction::Ask, price, 1);
+    place(&mut core, 2, 202, SPOT, OrderAction::Ask, price + 1, 1);
+
+    let cmd = core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 1,
+        order_id: 101,
+        symbol: SPOT,
+        price: 10,
+        reserve_price: 10,
+        size: 2,
+        action: OrderAction::Bid,
+        order_type: OrderType::FokBudget,
+        ..Default::default()
+    });
+    assert_eq!(cmd.matcher_events.len(), 1);
+    assert_eq!(cmd.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(available(&core, 1, QUOTE), i64::MAX);
+    assert_eq!(core.get_balance(1, QUOTE).unwrap().locked, 0);
+}
+
+#[test]
+fn test_extreme_mark_price() {
+    let mut core = create_core(0);
+    place(&mut core, 1, 101, FUTURES, OrderAction::Ask, 1_000, 5);
+    place(&mut core, 2, 201, FUTURES, OrderAction::Bid, 1_000, 5);
+
+    for price in [i64::MAX, 0] {
+        let cmd = core.submit_command(OrderCommand {
+            command: OrderCommandType::UpdateMarkPrice,
+            symbol: FUTURES,
+            price,
+            ..Default::default()
+        });
+        assert_eq!(cmd.result_code, CommandResultCode::Success);
+    }
+}
+
+#[test]
+fn test_spot_fill_credit_saturates_at_numeric_limit() {
+    let mut core = create_core(0);
+    place(&mut core, 1, 101, SPOT, OrderAction::Ask, 1_000, 1);
+    let cmd = place(&mut core, 2, 201, SPOT, OrderAction::Bid, 1_000, 1);
+    assert_eq!(cmd.matcher_events.len(), 1);
+
+    // Người bán có số dư quote ở giới hạn: tiền bán được cộng bão hòa, không tràn số
+    assert_eq!(available(&core, 1, QUOTE), i64::MAX);
+    assert_eq!(available(&core, 1, BASE), 9);
+    assert_eq!(available(&core, 2, QUOTE), i64::MAX - 1_000);
+    assert_eq!(available(&core, 2, BASE), 11);
+}
+
+#[test]
+fn test_margin_position_at_numeric_limit() {
+    let mut core = create_core(0);
+
+    // Hai lần mở vị thế tại giá cực đại: tổng giá mở vượt i64 và được giữ ở giới hạn
+    for order_id in [1, 2] {
+        place(&mut core, 1, 100 + order_id, FUTURES, OrderAction::Ask, i64::MAX, 1);
+        place(&mut core, 2, 200 + order_id, FUTURES, OrderAction::Bid, i64::MAX, 1);
+    }
+    let long = core.get_user_profile(2).unwrap().positions[&FUTURES].clone();
+    assert_eq!((long.net_volume(), long.open_price_long), (2, i64::MAX));
+    let short = core.get_user_profile(1).unwrap().positions[&FUTURES].clone();
+    assert_eq!((short.net_volume(), short.open_price_short), (-2, i64::MAX));
+
+    // Đóng vị thế tại giá 1: lãi/lỗ thực hiện cực lớn được ghi bão hòa
+    place(&mut core, 1, 103, FUTURES, OrderAction::Bid, 1, 2);
+    let close = place(&mut core, 2, 203, FUTURES, OrderAction::Ask, 1, 2);
+    assert_eq!(close.result_code, CommandResultCode::Success);
+    assert!(core.get_user_profile(1).unwrap().positions.is_empty());
+    assert!(core.get_user_profile(2).unwrap().positions.is_empty());
+    assert_eq!(available(&core, 1, QUOTE), i64::MAX);
+    assert_eq!(core.get_balance(1, QUOTE).unwrap().locked, 0);
+    assert_eq!(core.get_balance(2, QUOTE).unwrap().locked, 0);
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::{book_order, book_spec};
+use matching_core::api::*;
+use matching_core::core::orderbook::{AdvancedOrderBook, OrderBook};
+
+fn create_symbol_spec(tick_size: Price) -> CoreSymbolSpecification {
+    CoreSymbolSpecification { tick_size, ..book_spec() }
+}
+
+fn pegged(order_id: OrderId, action: OrderAction, peg_type: PegType, offset: Price, limit: Option<Price>, size: Size) -> OrderCommand {
+    OrderCommand {
+        peg: Some(Peg { peg_type, offset, limit }),
+        ..book_order(order_id, action, OrderType::Gtc, 0, size)
+    }
+}
+
+fn price_of(book: &AdvancedOrderBook, order_id: OrderId) -> Option<Price> {
+    book.get_order_by_id(order_id).map(|(price, _)| price)
+}
+
+#[test]
+fn test_primary_and_market_peg_follow_bbo() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(1));
+    book.new_order(&mut book_order(1, OrderAction::Ask, OrderType::Gtc, 110, 10));
+    book.new_order(&mut book_order(2, OrderAction::Bid, OrderType::Gtc, 100, 10));
+
+    book.new_order(&mut pegged(10, OrderAction::Bid, PegType::Primary, 0, None, 5));
+    book.new_order(&mut pegged(11, OrderAction::Bid, PegType::Market, 5, Some(103), 5));
+    assert_eq!(price_of(&book, 10), Some(100));
+    assert_eq!(price_of(&book, 11), Some(103));
+
+    // Giá mua tốt nhất tăng: lệnh neo cùng phía đi theo
+    book.new_order(&mut book_order(3, OrderAction::Bid, OrderType::Gtc, 102, 1));
+    assert_eq!(price_of(&book, 10), Some(102));
+
+    // Giá bán tốt nhất giảm: lệnh neo đối ứng đi theo, dưới giá giới hạn
+    book.new_order(&mut book_order(4, OrderAction::Ask, OrderType::Gtc, 107, 1));
+    assert_eq!(price_of(&book, 11), Some(102));
+
+    book.cancel_order(&mut book_order(3, OrderAction::Bid, OrderType::Gtc, 102, 1));
+    book.cancel_order(&mut book_order(4, OrderAction::Ask, OrderType::Gtc, 107, 1));
+    assert_eq!(price_of(&book, 10), Some(100));
+    assert_eq!(price_of(&book, 11), Some(103));
+    assert_eq!(book.get_l2_data(5).bid_prices, vec![103, 100]);
+}
+
+#[test]
+fn test_midpoint_peg_executes_at_half_tick() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(10));
+    book.new_order(&mut book_order(1, OrderAction::Ask, OrderType::Gtc, 110, 10));
+    book.new_order(&mut book_order(2, OrderAction::Bid, OrderType::Gtc, 100, 10));
+
+    // Giá giữa 105 nằm giữa hai bước giá
+    book.new_order(&mut pegged(10, OrderAction::Bid, PegType::Midpoint, 0, None, 5));
+    assert_eq!(price_of(&book, 10), Some(105));
+
+    let mut sell = book_order(20, OrderAction::Ask, OrderType::Ioc, 100, 3);
+    book.new_order(&mut sell);
+    assert_eq!(sell.matcher_events.len(), 1);
+    assert_eq!((sell.matcher_events[0].matched_order_id, sell.matcher_events[0].price), (10, 105));
+
+    // Hai lệnh neo giá giữa khớp với nhau tại giá giữa
+    let mut midpoint_ask = pegged(11, OrderAction::Ask, PegType::Midpoint, 0, None, 4);
+    book.new_order(&mut midpoint_ask);
+    assert_eq!(midpoint_ask.matcher_events[0].size, 2);
+    assert_eq!(midpoint_ask.matcher_events[0].price, 105);
+    assert_eq!(price_of(&book, 11), Some(105));
+    assert_eq!(book.get_total_bid_volume(), 10);
+
+    // Chênh lệch lẻ: làm tròn về phía bị động
+    book.new_order(&mut book_order(3, OrderAction::Ask, OrderType::Gtc, 101, 1));
+    assert_eq!(price_of(&book, 11), Some(101));
+}
+
+#[test]
+fn test_midpoint_rounds_toward_passive_side() {
+    let midpoint = Peg { peg_type: PegType::Midpoint, offset: 0, limit: None };
+
+    // Tổng chẵn: cả hai phía cùng giá giữa
+    assert_eq!(midpoint.price(OrderAction::Bid, Some(100), Some(110)), Some(105));
+    assert_eq!(midpoint.price(OrderAction::Ask, Some(100), Some(110)), Some(105));
+
+    // Tổng lẻ: lệnh mua làm tròn xuống, lệnh bán làm tròn lên
+    assert_eq!(midpoint.price(OrderAction::Bid, Some(100), Some(103)), Some(101));
+    assert_eq!(midpoint.price(OrderAction::Ask, Some(100), Some(103)), Some(102));
+
+    // Chênh lệch một đơn vị giá: mỗi phía đứng tại giá tốt nhất của chính mình, không giao nhau
+    assert_eq!(midpoint.price(OrderAction::Bid, Some(100), Some(101)), Some(100));
+    assert_eq!(midpoint.price(OrderAction::Ask, Some(100), Some(101)), Some(101));
+
+    // Giá âm: làm tròn theo div_euclid (xuống / lên), không phải cắt về 0
+    assert_eq!(midpoint.price(OrderAction::Bid, Some(-3), Some(-2)), Some(-3));
+    assert_eq!(midpoint.price(OrderAction::Ask, Some(-3), Some(-2)), Some(-2));
+}
+
+#[test]
+fn test_midpoint_pegs_on_one_tick_spread_stay_at_own_best() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(1));
+    book.new_order(&mut book_order(1, OrderAction::Ask, OrderType::Gtc, 101, 10));
+    book.new_order(&mut book_order(2, OrderAction::Bid, OrderType::Gtc, 100, 10));
+
+    // Giá giữa 100.5 không biểu diễn được: lệnh mua xuống 100, lệnh bán lên 101
+    book.new_order(&mut pegged(10, OrderAction::Bid, PegType::Midpoint, 0, None, 5));
+    let mut ask = pegged(11, OrderAction::Ask, PegType::Midpoint, 0, None, 5);
+    book.new_order(&mut ask);
+    assert!(ask.matcher_events.is_empty());
+    assert_eq!(price_of(&book, 10), Some(100));
+    assert_eq!(price_of(&book, 11), Some(101));
+    assert_eq!(book.get_total_bid_volume(), 15);
+    assert_eq!(book.get_total_ask_volume(), 15);
+}
+
+#[test]
+fn test_peg_without_reference_is_rejected_and_never_crosses() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(10));
+    let mut peg = pegged(10, OrderAction::Bid, PegType::Midpoint, 0, None, 5);
+    book.new_order(&mut peg);
+    assert_eq!(peg.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(price_of(&book, 10), None);
+
+    book.new_order(&mut book_order(1, OrderAction::Ask, OrderType::Gtc, 120, 10));
+    book.new_order(&mut book_order(2, OrderAction::Bid, OrderType::Gtc, 100, 10));
+    book.new_order(&mut pegged(11, OrderAction::Bid, PegType::Primary, -10, None, 5));
+    assert_eq!(price_of(&book, 11), Some(110));
+
+    // Giá mua tốt nhất lên 110: lệnh neo dừng dưới giá bán tốt nhất một bước giá
+    book.new_order(&mut book_order(3, OrderAction::Bid, OrderType::Gtc, 110, 1));
+    assert_eq!(price_of(&book, 11), Some(110));
+    assert_eq!(book.get_total_ask_volume(), 10);
+
+    // Lệnh neo không đổi giá nên giữ ưu tiên thời gian trước lệnh 3
+    let mut sell = book_order(21, OrderAction::Ask, OrderType::Ioc, 110, 1);
+    book.new_order(&mut sell);
+    assert_eq!(sell.matcher_events[0].matched_order_id, 11);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{book_order, book_spec};
+use matching_core::api::*;
+use matching_core::core::orderbook::{AdvancedOrderBook, OrderBook};
+
+fn create_symbol_spec(tick_size: Price) -> CoreSymbolSpecification {
+    CoreSymbolSpecification { tick_size, ..book_spec() }
+}
+
+fn sliding(order_id: OrderId, action: OrderAction, price: Price, size: Size) -> OrderCommand {
+    OrderCommand {
+        post_only_reprice: true,
+        ..book_order(order_id, action, OrderType::PostOnly, price, size)
+    }
+}
+
+#[test]
+fn test_post_only_would_take_result_code() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(0));
+    book.new_order(&mut book_order(1, OrderAction::Ask, OrderType::Gtc, 100, 10));
+
+    let mut crossing = book_order(2, OrderAction::Bid, OrderType::PostOnly, 101, 5);
+    assert_eq!(book.new_order(&mut crossing), CommandResultCode::PostOnlyWouldTake);
+    assert_eq!(crossing.matcher_events.len(), 1);
+    assert_eq!(crossing.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(book.get_total_bid_volume(), 0);
+
+    let mut passive = book_order(3, OrderAction::Bid, OrderType::PostOnly, 99, 5);
+    assert_eq!(book.new_order(&mut passive), CommandResultCode::Success);
+    assert!(passive.matcher_events.is_empty());
+}
+
+#[test]
+fn test_post_only_slide_reprices_inside_opposite_best() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(10));
+    book.new_order(&mut book_order(1, OrderAction::Ask, OrderType::Gtc, 120, 10));
+    book.new_order(&mut book_order(2, OrderAction::Bid, OrderType::Gtc, 100, 10));
+
+    // Lệnh mua giao với giá bán 120: dời về 110, báo giá mới trong sự kiện
+    let mut bid = sliding(10, OrderAction::Bid, 150, 5);
+    assert_eq!(book.new_order(&mut bid), CommandResultCode::Success);
+    assert_eq!(bid.matcher_events.len(), 1);
+    let event = &bid.matcher_events[0];
+    assert_eq!(event.event_type, MatcherEventType::PostOnlyReprice);
+    assert_eq!((event.matched_order_id, event.price, event.size), (10, 110, 5));
+    assert_eq!(book.get_order_by_id(10), Some((110, OrderAction::Bid)));
+    assert_eq!(book.get_total_ask_volume(), 10);
+
+    // Lệnh bán giao với giá mua 110 mới: dời lên 120
+    let mut ask = sliding(11, OrderAction::Ask, 90, 5);
+    book.new_order(&mut ask);
+    assert_eq!(ask.matcher_events[0].event_type, MatcherEventType::PostOnlyReprice);
+    assert_eq!(book.get_order_by_id(11), Some((120, OrderAction::Ask)));
+
+    // Không giao với phía đối ứng: giữ nguyên giá, không có sự kiện
+    let mut passive = sliding(12, OrderAction::Bid, 90, 5);
+    book.new_order(&mut passive);
+    assert!(passive.matcher_events.is_empty());
+    assert_eq!(book.get_order_by_id(12), Some((90, OrderAction::Bid)));
+    assert_eq!(book.get_l2_data(5).bid_prices, vec![110, 100, 90]);
+}
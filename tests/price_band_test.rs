@@ -0,0 +1,167 @@
+mod common;
+
+use common::add_user;
+use matching_core::api::*;
+use matching_core::core::exchange::ExchangeCore;
+
+const SYMBOL: SymbolId = 1;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+
+fn create_core(spec: CoreSymbolSpecification) -> ExchangeCore {
+    let mut core = common::create_core(&[CoreSymbolSpecification {
+        symbol_id: SYMBOL,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: BASE,
+        quote_currency: QUOTE,
+        ..spec
+    }]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, 1_000_000), (BASE, 1_000)]);
+    }
+    core
+}
+
+fn order(uid: UserId, order_id: OrderId, action: OrderAction, price: Price, size: Size, timestamp: i64) -> OrderCommand {
+    OrderCommand {
+        timestamp,
+        ..common::order(uid, order_id, SYMBOL, action, price, size)
+    }
+}
+
+fn reference_price(price: Price) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::SetReferencePrice,
+        symbol: SYMBOL,
+        price,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_static_band_rejects_orders() {
+    let mut core = create_core(CoreSymbolSpecification {
+        price_band_min: 90,
+        price_band_max: 110,
+        ..Default::default()
+    });
+
+    for (action, price) in [(OrderAction::Bid, 120), (OrderAction::Bid, 80), (OrderAction::Ask, 80), (OrderAction::Ask, 120)] {
+        let cmd = core.submit_command(order(1, 101, action, price, 5, 0));
+        assert_eq!(cmd.result_code, CommandResultCode::PriceOutsideBand, "{action:?} {price}");
+    }
+    assert_eq!(core.get_balance(1, QUOTE).unwrap(), matching_core::core::users::AccountBalance { available: 1_000_000, locked: 0 });
+    assert_eq!(core.get_balance(1, BASE).unwrap().locked, 0);
+
+    let cmd = core.submit_command(order(1, 101, OrderAction::Bid, 110, 5, 0));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+
+    // Dời lệnh ra ngoài dải cũng bị từ chối
+    let moved = core.submit_command(OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1,
+        order_id: 101,
+        symbol: SYMBOL,
+        price: 85,
+        ..Default::default()
+    });
+    assert_eq!(moved.result_code, CommandResultCode::PriceOutsideBand);
+}
+
+#[test]
+fn test_dynamic_band_follows_reference_price() {
+    let mut core = create_core(CoreSymbolSpecification {
+        price_band_rate: 500,
+        ..Default::default()
+    });
+
+    // Chưa có giá tham chiếu: dải động chưa áp dụng
+    assert_eq!(core.submit_command(order(2, 201, OrderAction::Ask, 2_000, 1, 0)).result_code, CommandResultCode::Success);
+
+    assert_eq!(core.submit_command(reference_price(1_000)).result_code, CommandResultCode::Success);
+    let cmd = core.submit_command(order(1, 101, OrderAction::Bid, 1_051, 1, 0));
+    assert_eq!(cmd.result_code, CommandResultCode::PriceOutsideBand);
+
+    // Giao dịch ở 1_040 trở thành giá tham chiếu mới: dải [988, 1_092]
+    core.submit_command(order(2, 202, OrderAction::Ask, 1_040, 1, 0));
+    let cmd = core.submit_command(order(1, 102, OrderAction::Bid, 1_050, 1, 0));
+    assert_eq!(cmd.matcher_events[0].price, 1_040);
+    assert_eq!(core.submit_command(order(1, 103, OrderAction::Bid, 987, 1, 0)).result_code, CommandResultCode::PriceOutsideBand);
+    assert_eq!(core.submit_command(order(1, 104, OrderAction::Bid, 988, 1, 0)).result_code, CommandResultCode::Success);
+}
+
+#[test]
+fn test_cap_mode_moves_aggressive_price_to_band() {
+    let mut core = create_core(CoreSymbolSpecification {
+        price_band_rate: 500,
+        price_band_action: PriceBandAction::Cap,
+        tick_size: 10,
+        ..Default::default()
+    });
+    core.submit_command(reference_price(1_000));
+    core.submit_command(order(2, 201, OrderAction::Ask, 1_040, 5, 0));
+    core.submit_command(order(2, 202, OrderAction::Ask, 1_060, 5, 0));
+
+    // Giá mua 1_100 được kéo về biên trên 1_050: chỉ khớp mức 1_040, phần còn lại treo ở 1_050
+    let cmd = core.submit_command(OrderCommand { reserve_price: 1_100, ..order(1, 101, OrderAction::Bid, 1_100, 8, 0) });
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(cmd.price, 1_050);
+    assert_eq!(cmd.matcher_events.len(), 1);
+    assert_eq!((cmd.matcher_events[0].size, cmd.matcher_events[0].price), (5, 1_040));
+    assert_eq!(core.get_balance(1, QUOTE).unwrap().locked, 3 * 1_100);
+    assert_eq!(core.get_balance(1, QUOTE).unwrap().available, 1_000_000 - 5 * 1_040 - 3 * 1_100);
+
+    // Biên dưới theo bước giá: 1_040 - 52 = 988 → 990; giá mua bị động dưới biên vẫn bị từ chối
+    let cmd = core.submit_command(order(1, 102, OrderAction::Bid, 980, 1, 0));
+    assert_eq!(cmd.result_code, CommandResultCode::PriceOutsideBand);
+    let cmd = core.submit_command(order(2, 203, OrderAction::Ask, 900, 1, 0));
+    assert_eq!((cmd.result_code, cmd.price), (CommandResultCode::Success, 990));
+    assert_eq!(cmd.matcher_events[0].price, 1_050);
+}
+
+#[test]
+fn test_circuit_breaker_halts_symbol() {
+    let mut core = create_core(CoreSymbolSpecification {
+        circuit_breaker_rate: 1_000,
+        circuit_breaker_window: 100,
+        circuit_breaker_cooldown: 50,
+        ..Default::default()
+    });
+
+    core.submit_command(order(2, 201, OrderAction::Ask, 100, 1, 0));
+    core.submit_command(order(1, 101, OrderAction::Bid, 100, 1, 0));
+    core.submit_command(order(2, 202, OrderAction::Ask, 109, 1, 5));
+    core.submit_command(order(2, 203, OrderAction::Ask, 115, 1, 5));
+    core.submit_command(order(2, 204, OrderAction::Ask, 120, 1, 5));
+    let within = core.submit_command(order(1, 102, OrderAction::Bid, 109, 1, 5));
+    assert!(within.matcher_events.iter().all(|e| e.event_type == MatcherEventType::Trade));
+
+    // 115 so với 100 trong cửa sổ: vượt 10%, giao dịch kích hoạt vẫn được giữ
+    let trigger = core.submit_command(order(1, 103, OrderAction::Bid, 115, 1, 10));
+    assert_eq!(trigger.result_code, CommandResultCode::Success);
+    let halt = trigger.matcher_events.last().unwrap();
+    assert_eq!((halt.event_type, halt.price, halt.amount), (MatcherEventType::Halt, 115, 60));
+    assert_eq!(core.get_balance(1, BASE).unwrap().available, 1_003);
+
+    // Trong thời gian ngắt mạch: lệnh đặt bị từ chối và hoàn khoản giữ, hủy lệnh vẫn được phép
+    let halted = core.submit_command(order(1, 104, OrderAction::Bid, 120, 1, 20));
+    assert_eq!(halted.result_code, CommandResultCode::CircuitBreakerHalted);
+    assert_eq!(core.get_balance(1, QUOTE).unwrap().locked, 0);
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 2,
+        order_id: 204,
+        symbol: SYMBOL,
+        timestamp: 30,
+        ..Default::default()
+    });
+    assert_eq!(cancel.result_code, CommandResultCode::Success);
+
+    // Hết thời gian tạm dừng: khớp lại bình thường, biến động trước ngắt mạch không kích hoạt lại
+    core.submit_command(order(2, 205, OrderAction::Ask, 112, 1, 60));
+    let resumed = core.submit_command(order(1, 105, OrderAction::Bid, 112, 1, 60));
+    assert_eq!(resumed.result_code, CommandResultCode::Success);
+    assert_eq!(resumed.matcher_events.len(), 1);
+    assert_eq!(resumed.matcher_events[0].event_type, MatcherEventType::Trade);
+    assert!(core.audit().unwrap().is_balanced());
+}
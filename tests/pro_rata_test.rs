@@ -0,0 +1,98 @@
+mod common;
+
+use common::{add_user, balance, fills, order, spot_spec};
+use matching_core::api::*;
+use matching_core::core::orderbook::allocation::allocate;
+use matching_core::core::orderbook::{AdvancedOrderBook, OrderBook};
+
+const SYMBOL: SymbolId = 1;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+
+fn spec(algorithm: MatchingAlgorithm) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        matching_algorithm: algorithm,
+        ..spot_spec(SYMBOL, BASE, QUOTE)
+    }
+}
+
+#[test]
+fn test_allocate_rules() {
+    // FIFO và mức giá khớp hết luôn theo thời gian
+    assert_eq!(allocate(&spec(MatchingAlgorithm::Fifo), 7, &[5, 5]), vec![5, 2]);
+    assert_eq!(allocate(&spec(MatchingAlgorithm::ProRata), 20, &[5, 5]), vec![5, 5]);
+
+    // Chia theo tỷ lệ, lot dư do làm tròn đi theo thời gian
+    let pro_rata = spec(MatchingAlgorithm::ProRata);
+    assert_eq!(allocate(&pro_rata, 10, &[10, 20, 30]), vec![2, 3, 5]);
+    assert_eq!(allocate(&pro_rata, 3, &[10, 10, 10, 10]), vec![3, 0, 0, 0]);
+
+    // Ưu tiên lệnh đầu
+    let top = CoreSymbolSpecification { pro_rata_top_order: true, ..pro_rata.clone() };
+    assert_eq!(allocate(&top, 10, &[4, 20, 20]), vec![4, 3, 3]);
+
+    // Phần nhỏ hơn mức tối thiểu bị bỏ, dồn về lệnh sớm nhất
+    let min = CoreSymbolSpecification { pro_rata_min_allocation: 2, ..pro_rata.clone() };
+    assert_eq!(allocate(&min, 5, &[40, 5, 5]), vec![5, 0, 0]);
+
+    // Giá - thời gian - tỷ lệ: 50% theo thời gian, phần còn lại theo tỷ lệ
+    let mixed = CoreSymbolSpecification {
+        pro_rata_fifo_rate: ALLOCATION_RATE_SCALE / 2,
+        ..spec(MatchingAlgorithm::PriceTimeProRata)
+    };
+    assert_eq!(allocate(&mixed, 8, &[6, 10]), vec![5, 3]);
+}
+
+#[test]
+fn test_direct_book_pro_rata_settles() {
+    let mut core = common::create_core(&[spec(MatchingAlgorithm::ProRata)]);
+    for uid in [1, 2, 3, 4] {
+        add_user(&mut core, uid, &[(QUOTE, 100_000), (BASE, 100)]);
+    }
+
+    core.submit_command(order(1, 11, SYMBOL, OrderAction::Ask, 100, 10));
+    core.submit_command(order(2, 21, SYMBOL, OrderAction::Ask, 100, 30));
+    core.submit_command(order(3, 31, SYMBOL, OrderAction::Ask, 101, 10));
+
+    // Mức 100 không đủ khớp hết: chia 1:3
+    let cmd = core.submit_command(order(4, 41, SYMBOL, OrderAction::Bid, 100, 8));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(fills(&cmd), vec![(11, 2), (21, 6)]);
+
+    // Mức 100 bị khớp hết theo thời gian rồi sang mức 101 (lệnh chủ động còn dư)
+    let cmd = core.submit_command(order(4, 42, SYMBOL, OrderAction::Bid, 101, 35));
+    assert_eq!(fills(&cmd), vec![(11, 8), (21, 24), (31, 3)]);
+
+    assert_eq!(balance(&core, 4, BASE).available, 143);
+    assert_eq!(balance(&core, 1, QUOTE).available, 101_000);
+    assert_eq!(balance(&core, 2, QUOTE).available, 103_000);
+    assert_eq!(balance(&core, 3, BASE).locked, 7);
+
+    // Lệnh còn lại vẫn hủy được
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 3,
+        order_id: 31,
+        symbol: SYMBOL,
+        ..Default::default()
+    });
+    assert_eq!(cancel.result_code, CommandResultCode::Success);
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_advanced_book_pro_rata() {
+    let mut book = AdvancedOrderBook::new(CoreSymbolSpecification {
+        pro_rata_top_order: true,
+        ..spec(MatchingAlgorithm::ProRata)
+    });
+    for (uid, order_id, size) in [(1, 11, 2), (2, 21, 10), (3, 31, 30)] {
+        book.new_order(&mut order(uid, order_id, SYMBOL, OrderAction::Bid, 100, size));
+    }
+
+    let mut ask = order(4, 41, SYMBOL, OrderAction::Ask, 100, 10);
+    book.new_order(&mut ask);
+    assert_eq!(fills(&ask), vec![(11, 2), (21, 2), (31, 6)]);
+    assert_eq!(book.get_total_bid_volume(), 32);
+    assert_eq!(book.get_l2_data(1).bid_volumes, vec![32]);
+}
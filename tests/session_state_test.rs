@@ -0,0 +1,121 @@
+mod common;
+
+use common::{add_user, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::ExchangeCore;
+
+const SYMBOL: SymbolId = 1;
+const BASE: Currency = 1;
+const QUOTE: Currency = 2;
+
+fn create_core() -> ExchangeCore {
+    let mut core = common::create_core(&[spot_spec(SYMBOL, BASE, QUOTE)]);
+    for uid in [1, 2] {
+        add_user(&mut core, uid, &[(QUOTE, 100_000), (BASE, 100)]);
+    }
+    core
+}
+
+fn order(uid: UserId, order_id: OrderId, action: OrderAction, price: Price, size: Size) -> OrderCommand {
+    common::order(uid, order_id, SYMBOL, action, price, size)
+}
+
+fn set_session(core: &mut ExchangeCore, state: TradingSessionState, timestamp: i64) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::SetSessionState,
+        symbol: SYMBOL,
+        session_state: Some(state),
+        timestamp,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_session_transitions_emit_events() {
+    let mut core = create_core();
+    assert_eq!(core.get_session_state(SYMBOL), Some(TradingSessionState::Continuous));
+    assert_eq!(core.get_session_state(99), None);
+
+    let cmd = set_session(&mut core, TradingSessionState::ClosingAuction, 10);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(cmd.matcher_events.len(), 1);
+    let event = &cmd.matcher_events[0];
+    assert_eq!(event.event_type, MatcherEventType::SessionChange(TradingSessionState::ClosingAuction));
+    assert_eq!(event.amount, 10);
+
+    // Không được mở lại khớp liên tục từ phiên đấu giá đóng cửa
+    let invalid = set_session(&mut core, TradingSessionState::Continuous, 20);
+    assert_eq!(invalid.result_code, CommandResultCode::InvalidSessionTransition);
+    assert!(invalid.matcher_events.is_empty());
+    assert_eq!(core.get_session_state(SYMBOL), Some(TradingSessionState::ClosingAuction));
+
+    for state in [TradingSessionState::Closed, TradingSessionState::PreOpen, TradingSessionState::OpeningAuction, TradingSessionState::Continuous] {
+        assert_eq!(set_session(&mut core, state, 30).result_code, CommandResultCode::Success, "{state:?}");
+    }
+    assert_eq!(core.get_session_state(SYMBOL), Some(TradingSessionState::Continuous));
+
+    let missing = core.submit_command(OrderCommand {
+        command: OrderCommandType::SetSessionState,
+        symbol: SYMBOL,
+        ..Default::default()
+    });
+    assert_eq!(missing.result_code, CommandResultCode::InvalidSessionTransition);
+}
+
+#[test]
+fn test_halted_symbol_only_accepts_cancels() {
+    let mut core = create_core();
+    core.submit_command(order(2, 201, OrderAction::Ask, 100, 5));
+    core.submit_command(order(2, 202, OrderAction::Ask, 110, 5));
+    set_session(&mut core, TradingSessionState::Halted, 10);
+
+    // Lệnh đặt bị từ chối và hoàn khoản giữ
+    let cmd = core.submit_command(order(1, 101, OrderAction::Bid, 100, 5));
+    assert_eq!(cmd.result_code, CommandResultCode::SessionCommandNotAllowed);
+    assert_eq!(core.get_balance(1, QUOTE).unwrap().available, 100_000);
+    assert_eq!(core.get_balance(1, QUOTE).unwrap().locked, 0);
+
+    let moved = core.submit_command(OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 2,
+        order_id: 201,
+        symbol: SYMBOL,
+        price: 90,
+        ..Default::default()
+    });
+    assert_eq!(moved.result_code, CommandResultCode::SessionCommandNotAllowed);
+
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 2,
+        order_id: 202,
+        symbol: SYMBOL,
+        ..Default::default()
+    });
+    assert_eq!(cancel.result_code, CommandResultCode::Success);
+    assert_eq!(core.get_balance(2, BASE).unwrap().locked, 5);
+
+    // Mở lại: lệnh đang treo được khớp bình thường
+    set_session(&mut core, TradingSessionState::Continuous, 20);
+    let cmd = core.submit_command(order(1, 101, OrderAction::Bid, 100, 5));
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(cmd.matcher_events[0].matched_order_id, 201);
+    assert!(core.audit().unwrap().is_balanced());
+}
+
+#[test]
+fn test_session_state_survives_snapshot() {
+    let dir = std::env::temp_dir().join(format!("session_snapshot_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut core = create_core();
+    set_session(&mut core, TradingSessionState::Closed, 10);
+    core.enable_snapshotting(&dir).unwrap();
+    core.take_snapshot(1).unwrap();
+
+    let store = matching_core::core::snapshot::SnapshotStore::new(&dir).unwrap();
+    let restored = ExchangeCore::from_state(store.load_snapshot(1).unwrap());
+    assert_eq!(restored.get_session_state(SYMBOL), Some(TradingSessionState::Closed));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
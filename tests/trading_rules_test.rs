@@ -0,0 +1,99 @@
+mod common;
+
+use common::{add_user, balance, order, spot_spec};
+use matching_core::api::*;
+use matching_core::core::exchange::ExchangeCore;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+
+const SYMBOL: SymbolId = 1;
+const QUOTE: Currency = 2;
+
+fn create_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        tick_size: 5,
+        lot_size: 10,
+        min_size: 20,
+        max_size: 1_000,
+        min_notional: 5_000,
+        ..spot_spec(SYMBOL, 1, QUOTE)
+    }
+}
+
+fn create_core() -> ExchangeCore {
+    let mut core = common::create_core(&[create_spec()]);
+    add_user(&mut core, 1, &[(QUOTE, 1_000_000)]);
+    core
+}
+
+fn bid(price: Price, size: Size) -> OrderCommand {
+    order(1, 101, SYMBOL, OrderAction::Bid, price, size)
+}
+
+#[test]
+fn test_order_rules_rejected_by_risk() {
+    let mut core = create_core();
+
+    let cases = [
+        (250, 0, CommandResultCode::MatchingInvalidOrderSize),
+        (250, -20, CommandResultCode::MatchingInvalidOrderSize),
+        (251, 20, CommandResultCode::InvalidPriceTick),
+        (250, 25, CommandResultCode::InvalidLotSize),
+        (250, 10, CommandResultCode::OrderSizeBelowMinimum),
+        (250, 1_010, CommandResultCode::OrderSizeAboveMaximum),
+        (100, 20, CommandResultCode::OrderNotionalBelowMinimum),
+    ];
+    for (price, size, expected) in cases {
+        let cmd = core.submit_command(bid(price, size));
+        assert_eq!(cmd.result_code, expected, "price {price}, size {size}");
+        assert!(cmd.matcher_events.is_empty());
+    }
+    assert_eq!(balance(&core, 1, QUOTE).available, 1_000_000);
+
+    let accepted = core.submit_command(bid(250, 20));
+    assert_eq!(accepted.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1, QUOTE).locked, 5_000);
+}
+
+#[test]
+fn test_move_order_checks_tick_size() {
+    let mut core = create_core();
+    core.submit_command(bid(250, 20));
+
+    let moved = core.submit_command(OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1,
+        order_id: 101,
+        symbol: SYMBOL,
+        price: 247,
+        ..Default::default()
+    });
+    assert_eq!(moved.result_code, CommandResultCode::InvalidPriceTick);
+
+    let moved = core.submit_command(OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1,
+        order_id: 101,
+        symbol: SYMBOL,
+        price: 245,
+        ..Default::default()
+    });
+    assert_eq!(moved.result_code, CommandResultCode::Success);
+}
+
+#[test]
+fn test_matching_engine_validates_rules() {
+    let mut engine = MatchingEngineRouter::new(0, 1);
+    engine.add_symbol(create_spec());
+
+    // Lệnh đã qua engine rủi ro nhưng vi phạm quy tắc: từ chối toàn bộ để hoàn phần đã giữ
+    let mut cmd = OrderCommand {
+        result_code: CommandResultCode::ValidForMatchingEngine,
+        ..bid(250, 15)
+    };
+    engine.process_order(&mut cmd);
+    assert_eq!(cmd.result_code, CommandResultCode::InvalidLotSize);
+    assert_eq!(cmd.matcher_events.len(), 1);
+    assert_eq!(cmd.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(cmd.matcher_events[0].size, 15);
+    assert!(engine.get_resting_orders().is_empty());
+}
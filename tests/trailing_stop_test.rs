@@ -0,0 +1,85 @@
+mod common;
+
+use common::{book_order, book_spec};
+use matching_core::api::*;
+use matching_core::core::orderbook::{AdvancedOrderBook, OrderBook, OrderBookState};
+
+/// Tạo một giao dịch tại giá cho trước: treo lệnh bán rồi mua IOC khớp ngay
+fn trade_at(book: &mut AdvancedOrderBook, order_id: OrderId, price: Price) {
+    book.new_order(&mut book_order(order_id, OrderAction::Ask, OrderType::Gtc, price, 1));
+    let mut taker = book_order(order_id + 1, OrderAction::Bid, OrderType::Ioc, price, 1);
+    book.new_order(&mut taker);
+    assert_eq!(taker.matcher_events[0].price, price);
+}
+
+#[test]
+fn test_trailing_stop_market_ratchets_and_fires() {
+    let mut book = AdvancedOrderBook::new(book_spec());
+    trade_at(&mut book, 1, 100);
+
+    let mut stop = OrderCommand {
+        trailing_offset: Some(TrailingOffset::Fixed(5)),
+        ..book_order(50, OrderAction::Ask, OrderType::StopMarket, 1, 3)
+    };
+    book.new_order(&mut stop);
+    assert!(stop.matcher_events.is_empty());
+    assert_eq!(book.get_stop_order(50), Some((95, 1)));
+
+    // Giá tăng kéo giá kích hoạt lên, giá giảm không kéo xuống
+    trade_at(&mut book, 3, 110);
+    assert_eq!(book.get_stop_order(50), Some((105, 11)));
+    trade_at(&mut book, 5, 108);
+    assert_eq!(book.get_stop_order(50), Some((105, 11)));
+
+    // Giá chạm 105: kích hoạt thành lệnh bán IOC, khớp vào lệnh mua 104
+    book.new_order(&mut book_order(7, OrderAction::Bid, OrderType::Gtc, 104, 10));
+    trade_at(&mut book, 8, 105);
+    assert_eq!(book.get_stop_order(50), None);
+    assert_eq!(book.get_total_bid_volume(), 7);
+    assert_eq!(book.get_total_ask_volume(), 0);
+}
+
+#[test]
+fn test_trailing_stop_limit_survives_snapshot() {
+    let mut book = AdvancedOrderBook::new(book_spec());
+    book.new_order(&mut book_order(1, OrderAction::Ask, OrderType::Gtc, 200, 1));
+    book.new_order(&mut book_order(2, OrderAction::Ask, OrderType::Gtc, 205, 10));
+
+    // Chưa có giao dịch: giá tham chiếu là giá bán tốt nhất, kích hoạt cách 10%
+    let mut stop = OrderCommand {
+        trailing_offset: Some(TrailingOffset::Rate(TRAILING_RATE_SCALE / 10)),
+        ..book_order(50, OrderAction::Bid, OrderType::StopLimit, 230, 5)
+    };
+    book.new_order(&mut stop);
+    assert_eq!(book.get_stop_order(50), Some((220, 230)));
+
+    // Giá giảm kéo giá kích hoạt xuống, giá giới hạn giữ khoảng cách
+    book.new_order(&mut book_order(3, OrderAction::Bid, OrderType::Gtc, 180, 1));
+    book.new_order(&mut book_order(4, OrderAction::Ask, OrderType::Ioc, 180, 1));
+    assert_eq!(book.get_stop_order(50), Some((198, 208)));
+
+    let bytes = bincode::serialize(&book.serialize_state()).unwrap();
+    let OrderBookState::Advanced(mut book) = bincode::deserialize(&bytes).unwrap() else {
+        panic!("sai loại sổ lệnh");
+    };
+    assert_eq!(book.get_stop_order(50), Some((198, 208)));
+
+    // Giá chạm 199: kích hoạt lệnh mua giới hạn 208
+    book.new_order(&mut book_order(5, OrderAction::Bid, OrderType::Gtc, 199, 1));
+    book.new_order(&mut book_order(6, OrderAction::Ask, OrderType::Ioc, 199, 1));
+    assert_eq!(book.get_stop_order(50), None);
+    assert_eq!(book.get_total_ask_volume(), 6);
+    assert_eq!(book.get_total_bid_volume(), 0);
+}
+
+#[test]
+fn test_trailing_stop_without_reference_is_rejected() {
+    let mut book = AdvancedOrderBook::new(book_spec());
+    let mut stop = OrderCommand {
+        trailing_offset: Some(TrailingOffset::Fixed(5)),
+        ..book_order(50, OrderAction::Ask, OrderType::StopMarket, 1, 3)
+    };
+    book.new_order(&mut stop);
+    assert_eq!(stop.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(book.get_stop_order(50), None);
+}